@@ -0,0 +1,112 @@
+// src/keystore.rs
+
+use std::collections::BTreeMap;
+use std::sync::{Arc, RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+use axum::extract::{Path, State};
+use axum::Json;
+use serde::Deserialize;
+use serde_json::json;
+use solana_sdk::signature::{Keypair, Signer};
+
+use crate::{decode_secret, keypair_from_secret, success, ApiError, ApiResult, SecretString};
+
+/// Longest label accepted by /keystore/import.
+const MAX_LABEL_LEN: usize = 64;
+const RESERVED_LABELS: &[&str] = &["import", "list"];
+
+/// Keypairs imported by label, held only in process memory. Labels are
+/// case-sensitive and unique; the map is ordered so /keystore/list is stable.
+#[derive(Clone, Default)]
+pub(crate) struct Keystore(Arc<RwLock<BTreeMap<String, Keypair>>>);
+
+impl Keystore {
+    // A panic while holding the lock can't leave the map half-updated (every
+    // write is a single insert or remove), so a poisoned lock is still usable.
+    pub(crate) fn read(&self) -> RwLockReadGuard<'_, BTreeMap<String, Keypair>> {
+        self.0.read().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn write(&self) -> RwLockWriteGuard<'_, BTreeMap<String, Keypair>> {
+        self.0.write().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Runs `f` with the keypair stored under `label`, or 404s naming it.
+    pub(crate) fn with_keypair<T>(
+        &self,
+        label: &str,
+        f: impl FnOnce(&Keypair) -> Result<T, ApiError>,
+    ) -> Result<T, ApiError> {
+        let keys = self.read();
+        let keypair = keys.get(label).ok_or_else(|| missing_label(label))?;
+        f(keypair)
+    }
+}
+
+fn missing_label(label: &str) -> ApiError {
+    ApiError::not_found(format!("No key with label \"{}\"", label))
+}
+
+#[derive(Deserialize)]
+pub(crate) struct ImportKeyRequest {
+    label: String,
+    secret: SecretString,
+    #[serde(rename = "secretEncoding")]
+    secret_encoding: Option<String>,
+}
+
+pub(crate) async fn import_key(
+    State(keystore): State<Keystore>,
+    Json(req): Json<ImportKeyRequest>,
+) -> ApiResult {
+    if req.label.is_empty() || req.label.len() > MAX_LABEL_LEN {
+        return Err(ApiError::bad_request(format!(
+            "label must be between 1 and {} bytes, got {}",
+            MAX_LABEL_LEN,
+            req.label.len()
+        )));
+    }
+    // Labels are addressed as /keystore/:label, so they can't contain a path
+    // separator or shadow the fixed routes beside it.
+    if req.label.contains('/') || RESERVED_LABELS.contains(&req.label.as_str()) {
+        return Err(ApiError::bad_request(format!(
+            "label \"{}\" is not allowed: labels cannot contain '/' or be one of: {}",
+            req.label,
+            RESERVED_LABELS.join(", ")
+        )));
+    }
+    let bytes = decode_secret("secret", &req.secret, req.secret_encoding.as_deref())?;
+    let (keypair, _) = keypair_from_secret("secret", &bytes)?;
+    let pubkey = keypair.pubkey();
+
+    let mut keys = keystore.write();
+    if keys.contains_key(&req.label) {
+        return Err(ApiError::conflict(format!("A key with label \"{}\" already exists", req.label)));
+    }
+    keys.insert(req.label.clone(), keypair);
+
+    success(json!({
+        "label": req.label,
+        "pubkey": pubkey.to_string()
+    }))
+}
+
+/// Labels and public keys only; secrets never leave the store.
+pub(crate) async fn list_keys(State(keystore): State<Keystore>) -> ApiResult {
+    let keys: Vec<_> = keystore
+        .read()
+        .iter()
+        .map(|(label, keypair)| json!({ "label": label, "pubkey": keypair.pubkey().to_string() }))
+        .collect();
+
+    success(json!({ "keys": keys }))
+}
+
+pub(crate) async fn delete_key(State(keystore): State<Keystore>, Path(label): Path<String>) -> ApiResult {
+    let keypair = keystore.write().remove(&label).ok_or_else(|| missing_label(&label))?;
+
+    success(json!({
+        "label": label,
+        "pubkey": keypair.pubkey().to_string()
+    }))
+}
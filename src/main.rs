@@ -1,23 +1,54 @@
 // src/main.rs
 
+mod keystore;
+mod parsed_instruction;
+mod transaction;
+
 use axum::{
-    routing::{post},
+    body::Bytes,
+    extract::{DefaultBodyLimit, FromRef, State},
+    routing::{delete, get, post},
     Json, Router,
     http::StatusCode,
-    response::IntoResponse,
+    response::{IntoResponse, Response},
 };
-use serde::{Deserialize};
+use serde::{de::DeserializeOwned, Deserialize};
 use serde_json::json;
-use solana_sdk::pubkey::Pubkey;
+use solana_sdk::compute_budget::ComputeBudgetInstruction;
+use solana_sdk::hash::Hash;
+use solana_sdk::instruction::Instruction;
+use solana_sdk::pubkey::{Pubkey, MAX_SEEDS, MAX_SEED_LEN};
+use solana_sdk::rent::Rent;
 use solana_sdk::signature::{Keypair, Signer};
+use solana_sdk::transaction::Transaction;
+use solana_program::address_lookup_table::instruction as lookup_table_instruction;
+use solana_program::address_lookup_table::state::LOOKUP_TABLE_MAX_ADDRESSES;
+use solana_program::bpf_loader_upgradeable;
+use solana_program::nonce::State as NonceState;
+use solana_program::stake::instruction as stake_instruction;
+use solana_program::stake::state::{Authorized, Lockup, StakeAuthorize, StakeStateV2};
 use solana_program::system_instruction;
-use spl_token::instruction as token_instruction;
+use solana_program::program_pack::Pack;
+use spl_associated_token_account::{get_associated_token_address, get_associated_token_address_with_program_id};
+use spl_associated_token_account::instruction as ata_instruction;
+use spl_token_2022::instruction as token_instruction;
+use spl_token::state::{Account as TokenAccount, Mint, Multisig};
+use spl_token_2022::extension::ExtensionType;
+use spl_token_2022::extension::default_account_state::instruction as default_account_state_instruction;
+use spl_token_2022::extension::interest_bearing_mint::instruction as interest_bearing_instruction;
+use spl_token_2022::extension::metadata_pointer::instruction as metadata_pointer_instruction;
+use spl_token_2022::extension::memo_transfer::instruction as memo_transfer_instruction;
+use spl_token_2022::extension::transfer_fee::{
+    instruction as transfer_fee_instruction, TransferFee, MAX_FEE_BASIS_POINTS,
+};
+use spl_token_metadata_interface::instruction as token_metadata_instruction;
 use std::net::SocketAddr;
+use zeroize::Zeroizing;
 use std::str::FromStr;
+use std::sync::{Arc, Mutex};
 use tokio::net::TcpListener;
-use bs58;
 use base64::{Engine as _, engine::general_purpose::STANDARD as BASE64};
-use ed25519_dalek::{SecretKey, PublicKey, Keypair as Ed25519Keypair, Signer as Ed25519Signer};
+use keystore::Keystore;
 
 
 
@@ -27,276 +58,9045 @@ struct CreateTokenRequest {
     mint_authority: String,
     mint: String,
     decimals: u8,
+    #[serde(rename = "freezeAuthority")]
+    freeze_authority: Option<String>,
+    #[serde(rename = "useInitializeMint2", default)]
+    use_initialize_mint2: bool,
+    #[serde(rename = "tokenProgram")]
+    token_program: Option<String>,
+}
+
+/// /token/create, which can also return its instruction as a transaction.
+#[derive(Deserialize)]
+struct CreateTokenOutputRequest {
+    #[serde(flatten)]
+    mint: CreateTokenRequest,
+    #[serde(flatten)]
+    output: TransactionOutputParams,
+    #[serde(rename = "recentBlockhash")]
+    recent_blockhash: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct CreateTokenFullRequest {
+    payer: String,
+    #[serde(flatten)]
+    mint: CreateTokenRequest,
 }
 
 #[derive(Deserialize)]
 struct SendSolRequest {
-    from: String,
+    from: Option<String>,
+    to: String,
+    #[serde(default, deserialize_with = "optional_string_or_u64")]
+    lamports: Option<u64>,
+    #[serde(rename = "amountSol")]
+    amount_sol: Option<String>,
+    memo: Option<String>,
+    #[serde(rename = "fromKeyLabel")]
+    from_key_label: Option<String>,
+    #[serde(rename = "recentBlockhash")]
+    recent_blockhash: Option<String>,
+    #[serde(flatten)]
+    output: TransactionOutputParams,
+}
+
+#[derive(Deserialize)]
+struct SendSolWithSeedRequest {
+    #[serde(rename = "fromBase")]
+    from_base: String,
+    #[serde(rename = "fromSeed")]
+    from_seed: String,
+    #[serde(rename = "fromOwner")]
+    from_owner: String,
+    to: String,
+    #[serde(deserialize_with = "string_or_u64")]
+    lamports: u64,
+}
+
+#[derive(Deserialize)]
+struct SolTransferEntry {
     to: String,
+    #[serde(deserialize_with = "string_or_u64")]
     lamports: u64,
 }
 
+#[derive(Deserialize)]
+struct SendSolBatchRequest {
+    from: String,
+    transfers: Vec<SolTransferEntry>,
+    #[serde(default, rename = "allowDuplicates")]
+    allow_duplicates: bool,
+}
+
 #[derive(Deserialize)]
 struct SendTokenRequest {
     destination: String,
+    /// The token account to debit; defaults to the owner's associated
+    /// token account for the mint.
+    source: Option<String>,
+    mint: String,
+    owner: Option<String>,
+    #[serde(default, deserialize_with = "optional_string_or_u64")]
+    amount: Option<u64>,
+    #[serde(rename = "uiAmount")]
+    ui_amount: Option<String>,
+    decimals: Option<u8>,
+    delegate: Option<String>,
+    #[serde(default)]
+    signers: Vec<String>,
+    #[serde(rename = "tokenProgram")]
+    token_program: Option<String>,
+    #[serde(rename = "ownerKeyLabel")]
+    owner_key_label: Option<String>,
+    #[serde(rename = "recentBlockhash")]
+    recent_blockhash: Option<String>,
+    #[serde(flatten)]
+    output: TransactionOutputParams,
+}
+
+#[derive(Deserialize)]
+struct MintTokenRequest {
+    mint: String,
+    destination: String,
+    authority: String,
+    #[serde(deserialize_with = "string_or_u64")]
+    amount: u64,
+    #[serde(default)]
+    signers: Vec<String>,
+    #[serde(rename = "tokenProgram")]
+    token_program: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct BurnTokenRequest {
+    account: String,
     mint: String,
     owner: String,
+    #[serde(deserialize_with = "string_or_u64")]
     amount: u64,
+    #[serde(default)]
+    signers: Vec<String>,
+    #[serde(rename = "tokenProgram")]
+    token_program: Option<String>,
 }
 
 #[derive(Deserialize)]
-struct SignMessageRequest {
-    message: String,
-    secret: String,
+struct MintTokenCheckedRequest {
+    mint: String,
+    destination: String,
+    authority: String,
+    #[serde(deserialize_with = "string_or_u64")]
+    amount: u64,
+    decimals: u8,
+    #[serde(default)]
+    signers: Vec<String>,
+    #[serde(rename = "tokenProgram")]
+    token_program: Option<String>,
 }
 
+#[derive(Deserialize)]
+struct BurnTokenCheckedRequest {
+    account: String,
+    mint: String,
+    owner: String,
+    #[serde(deserialize_with = "string_or_u64")]
+    amount: u64,
+    decimals: u8,
+    #[serde(default)]
+    signers: Vec<String>,
+    #[serde(rename = "tokenProgram")]
+    token_program: Option<String>,
+}
 
+#[derive(Deserialize)]
+struct InitializeTokenAccountRequest {
+    account: String,
+    mint: String,
+    owner: String,
+    #[serde(rename = "tokenProgram")]
+    token_program: Option<String>,
+}
 
-async fn generate_new_keypair() -> Json<serde_json::Value> {
-    let keypair = Keypair::new();
-    let pubkey = keypair.pubkey().to_string();
-    let secret = bs58::encode(keypair.to_bytes()).into_string();
+#[derive(Deserialize)]
+struct CreateAssociatedTokenAccountRequest {
+    payer: String,
+    owner: String,
+    mint: String,
+    #[serde(rename = "tokenProgram")]
+    token_program: Option<String>,
+}
 
-    Json(json!({
-        "success": true,
-        "data": {
-            "pubkey": pubkey,
-            "secret": secret
-        }
-    }))
+#[derive(Deserialize)]
+struct DeriveAssociatedTokenAccountRequest {
+    owner: String,
+    mint: String,
+    #[serde(rename = "tokenProgram")]
+    token_program: Option<String>,
 }
 
-async fn initialize_token_mint(
-    Json(req): Json<CreateTokenRequest>
-) -> impl IntoResponse {
-    let mint_authority = match Pubkey::from_str(&req.mint_authority) {
-        Ok(pk) => pk,
-        Err(e) => {
-            return (
-                StatusCode::BAD_REQUEST,
-                Json(json!({
-                    "success": false,
-                    "error": format!("Invalid mint_authority: {}", e)
-                }))
-            )
-        }
-    };
+#[derive(Deserialize)]
+struct ApproveRequest {
+    source: String,
+    delegate: String,
+    owner: String,
+    #[serde(deserialize_with = "string_or_u64")]
+    amount: u64,
+    mint: Option<String>,
+    decimals: Option<u8>,
+    #[serde(default)]
+    signers: Vec<String>,
+    #[serde(rename = "tokenProgram")]
+    token_program: Option<String>,
+}
 
-    let mint = match Pubkey::from_str(&req.mint) {
-        Ok(pk) => pk,
-        Err(e) => {
-            return (
-                StatusCode::BAD_REQUEST,
-                Json(json!({
-                    "success": false,
-                    "error": format!("Invalid mint: {}", e)
-                }))
-            )
-        }
-    };
-    let ix = match token_instruction::initialize_mint(
-        &spl_token::id(),
-        &mint,
-        &mint_authority,
-        Some(&mint_authority),
-        req.decimals,
-    ) {
-        Ok(ix) => ix,
-        Err(e) => {
-            return (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(json!({
-                    "success": false,
-                    "error": format!("Failed to build instruction: {}", e)
-                }))
-            )
+#[derive(Deserialize)]
+struct RevokeRequest {
+    source: String,
+    owner: String,
+    #[serde(default)]
+    signers: Vec<String>,
+    #[serde(rename = "tokenProgram")]
+    token_program: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct FreezeAccountRequest {
+    account: String,
+    mint: String,
+    #[serde(rename = "freezeAuthority")]
+    freeze_authority: String,
+    #[serde(default)]
+    signers: Vec<String>,
+    #[serde(rename = "tokenProgram")]
+    token_program: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct CloseAccountRequest {
+    account: String,
+    destination: String,
+    owner: String,
+    #[serde(default)]
+    signers: Vec<String>,
+    #[serde(rename = "tokenProgram")]
+    token_program: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+enum AuthorityTypeParam {
+    MintTokens,
+    FreezeAccount,
+    AccountOwner,
+    CloseAccount,
+}
+
+impl AuthorityTypeParam {
+    const ACCEPTED: &'static [&'static str] =
+        &["mintTokens", "freezeAccount", "accountOwner", "closeAccount"];
+}
+
+impl From<AuthorityTypeParam> for token_instruction::AuthorityType {
+    fn from(param: AuthorityTypeParam) -> Self {
+        match param {
+            AuthorityTypeParam::MintTokens => Self::MintTokens,
+            AuthorityTypeParam::FreezeAccount => Self::FreezeAccount,
+            AuthorityTypeParam::AccountOwner => Self::AccountOwner,
+            AuthorityTypeParam::CloseAccount => Self::CloseAccount,
         }
-    };
+    }
+}
 
-    let accounts: Vec<_> = ix.accounts.iter().map(|meta| {
-        json!({
-            "pubkey": meta.pubkey.to_string(),
-            "is_signer": meta.is_signer,
-            "is_writable": meta.is_writable,
-        })
-    }).collect();
-    let instruction_data = BASE64.encode(&ix.data);
+#[derive(Deserialize)]
+struct SetAuthorityRequest {
+    account: String,
+    #[serde(rename = "currentAuthority")]
+    current_authority: String,
+    #[serde(rename = "authorityType")]
+    authority_type: String,
+    #[serde(rename = "newAuthority")]
+    new_authority: Option<String>,
+    #[serde(default)]
+    signers: Vec<String>,
+    #[serde(rename = "tokenProgram")]
+    token_program: Option<String>,
+}
 
-    (
-        StatusCode::OK,
-        Json(json!({
-            "success": true,
-            "data": {
-                "program_id": ix.program_id.to_string(),
-                "accounts": accounts,
-                "instruction_data": instruction_data
-            }
-        }))
-    )
+#[derive(Deserialize)]
+struct InitializeMultisigRequest {
+    #[serde(rename = "multisigAccount")]
+    multisig_account: String,
+    signers: Vec<String>,
+    m: u8,
+    #[serde(rename = "tokenProgram")]
+    token_program: Option<String>,
 }
 
-async fn transfer_spl_tokens(
-    Json(req): Json<SendTokenRequest>
-) -> impl IntoResponse {
-    let destination = Pubkey::from_str(&req.destination).unwrap_or_else(|_| Pubkey::default());
-    let mint = Pubkey::from_str(&req.mint).unwrap_or_else(|_| Pubkey::default());
-    let owner = Pubkey::from_str(&req.owner).unwrap_or_else(|_| Pubkey::default());
+#[derive(Deserialize)]
+struct SyncNativeRequest {
+    account: String,
+    #[serde(rename = "tokenProgram")]
+    token_program: Option<String>,
+}
 
-    let ix = token_instruction::transfer(
-        &spl_token::id(),
-        &destination,
-        &destination,
-        &owner,
-        &[],
-        req.amount,
-    ).unwrap_or_else(|_| {
-        token_instruction::transfer(
-            &spl_token::id(),
-            &Pubkey::default(),
-            &Pubkey::default(),
-            &Pubkey::default(),
-            &[],
-            0,
-        ).unwrap()
-    });
+#[derive(Deserialize)]
+struct WrapSolRequest {
+    owner: String,
+    payer: String,
+    #[serde(deserialize_with = "string_or_u64")]
+    lamports: u64,
+}
 
-    let accounts: Vec<_> = ix.accounts.iter().map(|meta| {
-        json!({
-            "pubkey": meta.pubkey.to_string(),
-            "isSigner": meta.is_signer,
-        })
-    }).collect();
+#[derive(Deserialize)]
+struct UnwrapSolRequest {
+    owner: String,
+    account: Option<String>,
+}
 
-    let instruction_data = BASE64.encode(&ix.data);
+#[derive(Deserialize)]
+struct InitializeTransferFeeRequest {
+    mint: String,
+    #[serde(rename = "feeBasisPoints")]
+    fee_basis_points: u16,
+    #[serde(rename = "maximumFee")]
+    #[serde(deserialize_with = "string_or_u64")]
+    maximum_fee: u64,
+    #[serde(rename = "transferFeeConfigAuthority")]
+    transfer_fee_config_authority: Option<String>,
+    #[serde(rename = "withdrawWithheldAuthority")]
+    withdraw_withheld_authority: Option<String>,
+}
 
-    (
-        StatusCode::OK,
-        Json(json!({
-            "success": true,
-            "data": {
-                "program_id": ix.program_id.to_string(),
-                "accounts": accounts,
-                "instruction_data": instruction_data
-            }
-        }))
-    )
+#[derive(Deserialize)]
+struct TransferWithFeeRequest {
+    source: String,
+    mint: String,
+    destination: String,
+    owner: String,
+    #[serde(deserialize_with = "string_or_u64")]
+    amount: u64,
+    decimals: u8,
+    #[serde(deserialize_with = "string_or_u64")]
+    fee: u64,
+    #[serde(rename = "feeBasisPoints")]
+    fee_basis_points: Option<u16>,
+    #[serde(default, rename = "maximumFee", deserialize_with = "optional_string_or_u64")]
+    maximum_fee: Option<u64>,
+    #[serde(default)]
+    signers: Vec<String>,
 }
 
+#[derive(Deserialize)]
+struct WithdrawWithheldRequest {
+    mint: String,
+    destination: String,
+    authority: String,
+    #[serde(default)]
+    sources: Vec<String>,
+    #[serde(default)]
+    signers: Vec<String>,
+}
 
-async fn transfer_sol(
-    Json(req): Json<SendSolRequest>
-) -> impl IntoResponse {
-    let from = Pubkey::from_str(&req.from).unwrap_or_else(|_| {
-        Pubkey::default()
-    });
+#[derive(Deserialize)]
+struct InitializeInterestBearingRequest {
+    mint: String,
+    #[serde(rename = "rateAuthority")]
+    rate_authority: Option<String>,
+    rate: i64,
+}
 
-    let to = Pubkey::from_str(&req.to).unwrap_or_else(|_| {
-        Pubkey::default()
-    });
-        
-    let ix = system_instruction::transfer(&from, &to, req.lamports);
+#[derive(Deserialize)]
+struct UpdateInterestRateRequest {
+    mint: String,
+    #[serde(rename = "rateAuthority")]
+    rate_authority: String,
+    rate: i64,
+    #[serde(default)]
+    signers: Vec<String>,
+}
 
-    let accounts: Vec<_> = ix.accounts.iter().map(|meta| {
-        meta.pubkey.to_string()
-    }).collect();
+#[derive(Deserialize)]
+struct MemoTransferRequest {
+    account: String,
+    owner: String,
+    #[serde(default)]
+    signers: Vec<String>,
+}
 
-    let instruction_data = BASE64.encode(&ix.data);
+#[derive(Deserialize)]
+struct InitializeNonTransferableRequest {
+    mint: String,
+    decimals: Option<u8>,
+    #[serde(rename = "mintAuthority")]
+    mint_authority: Option<String>,
+    #[serde(rename = "freezeAuthority")]
+    freeze_authority: Option<String>,
+}
 
-    (
-        StatusCode::OK,
-        Json(json!({
-            "success": true,
-            "data": {
-                "program_id": ix.program_id.to_string(),
-                "accounts": accounts,
-                "instruction_data": instruction_data
-            }
-        }))
-    )
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+enum AccountStateParam {
+    Initialized,
+    Frozen,
 }
 
+impl AccountStateParam {
+    const ACCEPTED: &'static [&'static str] = &["initialized", "frozen"];
+}
 
-async fn sign_message_with_ed25519(
-    Json(req): Json<SignMessageRequest>
-) -> impl IntoResponse {        
-    if req.message.is_empty() || req.secret.is_empty() {
-        return (
-            StatusCode::BAD_REQUEST,
-            Json(json!({
-                "success": false,
-                "error": "Missing required fields"
-            }))
-        );
+impl From<AccountStateParam> for spl_token_2022::state::AccountState {
+    fn from(param: AccountStateParam) -> Self {
+        match param {
+            AccountStateParam::Initialized => Self::Initialized,
+            AccountStateParam::Frozen => Self::Frozen,
+        }
     }
+}
 
-    let secret_bytes = match bs58::decode(&req.secret).into_vec() {
-        Ok(bytes) => bytes,
-        Err(_) => {
-            return (
-                StatusCode::BAD_REQUEST,
-                Json(json!({
-                    "success": false,
-                    "error": "Invalid base58 secret key"
-                }))
-            );
-        }
-    };
-    
-    let secret_key = match SecretKey::from_bytes(&secret_bytes) {
-        Ok(key) => key,
-        Err(_) => {
-            return (
-                StatusCode::BAD_REQUEST,
-                Json(json!({
-                    "success": false,
-                    "error": "Invalid secret key"
-                }))
-            );
+#[derive(Deserialize)]
+struct InitializeDefaultAccountStateRequest {
+    mint: String,
+    state: String,
+}
+
+#[derive(Deserialize)]
+struct UpdateDefaultAccountStateRequest {
+    mint: String,
+    #[serde(rename = "freezeAuthority")]
+    freeze_authority: String,
+    state: String,
+    #[serde(default)]
+    signers: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct InitializePermanentDelegateRequest {
+    mint: String,
+    delegate: String,
+}
+
+#[derive(Deserialize)]
+struct InitializeMetadataPointerRequest {
+    mint: String,
+    authority: Option<String>,
+    #[serde(rename = "metadataAddress")]
+    metadata_address: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct InitializeTokenMetadataRequest {
+    mint: String,
+    #[serde(rename = "mintAuthority")]
+    mint_authority: String,
+    #[serde(rename = "updateAuthority")]
+    update_authority: String,
+    name: String,
+    symbol: String,
+    uri: String,
+    decimals: Option<u8>,
+}
+
+#[derive(Deserialize)]
+struct InitializeMintCloseAuthorityRequest {
+    mint: String,
+    #[serde(rename = "closeAuthority")]
+    close_authority: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct CloseMintRequest {
+    mint: String,
+    destination: String,
+    authority: String,
+    #[serde(default)]
+    signers: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct ConvertAmountRequest {
+    #[serde(rename = "uiAmount")]
+    ui_amount: Option<String>,
+    amount: Option<String>,
+    decimals: u8,
+}
+
+/// Extensions that live on token accounts (as opposed to mints).
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+enum AccountExtensionParam {
+    TransferFeeAmount,
+    ConfidentialTransferAccount,
+    ImmutableOwner,
+    MemoTransfer,
+    CpiGuard,
+    NonTransferableAccount,
+    TransferHookAccount,
+    ConfidentialTransferFeeAmount,
+}
+
+impl AccountExtensionParam {
+    const ACCEPTED: &'static [&'static str] = &[
+        "transferFeeAmount",
+        "confidentialTransferAccount",
+        "immutableOwner",
+        "memoTransfer",
+        "cpiGuard",
+        "nonTransferableAccount",
+        "transferHookAccount",
+        "confidentialTransferFeeAmount",
+    ];
+}
+
+impl From<AccountExtensionParam> for ExtensionType {
+    fn from(param: AccountExtensionParam) -> Self {
+        match param {
+            AccountExtensionParam::TransferFeeAmount => Self::TransferFeeAmount,
+            AccountExtensionParam::ConfidentialTransferAccount => Self::ConfidentialTransferAccount,
+            AccountExtensionParam::ImmutableOwner => Self::ImmutableOwner,
+            AccountExtensionParam::MemoTransfer => Self::MemoTransfer,
+            AccountExtensionParam::CpiGuard => Self::CpiGuard,
+            AccountExtensionParam::NonTransferableAccount => Self::NonTransferableAccount,
+            AccountExtensionParam::TransferHookAccount => Self::TransferHookAccount,
+            AccountExtensionParam::ConfidentialTransferFeeAmount => Self::ConfidentialTransferFeeAmount,
         }
-    };
-    
-    let public_key = PublicKey::from(&secret_key);
-    
-    let keypair = Ed25519Keypair {
-        secret: secret_key,
-        public: public_key,
-    };
-    
-    let signature = keypair.sign(req.message.as_bytes());
-
-    (
-        StatusCode::OK,
-        Json(json!({
-            "success": true,
-            "data": {
-                "signature": BASE64.encode(signature.to_bytes()),
-                "public_key": bs58::encode(public_key.to_bytes()).into_string(),
-                "message": req.message
-            }
-        }))
-    )
+    }
 }
 
+#[derive(Deserialize)]
+struct TokenAccountSizeRequest {
+    #[serde(default)]
+    extensions: Vec<String>,
+}
 
-#[tokio::main]
-async fn main() {
-    let app = Router::new()
-        .route("/keypair", post(generate_new_keypair))
-        .route("//keypair", post(generate_new_keypair))
-        .route("/token/create", post(initialize_token_mint))
-        .route("//token/create", post(initialize_token_mint))
-        .route("/send/sol", post(transfer_sol))
-        .route("//send/sol", post(transfer_sol))
-        .route("/send/token", post(transfer_spl_tokens))
-        .route("//send/token", post(transfer_spl_tokens))
-        .route("/message/sign", post(sign_message_with_ed25519))
-        .route("//message/sign", post(sign_message_with_ed25519));
+#[derive(Deserialize)]
+struct WalletTransferRequest {
+    #[serde(rename = "ownerWallet")]
+    owner_wallet: String,
+    #[serde(rename = "destinationWallet")]
+    destination_wallet: String,
+    mint: String,
+    #[serde(deserialize_with = "string_or_u64")]
+    amount: u64,
+    decimals: Option<u8>,
+    #[serde(rename = "tokenProgram")]
+    token_program: Option<String>,
+}
 
-    let addr = SocketAddr::from(([0, 0, 0, 0], 8080));
-    println!("Listening on {}", addr);
-    
-    let listener = TcpListener::bind(addr).await.unwrap();
-    axum::serve(listener, app)
-        .await
+#[derive(Deserialize)]
+struct CreateAccountRequest {
+    payer: String,
+    #[serde(rename = "newAccount")]
+    new_account: String,
+    #[serde(default, deserialize_with = "optional_string_or_u64")]
+    lamports: Option<u64>,
+    #[serde(deserialize_with = "string_or_u64")]
+    space: u64,
+    owner: String,
+}
+
+#[derive(Deserialize)]
+struct CreateAccountWithSeedRequest {
+    payer: String,
+    base: String,
+    seed: String,
+    #[serde(default, deserialize_with = "optional_string_or_u64")]
+    lamports: Option<u64>,
+    #[serde(deserialize_with = "string_or_u64")]
+    space: u64,
+    owner: String,
+}
+
+#[derive(Deserialize)]
+struct AllocateRequest {
+    account: String,
+    #[serde(deserialize_with = "string_or_u64")]
+    space: u64,
+    base: Option<String>,
+    seed: Option<String>,
+    owner: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct AssignRequest {
+    account: String,
+    owner: String,
+    base: Option<String>,
+    seed: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct CreateNonceRequest {
+    payer: String,
+    #[serde(rename = "nonceAccount")]
+    nonce_account: String,
+    authority: String,
+    #[serde(default, deserialize_with = "optional_string_or_u64")]
+    lamports: Option<u64>,
+}
+
+#[derive(Deserialize)]
+struct AdvanceNonceRequest {
+    #[serde(rename = "nonceAccount")]
+    nonce_account: String,
+    authority: String,
+}
+
+#[derive(Deserialize)]
+struct WithdrawNonceRequest {
+    #[serde(rename = "nonceAccount")]
+    nonce_account: String,
+    authority: String,
+    to: String,
+    #[serde(deserialize_with = "string_or_u64")]
+    lamports: u64,
+}
+
+#[derive(Deserialize)]
+struct AuthorizeNonceRequest {
+    #[serde(rename = "nonceAccount")]
+    nonce_account: String,
+    #[serde(rename = "currentAuthority")]
+    current_authority: String,
+    #[serde(rename = "newAuthority")]
+    new_authority: String,
+}
+
+#[derive(Deserialize)]
+struct AddressWithSeedRequest {
+    base: String,
+    seed: String,
+    owner: String,
+}
+
+#[derive(Deserialize)]
+struct ValidateAddressRequest {
+    address: String,
+}
+
+/// One typed PDA seed, e.g. `{ "string": "metadata" }` or `{ "u64le": 42 }`.
+#[derive(Deserialize)]
+enum PdaSeedParam {
+    #[serde(rename = "string")]
+    String(String),
+    #[serde(rename = "pubkey")]
+    Pubkey(String),
+    #[serde(rename = "bytesBase64")]
+    BytesBase64(String),
+    #[serde(rename = "u64le", deserialize_with = "string_or_u64")]
+    U64Le(u64),
+}
+
+#[derive(Deserialize)]
+struct DerivePdaRequest {
+    #[serde(rename = "programId")]
+    program_id: String,
+    seeds: Vec<PdaSeedParam>,
+    bump: Option<u8>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+enum MessageEncodingParam {
+    Utf8,
+    Base64,
+    Hex,
+}
+
+impl MessageEncodingParam {
+    const ACCEPTED: &'static [&'static str] = &["utf8", "base64", "hex"];
+}
+
+#[derive(Deserialize)]
+struct MemoRequest {
+    message: String,
+    encoding: Option<String>,
+    #[serde(default)]
+    signers: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct ComputeBudgetRequest {
+    #[serde(default, rename = "unitLimit", deserialize_with = "optional_string_or_u64")]
+    unit_limit: Option<u64>,
+    #[serde(default, rename = "microLamports", deserialize_with = "optional_string_or_u64")]
+    micro_lamports: Option<u64>,
+}
+
+#[derive(Deserialize)]
+struct CreateAndDelegateStakeRequest {
+    payer: String,
+    #[serde(rename = "stakeAccount")]
+    stake_account: String,
+    staker: String,
+    withdrawer: String,
+    #[serde(deserialize_with = "string_or_u64")]
+    lamports: u64,
+    #[serde(rename = "voteAccount")]
+    vote_account: String,
+}
+
+#[derive(Deserialize)]
+struct DeactivateStakeRequest {
+    #[serde(rename = "stakeAccount")]
+    stake_account: String,
+    staker: String,
+}
+
+#[derive(Deserialize)]
+struct WithdrawStakeRequest {
+    #[serde(rename = "stakeAccount")]
+    stake_account: String,
+    withdrawer: String,
+    to: String,
+    #[serde(deserialize_with = "string_or_u64")]
+    lamports: u64,
+    custodian: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct SplitStakeRequest {
+    #[serde(rename = "stakeAccount")]
+    stake_account: String,
+    staker: String,
+    #[serde(rename = "newStakeAccount")]
+    new_stake_account: String,
+    #[serde(deserialize_with = "string_or_u64")]
+    lamports: u64,
+}
+
+#[derive(Deserialize)]
+struct MergeStakeRequest {
+    destination: String,
+    source: String,
+    staker: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+enum StakeAuthorizeParam {
+    Staker,
+    Withdrawer,
+}
+
+impl StakeAuthorizeParam {
+    const ACCEPTED: &'static [&'static str] = &["staker", "withdrawer"];
+}
+
+impl From<StakeAuthorizeParam> for StakeAuthorize {
+    fn from(param: StakeAuthorizeParam) -> Self {
+        match param {
+            StakeAuthorizeParam::Staker => Self::Staker,
+            StakeAuthorizeParam::Withdrawer => Self::Withdrawer,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct AuthorizeStakeRequest {
+    #[serde(rename = "stakeAccount")]
+    stake_account: String,
+    #[serde(rename = "currentAuthority")]
+    current_authority: String,
+    #[serde(rename = "newAuthority")]
+    new_authority: String,
+    #[serde(rename = "authorityType")]
+    authority_type: String,
+    custodian: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct CreateLookupTableRequest {
+    authority: String,
+    payer: String,
+    #[serde(rename = "recentSlot", deserialize_with = "string_or_u64")]
+    recent_slot: u64,
+}
+
+#[derive(Deserialize)]
+struct ExtendLookupTableRequest {
+    #[serde(rename = "lookupTable")]
+    lookup_table: String,
+    authority: String,
+    payer: String,
+    addresses: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct DeactivateLookupTableRequest {
+    #[serde(rename = "lookupTable")]
+    lookup_table: String,
+    authority: String,
+}
+
+#[derive(Deserialize)]
+struct CloseLookupTableRequest {
+    #[serde(rename = "lookupTable")]
+    lookup_table: String,
+    authority: String,
+    recipient: String,
+}
+
+#[derive(Deserialize)]
+struct SetUpgradeAuthorityRequest {
+    #[serde(rename = "programId")]
+    program_id: String,
+    #[serde(rename = "currentAuthority")]
+    current_authority: String,
+    #[serde(rename = "newAuthority")]
+    new_authority: Option<String>,
+    #[serde(default, rename = "confirmImmutable")]
+    confirm_immutable: bool,
+}
+
+#[derive(Deserialize)]
+struct Ed25519VerifyInstructionRequest {
+    message: String,
+    encoding: Option<String>,
+    signature: String,
+    #[serde(rename = "publicKey")]
+    public_key: String,
+}
+
+#[derive(Deserialize)]
+struct Secp256k1VerifyInstructionRequest {
+    message: String,
+    signature: String,
+    #[serde(rename = "recoveryId")]
+    recovery_id: u8,
+    #[serde(rename = "ethAddress")]
+    eth_address: String,
+    #[serde(default, rename = "instructionIndex")]
+    instruction_index: u8,
+}
+
+#[derive(Deserialize, Default)]
+struct GenerateKeypairRequest {
+    count: Option<usize>,
+    format: Option<String>,
+    #[serde(alias = "encryptionPassphrase")]
+    passphrase: Option<SecretString>,
+}
+
+#[derive(Deserialize, Clone, Copy)]
+#[serde(rename_all = "camelCase")]
+enum SecretFormatParam {
+    Base58,
+    JsonArray,
+    Hex,
+}
+
+impl SecretFormatParam {
+    const ACCEPTED: &'static [&'static str] = &["base58", "jsonArray", "hex"];
+}
+
+#[derive(Deserialize, Clone, Copy)]
+#[serde(rename_all = "camelCase")]
+enum OutputParam {
+    Instruction,
+    Transaction,
+}
+
+impl OutputParam {
+    const ACCEPTED: &'static [&'static str] = &["instruction", "transaction"];
+}
+
+/// Lets an instruction endpoint return its instructions wrapped in an
+/// unsigned transaction, alongside the usual instruction fields.
+#[derive(Deserialize, Default)]
+struct TransactionOutputParams {
+    output: Option<String>,
+    #[serde(rename = "feePayer")]
+    fee_payer: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+enum SeedEncodingParam {
+    Hex,
+    Base58,
+}
+
+impl SeedEncodingParam {
+    const ACCEPTED: &'static [&'static str] = &["hex", "base58"];
+}
+
+#[derive(Deserialize)]
+struct KeypairFromSeedRequest {
+    seed: SecretString,
+    encoding: Option<String>,
+    format: Option<String>,
+    #[serde(alias = "encryptionPassphrase")]
+    passphrase: Option<SecretString>,
+}
+
+#[derive(Deserialize)]
+struct KeypairFromMnemonicRequest {
+    mnemonic: SecretString,
+    passphrase: Option<SecretString>,
+    #[serde(rename = "derivationPath")]
+    derivation_path: Option<String>,
+    #[serde(rename = "accountIndex")]
+    account_index: Option<u32>,
+    format: Option<String>,
+    /// Encrypts the returned secret; `passphrase` is the BIP39 passphrase.
+    #[serde(rename = "encryptionPassphrase")]
+    encryption_passphrase: Option<SecretString>,
+}
+
+#[derive(Deserialize, Default)]
+struct GenerateMnemonicRequest {
+    #[serde(rename = "wordCount")]
+    word_count: Option<usize>,
+    format: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+enum SecretEncodingParam {
+    Base58,
+    Hex,
+    Base64,
+}
+
+impl SecretEncodingParam {
+    const ACCEPTED: &'static [&'static str] = &["base58", "hex", "base64"];
+}
+
+#[derive(Deserialize)]
+struct VerifyKeypairRequest {
+    secret: SecretString,
+    #[serde(rename = "secretEncoding")]
+    secret_encoding: Option<String>,
+}
+
+#[derive(Deserialize, Clone, Copy)]
+#[serde(rename_all = "camelCase")]
+enum SignatureEncodingParam {
+    Base64,
+    Base58,
+    Hex,
+}
+
+impl SignatureEncodingParam {
+    const ACCEPTED: &'static [&'static str] = &["base64", "base58", "hex"];
+}
+
+#[derive(Deserialize)]
+struct ConvertSignatureRequest {
+    signature: String,
+    encoding: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct VerifyMessageRequest {
+    message: String,
+    encoding: Option<String>,
+    signature: String,
+    pubkey: String,
+}
+
+/// The key that signs a message: a plain secret, an encrypted one with its
+/// passphrase, or a keystore label.
+#[derive(Deserialize)]
+struct MessageSigner {
+    secret: Option<SecretString>,
+    #[serde(rename = "secretEncoding")]
+    secret_encoding: Option<String>,
+    #[serde(rename = "encryptedSecret")]
+    encrypted_secret: Option<String>,
+    passphrase: Option<SecretString>,
+    #[serde(rename = "keyLabel")]
+    key_label: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct SignMessageRequest {
+    message: String,
+    encoding: Option<String>,
+    #[serde(flatten)]
+    signer: MessageSigner,
+    #[serde(rename = "signatureEncoding")]
+    signature_encoding: Option<String>,
+    #[serde(rename = "expectedPubkey")]
+    expected_pubkey: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct SignMessageMultiRequest {
+    message: String,
+    encoding: Option<String>,
+    signers: Vec<MessageSigner>,
+    #[serde(rename = "signatureEncoding")]
+    signature_encoding: Option<String>,
+}
+
+
+/// Deserializes a u64 from either a JSON number or a string of digits, so
+/// clients can send values above 2^53 without losing precision.
+fn string_or_u64<'de, D>(deserializer: D) -> Result<u64, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    struct StringOrU64;
+
+    impl<'de> serde::de::Visitor<'de> for StringOrU64 {
+        type Value = u64;
+
+        fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+            f.write_str("a non-negative integer or a string of decimal digits")
+        }
+
+        fn visit_u64<E: serde::de::Error>(self, value: u64) -> Result<u64, E> {
+            Ok(value)
+        }
+
+        fn visit_i64<E: serde::de::Error>(self, value: i64) -> Result<u64, E> {
+            u64::try_from(value).map_err(|_| E::custom(format!("must not be negative, got {}", value)))
+        }
+
+        fn visit_f64<E: serde::de::Error>(self, value: f64) -> Result<u64, E> {
+            Err(E::custom(format!("must be an integer, got {}", value)))
+        }
+
+        fn visit_str<E: serde::de::Error>(self, value: &str) -> Result<u64, E> {
+            if value.is_empty() || !value.bytes().all(|b| b.is_ascii_digit()) {
+                return Err(E::custom(format!("must contain only decimal digits, got {:?}", value)));
+            }
+            value
+                .parse()
+                .map_err(|_| E::custom(format!("exceeds the maximum of {}", u64::MAX)))
+        }
+    }
+
+    deserializer.deserialize_any(StringOrU64)
+}
+
+fn optional_string_or_u64<'de, D>(deserializer: D) -> Result<Option<u64>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    struct Wrapper(#[serde(deserialize_with = "string_or_u64")] u64);
+
+    Option::<Wrapper>::deserialize(deserializer).map(|value| value.map(|Wrapper(value)| value))
+}
+
+struct ApiError {
+    status: StatusCode,
+    message: String,
+}
+
+impl ApiError {
+    fn bad_request(message: impl Into<String>) -> Self {
+        Self { status: StatusCode::BAD_REQUEST, message: message.into() }
+    }
+
+    fn not_found(message: impl Into<String>) -> Self {
+        Self { status: StatusCode::NOT_FOUND, message: message.into() }
+    }
+
+    fn conflict(message: impl Into<String>) -> Self {
+        Self { status: StatusCode::CONFLICT, message: message.into() }
+    }
+
+    fn payload_too_large(message: impl Into<String>) -> Self {
+        Self { status: StatusCode::PAYLOAD_TOO_LARGE, message: message.into() }
+    }
+
+    fn internal(message: impl Into<String>) -> Self {
+        Self { status: StatusCode::INTERNAL_SERVER_ERROR, message: message.into() }
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        (
+            self.status,
+            Json(json!({
+                "success": false,
+                "error": self.message
+            }))
+        ).into_response()
+    }
+}
+
+type ApiResult = Result<Json<serde_json::Value>, ApiError>;
+
+fn parse_pubkey(field: &str, value: &str) -> Result<Pubkey, ApiError> {
+    Pubkey::from_str(value)
+        .map_err(|e| ApiError::bad_request(format!("Invalid {}: {}", field, e)))
+}
+
+fn parse_optional_pubkey(field: &str, value: Option<&str>) -> Result<Option<Pubkey>, ApiError> {
+    value.map(|value| parse_pubkey(field, value)).transpose()
+}
+
+/// Resolves the optional tokenProgram field: "token" (the default),
+/// "token-2022", or either program's id. The token-2022 builders accept
+/// both program ids, so handlers can use them for classic mints as well.
+fn parse_token_program(value: Option<&str>) -> Result<Pubkey, ApiError> {
+    match value {
+        None | Some("token") => Ok(spl_token::id()),
+        Some("token-2022") => Ok(spl_token_2022::id()),
+        Some(other) => match Pubkey::from_str(other) {
+            Ok(id) if id == spl_token::id() || id == spl_token_2022::id() => Ok(id),
+            _ => Err(ApiError::bad_request(format!(
+                "Invalid tokenProgram: {:?}, expected one of: token, token-2022, {}, {}",
+                other,
+                spl_token::id(),
+                spl_token_2022::id()
+            ))),
+        },
+    }
+}
+
+/// Parses an array of pubkeys, naming the offending index on failure.
+fn parse_pubkey_list(field: &str, values: &[String]) -> Result<Vec<Pubkey>, ApiError> {
+    values
+        .iter()
+        .enumerate()
+        .map(|(i, value)| parse_pubkey(&format!("{}[{}]", field, i), value))
+        .collect()
+}
+
+/// Parses multisig signer pubkeys, capped at spl-token's MAX_SIGNERS. The
+/// builders append each one as a signer meta after the authority.
+fn parse_multisig_signers(values: &[String]) -> Result<Vec<Pubkey>, ApiError> {
+    if values.len() > token_instruction::MAX_SIGNERS {
+        return Err(ApiError::bad_request(format!(
+            "at most {} signers are allowed, got {}",
+            token_instruction::MAX_SIGNERS,
+            values.len()
+        )));
+    }
+    parse_pubkey_list("signers", values)
+}
+
+fn validate_fee_basis_points(fee_basis_points: u16) -> Result<(), ApiError> {
+    if fee_basis_points > MAX_FEE_BASIS_POINTS {
+        return Err(ApiError::bad_request(format!(
+            "feeBasisPoints must be at most {}, got {}",
+            MAX_FEE_BASIS_POINTS,
+            fee_basis_points
+        )));
+    }
+    Ok(())
+}
+
+/// Interest rates are stored as i16 basis points; the request takes a wider
+/// integer so out-of-range values get a 400 instead of a JSON rejection.
+fn parse_interest_rate(rate: i64) -> Result<i16, ApiError> {
+    i16::try_from(rate).map_err(|_| {
+        ApiError::bad_request(format!(
+            "rate must be between {} and {} basis points, got {}",
+            i16::MIN,
+            i16::MAX,
+            rate
+        ))
+    })
+}
+
+/// SOL amounts are lamports at 9 decimals.
+const LAMPORTS_DECIMALS: u8 = 9;
+
+/// Converts a decimal string such as "12.345" into base units using integer
+/// arithmetic only, so values beyond f64 precision convert exactly.
+fn parse_ui_amount(field: &str, value: &str, decimals: u8) -> Result<u64, ApiError> {
+    if value.starts_with('-') {
+        return Err(ApiError::bad_request(format!("{} must not be negative", field)));
+    }
+    let (whole, fraction) = match value.split_once('.') {
+        Some((whole, fraction)) => (whole, fraction),
+        None => (value, ""),
+    };
+    let is_digits = |part: &str| !part.is_empty() && part.bytes().all(|b| b.is_ascii_digit());
+    if !is_digits(whole) || (value.contains('.') && !is_digits(fraction)) {
+        return Err(ApiError::bad_request(format!(
+            "{} must be a decimal string such as \"12.345\", got {:?}",
+            field, value
+        )));
+    }
+    if fraction.len() > usize::from(decimals) {
+        return Err(ApiError::bad_request(format!(
+            "{} has {} fractional digits but only {} decimals are allowed",
+            field,
+            fraction.len(),
+            decimals
+        )));
+    }
+
+    let overflow = || {
+        ApiError::bad_request(format!("{} exceeds the maximum of {} base units", field, u64::MAX))
+    };
+    let scale = 10u64.checked_pow(u32::from(decimals)).ok_or_else(overflow)?;
+    let whole: u64 = whole.parse().map_err(|_| overflow())?;
+    let fraction: u64 = if fraction.is_empty() {
+        0
+    } else {
+        let padded = format!("{:0<width$}", fraction, width = usize::from(decimals));
+        padded.parse().map_err(|_| overflow())?
+    };
+
+    whole
+        .checked_mul(scale)
+        .and_then(|base| base.checked_add(fraction))
+        .ok_or_else(overflow)
+}
+
+/// Formats base units as a decimal string, without trailing fractional zeros.
+fn format_ui_amount(amount: u64, decimals: u8) -> String {
+    if decimals == 0 {
+        return amount.to_string();
+    }
+    let digits = format!("{:0>width$}", amount, width = usize::from(decimals) + 1);
+    let (whole, fraction) = digits.split_at(digits.len() - usize::from(decimals));
+    let fraction = fraction.trim_end_matches('0');
+    if fraction.is_empty() {
+        whole.to_string()
+    } else {
+        format!("{}.{}", whole, fraction)
+    }
+}
+
+fn validate_seed(field: &str, seed: &str) -> Result<(), ApiError> {
+    if seed.len() > MAX_SEED_LEN {
+        return Err(ApiError::bad_request(format!(
+            "{} must be at most {} bytes, got {}",
+            field,
+            MAX_SEED_LEN,
+            seed.len()
+        )));
+    }
+    Ok(())
+}
+
+/// Validates the seed and derives `Pubkey::create_with_seed(base, seed, owner)`.
+fn derive_address_with_seed(
+    base: &Pubkey,
+    seed_field: &str,
+    seed: &str,
+    owner: &Pubkey,
+) -> Result<Pubkey, ApiError> {
+    validate_seed(seed_field, seed)?;
+    Pubkey::create_with_seed(base, seed, owner)
+        .map_err(|e| ApiError::bad_request(format!("Failed to derive address: {}", e)))
+}
+
+/// Resolves the optional `base`/`seed` pair used by the with-seed system
+/// instructions. Both must be supplied together, and `account` must match the
+/// address derived from them.
+fn parse_seed_base(
+    account: &Pubkey,
+    base: Option<&str>,
+    seed: Option<&str>,
+    owner: &Pubkey,
+) -> Result<Option<Pubkey>, ApiError> {
+    match (base, seed) {
+        (None, None) => Ok(None),
+        (Some(base), Some(seed)) => {
+            let base = parse_pubkey("base", base)?;
+            let derived = derive_address_with_seed(&base, "seed", seed, owner)?;
+            if derived != *account {
+                return Err(ApiError::bad_request(format!(
+                    "account does not match address derived from base and seed: {}",
+                    derived
+                )));
+            }
+            Ok(Some(base))
+        }
+        _ => Err(ApiError::bad_request("base and seed must be provided together")),
+    }
+}
+
+fn validate_space(space: u64) -> Result<(), ApiError> {
+    if space > system_instruction::MAX_PERMITTED_DATA_LENGTH {
+        return Err(ApiError::bad_request(format!(
+            "space must be at most {} bytes, got {}",
+            system_instruction::MAX_PERMITTED_DATA_LENGTH,
+            space
+        )));
+    }
+    Ok(())
+}
+
+/// Uses the requested lamports, or the rent-exempt minimum for `space`.
+fn resolve_lamports(lamports: Option<u64>, space: u64) -> u64 {
+    lamports.unwrap_or_else(|| Rent::default().minimum_balance(space as usize))
+}
+
+fn validate_decimals(decimals: u8) -> Result<(), ApiError> {
+    if decimals > 9 {
+        return Err(ApiError::bad_request(format!(
+            "decimals must be between 0 and 9, got {}",
+            decimals
+        )));
+    }
+    Ok(())
+}
+
+/// Parses a string field into a serde enum, listing the accepted values
+/// in the 400 when it doesn't match.
+fn parse_enum<T: DeserializeOwned>(field: &str, value: &str, accepted: &[&str]) -> Result<T, ApiError> {
+    serde_json::from_value(serde_json::Value::String(value.to_string())).map_err(|_| {
+        ApiError::bad_request(format!(
+            "Invalid {}: {:?}, expected one of: {}",
+            field,
+            value,
+            accepted.join(", ")
+        ))
+    })
+}
+
+fn require_nonzero_amount(amount: u64) -> Result<(), ApiError> {
+    if amount == 0 {
+        return Err(ApiError::bad_request("amount must be greater than 0"));
+    }
+    Ok(())
+}
+
+/// Larger memos leave no room in the transaction for anything else.
+const MAX_MEMO_LEN: usize = 566;
+
+fn validate_memo(field: &str, memo: &[u8]) -> Result<(), ApiError> {
+    if memo.len() > MAX_MEMO_LEN {
+        return Err(ApiError::bad_request(format!(
+            "{} must be at most {} bytes, got {}",
+            field,
+            MAX_MEMO_LEN,
+            memo.len()
+        )));
+    }
+    Ok(())
+}
+
+/// Decodes `value` according to an optional `encoding` field, defaulting to
+/// the raw UTF-8 bytes of the string.
+fn decode_message(field: &str, value: &str, encoding: Option<&str>) -> Result<Vec<u8>, ApiError> {
+    let encoding = match encoding {
+        Some(encoding) => parse_enum("encoding", encoding, MessageEncodingParam::ACCEPTED)?,
+        None => MessageEncodingParam::Utf8,
+    };
+    match encoding {
+        MessageEncodingParam::Utf8 => Ok(value.as_bytes().to_vec()),
+        MessageEncodingParam::Base64 => BASE64
+            .decode(value)
+            .map_err(|e| ApiError::bad_request(format!("Invalid {}: {}", field, e))),
+        MessageEncodingParam::Hex => hex::decode(value)
+            .map_err(|e| ApiError::bad_request(format!("Invalid {}: {}", field, e))),
+    }
+}
+
+/// For endpoints whose JSON body is optional: an empty body yields the
+/// defaults, anything else must parse.
+fn parse_optional_body<T: DeserializeOwned + Default>(body: &[u8]) -> Result<T, ApiError> {
+    if body.iter().all(u8::is_ascii_whitespace) {
+        return Ok(T::default());
+    }
+    serde_json::from_slice(body)
+        .map_err(|e| ApiError::bad_request(format!("Invalid request body: {}", e)))
+}
+
+fn require_nonzero_lamports(lamports: u64) -> Result<(), ApiError> {
+    if lamports == 0 {
+        return Err(ApiError::bad_request("lamports must be greater than 0"));
+    }
+    Ok(())
+}
+
+fn success(data: serde_json::Value) -> ApiResult {
+    Ok(Json(json!({
+        "success": true,
+        "data": data
+    })))
+}
+
+/// Serializes an instruction as program_id, accounts with
+/// pubkey/is_signer/is_writable, and base64 instruction_data.
+fn instruction_to_json(ix: &Instruction) -> serde_json::Value {
+    let accounts: Vec<_> = ix.accounts.iter().map(|meta| {
+        json!({
+            "pubkey": meta.pubkey.to_string(),
+            "is_signer": meta.is_signer,
+            "is_writable": meta.is_writable,
+        })
+    }).collect();
+
+    json!({
+        "program_id": ix.program_id.to_string(),
+        "accounts": accounts,
+        "instruction_data": BASE64.encode(&ix.data)
+    })
+}
+
+/// Wraps a single instruction in the success envelope used by /token/create.
+fn instruction_response(ix: &Instruction) -> ApiResult {
+    instruction_response_with(ix, json!({}))
+}
+
+/// Like `instruction_response`, with the fields of `extra` merged into `data`.
+fn instruction_response_with(ix: &Instruction, extra: serde_json::Value) -> ApiResult {
+    let mut data = instruction_to_json(ix);
+    if let (Some(data), serde_json::Value::Object(extra)) = (data.as_object_mut(), extra) {
+        data.extend(extra);
+    }
+
+    success(data)
+}
+
+/// Wraps an ordered instruction sequence as `data.instructions`, with the
+/// fields of `extra` alongside it.
+fn instructions_response(ixs: &[Instruction], extra: serde_json::Value) -> ApiResult {
+    let mut data = json!({
+        "instructions": ixs.iter().map(instruction_to_json).collect::<Vec<_>>()
+    });
+    if let (Some(data), serde_json::Value::Object(extra)) = (data.as_object_mut(), extra) {
+        data.extend(extra);
+    }
+
+    success(data)
+}
+
+fn parse_blockhash(field: &str, value: &str) -> Result<Hash, ApiError> {
+    Hash::from_str(value).map_err(|e| ApiError::bad_request(format!("Invalid {}: {}", field, e)))
+}
+
+/// The fee payer and blockhash for output "transaction", or None for the
+/// default instruction-only response.
+type TransactionOutput = Option<(Pubkey, Hash)>;
+
+fn parse_transaction_output(
+    params: &TransactionOutputParams,
+    recent_blockhash: Option<&str>,
+) -> Result<TransactionOutput, ApiError> {
+    let output = match params.output.as_deref() {
+        Some(output) => parse_enum("output", output, OutputParam::ACCEPTED)?,
+        None => OutputParam::Instruction,
+    };
+    match output {
+        OutputParam::Instruction if params.fee_payer.is_some() => {
+            Err(ApiError::bad_request("feePayer requires output \"transaction\""))
+        }
+        OutputParam::Instruction => Ok(None),
+        OutputParam::Transaction => {
+            let fee_payer = params
+                .fee_payer
+                .as_deref()
+                .ok_or_else(|| ApiError::bad_request("feePayer is required with output \"transaction\""))?;
+            let blockhash = recent_blockhash
+                .ok_or_else(|| ApiError::bad_request("recentBlockhash is required with output \"transaction\""))?;
+            Ok(Some((parse_pubkey("feePayer", fee_payer)?, parse_blockhash("recentBlockhash", blockhash)?)))
+        }
+    }
+}
+
+/// Parses output "transaction" for a handler that can also sign with a
+/// keystore key. The two are exclusive, and recentBlockhash goes to
+/// whichever is in use.
+fn parse_output_or_keystore_signing<'a>(
+    params: &TransactionOutputParams,
+    label_field: &str,
+    label: Option<&'a str>,
+    recent_blockhash: Option<&str>,
+) -> Result<(TransactionOutput, KeystoreSigning<'a>), ApiError> {
+    let output = parse_transaction_output(params, recent_blockhash)?;
+    if output.is_none() {
+        return Ok((None, parse_keystore_signing(label_field, label, recent_blockhash)?));
+    }
+    if label.is_some() {
+        return Err(ApiError::bad_request(format!(
+            "{} already returns a signed transaction and cannot be combined with output \"transaction\"",
+            label_field
+        )));
+    }
+    Ok((output, None))
+}
+
+/// Merges the unsigned transaction for `ixs` into a response's `data`.
+fn extend_with_transaction(
+    data: &mut serde_json::Value,
+    ixs: &[Instruction],
+    output: TransactionOutput,
+) -> Result<(), ApiError> {
+    if let Some((fee_payer, blockhash)) = output {
+        let built = transaction::unsigned_transaction_json(ixs, &fee_payer, blockhash)?;
+        if let (Some(data), serde_json::Value::Object(built)) = (data.as_object_mut(), built) {
+            data.extend(built);
+        }
+    }
+    Ok(())
+}
+
+/// A keystore label and the blockhash to sign with; handlers that can sign
+/// server-side take both or neither.
+type KeystoreSigning<'a> = Option<(&'a str, Hash)>;
+
+fn parse_keystore_signing<'a>(
+    label_field: &str,
+    label: Option<&'a str>,
+    recent_blockhash: Option<&str>,
+) -> Result<KeystoreSigning<'a>, ApiError> {
+    match (label, recent_blockhash) {
+        (Some(label), Some(blockhash)) => Ok(Some((label, parse_blockhash("recentBlockhash", blockhash)?))),
+        (None, None) => Ok(None),
+        (Some(_), None) => Err(ApiError::bad_request(format!("recentBlockhash is required with {}", label_field))),
+        (None, Some(_)) => Err(ApiError::bad_request(format!("recentBlockhash requires {}", label_field))),
+    }
+}
+
+/// Resolves the account a keystore-signed request acts for. An explicit
+/// pubkey is optional but must agree with the stored key.
+fn keystore_signer_pubkey(
+    keystore: &Keystore,
+    label: &str,
+    field: &str,
+    label_field: &str,
+    explicit: Option<&str>,
+) -> Result<Pubkey, ApiError> {
+    let pubkey = keystore.with_keypair(label, |keypair| Ok(keypair.pubkey()))?;
+    if let Some(explicit) = explicit {
+        if parse_pubkey(field, explicit)? != pubkey {
+            return Err(ApiError::bad_request(format!(
+                "{} does not match the key stored under {} \"{}\"",
+                field, label_field, label
+            )));
+        }
+    }
+    Ok(pubkey)
+}
+
+/// Builds a transaction paid for and signed by `signer` alone. Returns the
+/// wire-format base64 that RPC sendTransaction accepts and its signature.
+fn signed_transaction_json(
+    ixs: &[Instruction],
+    signer: &Keypair,
+    recent_blockhash: Hash,
+) -> Result<serde_json::Value, ApiError> {
+    let mut tx = Transaction::new_with_payer(ixs, Some(&signer.pubkey()));
+    tx.try_sign(&[signer], recent_blockhash)
+        .map_err(|e| ApiError::bad_request(format!("Failed to sign transaction: {}", e)))?;
+    let wire = bincode::serialize(&tx)
+        .map_err(|e| ApiError::internal(format!("Failed to serialize transaction: {}", e)))?;
+
+    Ok(json!({
+        "transaction": BASE64.encode(wire),
+        "signature": tx.signatures[0].to_string()
+    }))
+}
+
+/// The `/keypair` response shape: base58 pubkey and the 64-byte secret in
+/// the requested format.
+fn keypair_json(keypair: &Keypair, format: SecretFormatParam) -> serde_json::Value {
+    let bytes = Zeroizing::new(keypair.to_bytes());
+    let secret = match format {
+        SecretFormatParam::Base58 => json!(bs58::encode(&*bytes).into_string()),
+        SecretFormatParam::JsonArray => json!(&bytes[..]),
+        SecretFormatParam::Hex => json!(hex::encode(&bytes[..])),
+    };
+    json!({
+        "pubkey": keypair.pubkey().to_string(),
+        "secret": secret
+    })
+}
+
+fn parse_secret_format(format: Option<&str>) -> Result<SecretFormatParam, ApiError> {
+    match format {
+        Some(format) => parse_enum("format", format, SecretFormatParam::ACCEPTED),
+        None => Ok(SecretFormatParam::Base58),
+    }
+}
+
+/// How a keypair endpoint returns the secret: plain in the chosen format, or
+/// encrypted under a passphrase.
+#[derive(Clone)]
+enum SecretOutput {
+    Plain(SecretFormatParam),
+    Encrypted(Arc<SecretString>),
+}
+
+fn parse_secret_output(
+    format: Option<&str>,
+    passphrase: Option<SecretString>,
+    passphrase_field: &str,
+) -> Result<SecretOutput, ApiError> {
+    match passphrase {
+        Some(_) if format.is_some() => Err(ApiError::bad_request(format!(
+            "format cannot be combined with {}; encrypted secrets have a fixed format",
+            passphrase_field
+        ))),
+        Some(passphrase) if passphrase.is_empty() => {
+            Err(ApiError::bad_request(format!("{} must not be empty", passphrase_field)))
+        }
+        Some(passphrase) => Ok(SecretOutput::Encrypted(Arc::new(passphrase))),
+        None => parse_secret_format(format).map(SecretOutput::Plain),
+    }
+}
+
+fn keypair_output_json(keypair: &Keypair, output: &SecretOutput) -> Result<serde_json::Value, ApiError> {
+    match output {
+        SecretOutput::Plain(format) => Ok(keypair_json(keypair, *format)),
+        SecretOutput::Encrypted(passphrase) => {
+            let bytes = Zeroizing::new(keypair.to_bytes());
+            Ok(json!({
+                "pubkey": keypair.pubkey().to_string(),
+                "secret": encrypt_secret(&*bytes, passphrase)?,
+                "encrypted": true
+            }))
+        }
+    }
+}
+
+/// Runs Argon2-backed work (encrypting or decrypting a secret) on a blocking
+/// thread so a burst of requests can't stall the async workers.
+async fn run_blocking<T: Send + 'static>(
+    f: impl FnOnce() -> Result<T, ApiError> + Send + 'static,
+) -> Result<T, ApiError> {
+    tokio::task::spawn_blocking(f)
+        .await
+        .map_err(|e| ApiError::internal(format!("Key derivation failed: {}", e)))?
+}
+
+/// `keypair_output_json` for a single key, off the async workers when the
+/// secret has to be encrypted.
+async fn keypair_output(keypair: Keypair, output: SecretOutput) -> Result<serde_json::Value, ApiError> {
+    match output {
+        SecretOutput::Plain(format) => Ok(keypair_json(&keypair, format)),
+        output => run_blocking(move || keypair_output_json(&keypair, &output)).await,
+    }
+}
+
+/// Scheme tag leading every encrypted secret:
+/// `argon2id-aes256gcm$m=<KiB>,t=<passes>,p=<lanes>$<salt>$<nonce>$<ciphertext>`
+/// with the last three parts in base64. The parameters travel with the
+/// ciphertext so they can be raised later without breaking old secrets.
+const ENCRYPTED_SECRET_SCHEME: &str = "argon2id-aes256gcm";
+/// OWASP's baseline Argon2id configuration: 19 MiB, 2 passes, 1 lane.
+const ARGON2_MEMORY_KIB: u32 = 19 * 1024;
+const ARGON2_PASSES: u32 = 2;
+const ARGON2_LANES: u32 = 1;
+/// Ceilings on parameters accepted when decrypting, so a crafted string
+/// can't make the server burn arbitrary memory or CPU.
+const ARGON2_MAX_MEMORY_KIB: u32 = 64 * 1024;
+const ARGON2_MAX_PASSES: u32 = 10;
+const ARGON2_MAX_LANES: u32 = 4;
+const ENCRYPTED_SECRET_SALT_LEN: usize = 16;
+const ENCRYPTED_SECRET_NONCE_LEN: usize = 12;
+
+fn derive_encryption_key(
+    passphrase: &str,
+    salt: &[u8],
+    memory_kib: u32,
+    passes: u32,
+    lanes: u32,
+) -> Result<Zeroizing<[u8; 32]>, ApiError> {
+    let params = argon2::Params::new(memory_kib, passes, lanes, Some(32))
+        .map_err(|e| ApiError::bad_request(format!("Invalid encryptedSecret: {}", e)))?;
+    let argon2 = argon2::Argon2::new(argon2::Algorithm::Argon2id, argon2::Version::V0x13, params);
+    let mut key = Zeroizing::new([0u8; 32]);
+    argon2
+        .hash_password_into(passphrase.as_bytes(), salt, &mut *key)
+        .map_err(|e| ApiError::bad_request(format!("Invalid encryptedSecret: {}", e)))?;
+    Ok(key)
+}
+
+fn encrypt_secret(secret: &[u8], passphrase: &str) -> Result<String, ApiError> {
+    use aes_gcm::aead::{Aead, KeyInit};
+    use rand::RngCore;
+
+    let mut salt = [0u8; ENCRYPTED_SECRET_SALT_LEN];
+    let mut nonce = [0u8; ENCRYPTED_SECRET_NONCE_LEN];
+    rand::rngs::OsRng.fill_bytes(&mut salt);
+    rand::rngs::OsRng.fill_bytes(&mut nonce);
+
+    let key = derive_encryption_key(passphrase, &salt, ARGON2_MEMORY_KIB, ARGON2_PASSES, ARGON2_LANES)?;
+    let cipher = aes_gcm::Aes256Gcm::new_from_slice(&*key)
+        .map_err(|_| ApiError::internal("Failed to initialise cipher"))?;
+    let ciphertext = cipher
+        .encrypt(aes_gcm::Nonce::from_slice(&nonce), secret)
+        .map_err(|_| ApiError::internal("Failed to encrypt secret"))?;
+
+    Ok(format!(
+        "{}$m={},t={},p={}${}${}${}",
+        ENCRYPTED_SECRET_SCHEME,
+        ARGON2_MEMORY_KIB,
+        ARGON2_PASSES,
+        ARGON2_LANES,
+        BASE64.encode(salt),
+        BASE64.encode(nonce),
+        BASE64.encode(ciphertext)
+    ))
+}
+
+/// Reverses `encrypt_secret`. Anything structurally wrong with the string is
+/// reported as an invalid encryptedSecret; a well-formed string that fails
+/// authentication means the passphrase is wrong (or the ciphertext was
+/// tampered with, which GCM can't tell apart).
+fn decrypt_secret(encoded: &str, passphrase: &str) -> Result<SecretBytes, ApiError> {
+    use aes_gcm::aead::{Aead, KeyInit};
+
+    let malformed = |reason: &str| ApiError::bad_request(format!("Invalid encryptedSecret: {}", reason));
+    let parts: Vec<&str> = encoded.split('$').collect();
+    let [scheme, params, salt, nonce, ciphertext] = parts[..] else {
+        return Err(malformed("expected 5 '$'-separated parts"));
+    };
+    if scheme != ENCRYPTED_SECRET_SCHEME {
+        return Err(malformed(&format!("unsupported scheme \"{}\"", scheme)));
+    }
+
+    let (mut memory_kib, mut passes, mut lanes) = (None, None, None);
+    for param in params.split(',') {
+        let (name, value) = param.split_once('=').ok_or_else(|| malformed("bad parameter list"))?;
+        let value: u32 = value.parse().map_err(|_| malformed("bad parameter list"))?;
+        match name {
+            "m" => memory_kib = Some(value),
+            "t" => passes = Some(value),
+            "p" => lanes = Some(value),
+            _ => return Err(malformed(&format!("unknown parameter \"{}\"", name))),
+        }
+    }
+    let (Some(memory_kib), Some(passes), Some(lanes)) = (memory_kib, passes, lanes) else {
+        return Err(malformed("parameters m, t and p are required"));
+    };
+    if memory_kib > ARGON2_MAX_MEMORY_KIB || passes > ARGON2_MAX_PASSES || lanes > ARGON2_MAX_LANES {
+        return Err(malformed(&format!(
+            "parameters exceed limits (m<={}, t<={}, p<={})",
+            ARGON2_MAX_MEMORY_KIB, ARGON2_MAX_PASSES, ARGON2_MAX_LANES
+        )));
+    }
+
+    let decode = |name: &str, value: &str| {
+        BASE64.decode(value).map_err(|e| malformed(&format!("{} is not valid base64: {}", name, e)))
+    };
+    let salt = decode("salt", salt)?;
+    let nonce = decode("nonce", nonce)?;
+    let ciphertext = decode("ciphertext", ciphertext)?;
+    if nonce.len() != ENCRYPTED_SECRET_NONCE_LEN {
+        return Err(malformed(&format!(
+            "nonce must be {} bytes, got {}",
+            ENCRYPTED_SECRET_NONCE_LEN,
+            nonce.len()
+        )));
+    }
+
+    let key = derive_encryption_key(passphrase, &salt, memory_kib, passes, lanes)?;
+    let cipher = aes_gcm::Aes256Gcm::new_from_slice(&*key)
+        .map_err(|_| ApiError::internal("Failed to initialise cipher"))?;
+    cipher
+        .decrypt(aes_gcm::Nonce::from_slice(&nonce), ciphertext.as_slice())
+        .map(SecretBytes::new)
+        .map_err(|_| ApiError::bad_request("Wrong passphrase for encryptedSecret"))
+}
+
+/// Upper bound on `count` for a single /keypair request.
+const MAX_KEYPAIR_COUNT: usize = 1000;
+
+/// Decoded secret key material. The buffer is wiped on drop and Debug never
+/// prints it, so it can't leak through logs or lingering heap allocations.
+struct SecretBytes(Zeroizing<Vec<u8>>);
+
+impl SecretBytes {
+    fn new(bytes: Vec<u8>) -> Self {
+        Self(Zeroizing::new(bytes))
+    }
+}
+
+impl std::ops::Deref for SecretBytes {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl std::fmt::Debug for SecretBytes {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "SecretBytes([REDACTED; {} bytes])", self.0.len())
+    }
+}
+
+/// An encoded secret as received in a request body, wiped on drop.
+struct SecretString(Zeroizing<String>);
+
+impl<'de> Deserialize<'de> for SecretString {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer).map(|value| Self(Zeroizing::new(value)))
+    }
+}
+
+impl std::ops::Deref for SecretString {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl std::fmt::Debug for SecretString {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("SecretString([REDACTED])")
+    }
+}
+
+/// Decodes a secret in the declared encoding (base58 by default), naming the
+/// encoding in the error so a hex key sent as base58 is easy to spot.
+fn decode_secret(field: &str, value: &str, encoding: Option<&str>) -> Result<SecretBytes, ApiError> {
+    let encoding = match encoding {
+        Some(encoding) => parse_enum("secretEncoding", encoding, SecretEncodingParam::ACCEPTED)?,
+        None => SecretEncodingParam::Base58,
+    };
+    let (name, decoded) = match encoding {
+        SecretEncodingParam::Base58 => ("base58", bs58::decode(value).into_vec().map_err(|e| e.to_string())),
+        SecretEncodingParam::Hex => ("hex", hex::decode(value).map_err(|e| e.to_string())),
+        SecretEncodingParam::Base64 => ("base64", BASE64.decode(value).map_err(|e| e.to_string())),
+    };
+    decoded
+        .map(SecretBytes::new)
+        .map_err(|e| ApiError::bad_request(format!("Invalid {} ({}): {}", field, name, e)))
+}
+
+/// Builds a keypair from either a 32-byte ed25519 seed or a 64-byte Solana
+/// keypair (seed followed by public key), returning which form was given. The
+/// embedded public key of a 64-byte secret must match the derived one.
+fn keypair_from_secret(field: &str, bytes: &[u8]) -> Result<(Keypair, &'static str), ApiError> {
+    let derive = |seed: &[u8]| {
+        solana_sdk::signer::keypair::keypair_from_seed(seed)
+            .map_err(|e| ApiError::bad_request(format!("Invalid {}: {}", field, e)))
+    };
+    match bytes.len() {
+        32 => Ok((derive(bytes)?, "ed25519_seed")),
+        64 => {
+            let keypair = derive(&bytes[..32])?;
+            if keypair.pubkey().as_ref() != &bytes[32..] {
+                return Err(ApiError::bad_request(format!(
+                    "{} embeds public key {} but its first 32 bytes derive {}",
+                    field,
+                    bs58::encode(&bytes[32..]).into_string(),
+                    keypair.pubkey()
+                )));
+            }
+            Ok((keypair, "solana_keypair"))
+        }
+        len => Err(ApiError::bad_request(format!(
+            "{} must be a 32-byte ed25519 seed or a 64-byte Solana keypair, got {} bytes",
+            field, len
+        ))),
+    }
+}
+
+async fn verify_keypair(Json(req): Json<VerifyKeypairRequest>) -> ApiResult {
+    let bytes = decode_secret("secret", &req.secret, req.secret_encoding.as_deref())?;
+    let (keypair, format) = keypair_from_secret("secret", &bytes)?;
+
+    success(json!({
+        "pubkey": keypair.pubkey().to_string(),
+        "format": format
+    }))
+}
+
+/// Env var that switches /keypair to reproducible output for integration
+/// tests. It holds a u64 seed and only takes effect alongside
+/// KEYPAIR_TEST_SEED_ACK_VAR, so a stray variable can't silently make a
+/// production server hand out predictable keys.
+const KEYPAIR_TEST_SEED_VAR: &str = "KEYPAIR_TEST_SEED";
+const KEYPAIR_TEST_SEED_ACK_VAR: &str = "KEYPAIR_TEST_SEED_INSECURE_ACK";
+
+/// Draws /keypair output from a seeded ChaCha stream, so the Nth keypair a
+/// server generates is the same on every run with the same seed.
+#[derive(Clone)]
+struct TestKeypairSource(Arc<Mutex<rand_chacha::ChaCha20Rng>>);
+
+impl TestKeypairSource {
+    fn new(seed: u64) -> Self {
+        use rand::SeedableRng;
+
+        Self(Arc::new(Mutex::new(rand_chacha::ChaCha20Rng::seed_from_u64(seed))))
+    }
+
+    /// Reads the seed and acknowledgement from their env values. An unset
+    /// seed disables the mode; a seed without the acknowledgement is an error
+    /// the server refuses to start with.
+    fn from_env_values(seed: Option<&str>, ack: Option<&str>) -> Result<Option<Self>, String> {
+        let Some(seed) = seed else {
+            return Ok(None);
+        };
+        if ack != Some("1") {
+            return Err(format!(
+                "{} is set but {} is not \"1\"; deterministic keypairs are predictable and for tests only",
+                KEYPAIR_TEST_SEED_VAR, KEYPAIR_TEST_SEED_ACK_VAR
+            ));
+        }
+        let seed = seed
+            .parse()
+            .map_err(|_| format!("{} must be an unsigned 64-bit integer, got {:?}", KEYPAIR_TEST_SEED_VAR, seed))?;
+        Ok(Some(Self::new(seed)))
+    }
+
+    fn next_keypair(&self) -> Keypair {
+        use rand::RngCore;
+
+        let mut seed = Zeroizing::new([0u8; 32]);
+        self.0.lock().unwrap_or_else(|e| e.into_inner()).fill_bytes(&mut *seed);
+        solana_sdk::signer::keypair::keypair_from_seed(&*seed).expect("a 32-byte seed always yields a keypair")
+    }
+}
+
+/// With an empty body (or count 1) this returns a single keypair as before;
+/// larger counts return data.keypairs, generated across blocking threads.
+/// In deterministic test mode keys are drawn in order and flagged as such.
+async fn generate_new_keypair(
+    State(test_keys): State<Option<TestKeypairSource>>,
+    body: Bytes,
+) -> ApiResult {
+    let req: GenerateKeypairRequest = parse_optional_body(&body)?;
+    let output = parse_secret_output(req.format.as_deref(), req.passphrase, "passphrase")?;
+    let count = req.count.unwrap_or(1);
+    if count == 0 || count > MAX_KEYPAIR_COUNT {
+        return Err(ApiError::bad_request(format!(
+            "count must be between 1 and {}, got {}",
+            MAX_KEYPAIR_COUNT, count
+        )));
+    }
+    if let Some(test_keys) = test_keys {
+        let mut keypairs = run_blocking(move || {
+            (0..count)
+                .map(|_| keypair_output_json(&test_keys.next_keypair(), &output))
+                .collect::<Result<Vec<_>, _>>()
+        })
+        .await?;
+        let mut data = match count {
+            1 => keypairs.remove(0),
+            _ => json!({ "keypairs": keypairs }),
+        };
+        data["deterministic"] = json!(true);
+        return success(data);
+    }
+    if count == 1 {
+        return success(keypair_output(Keypair::new(), output).await?);
+    }
+
+    let workers = std::thread::available_parallelism().map_or(1, |n| n.get()).min(count);
+    let tasks: Vec<_> = (0..workers)
+        .map(|worker| {
+            let share = count / workers + usize::from(worker < count % workers);
+            let output = output.clone();
+            tokio::task::spawn_blocking(move || {
+                (0..share)
+                    .map(|_| keypair_output_json(&Keypair::new(), &output))
+                    .collect::<Result<Vec<_>, _>>()
+            })
+        })
+        .collect();
+
+    let mut keypairs = Vec::with_capacity(count);
+    for task in tasks {
+        let batch = task
+            .await
+            .map_err(|e| ApiError::internal(format!("Keypair generation failed: {}", e)))??;
+        keypairs.extend(batch);
+    }
+
+    success(json!({ "keypairs": keypairs }))
+}
+
+async fn keypair_from_seed(Json(req): Json<KeypairFromSeedRequest>) -> ApiResult {
+    let output = parse_secret_output(req.format.as_deref(), req.passphrase, "passphrase")?;
+    let encoding = match req.encoding.as_deref() {
+        Some(encoding) => parse_enum("encoding", encoding, SeedEncodingParam::ACCEPTED)?,
+        None => SeedEncodingParam::Hex,
+    };
+    let seed = match encoding {
+        SeedEncodingParam::Hex => hex::decode(&*req.seed).map_err(|e| e.to_string()),
+        SeedEncodingParam::Base58 => bs58::decode(&*req.seed).into_vec().map_err(|e| e.to_string()),
+    }
+    .map(SecretBytes::new)
+    .map_err(|e| ApiError::bad_request(format!("Invalid seed: {}", e)))?;
+    if seed.len() != 32 {
+        return Err(ApiError::bad_request(format!("seed must be 32 bytes, got {}", seed.len())));
+    }
+
+    let keypair = solana_sdk::signer::keypair::keypair_from_seed(&seed)
+        .map_err(|e| ApiError::internal(format!("Failed to derive keypair: {}", e)))?;
+
+    success(keypair_output(keypair, output).await?)
+}
+
+fn build_initialize_mint(req: &CreateTokenRequest) -> Result<Instruction, ApiError> {
+    let token_program = parse_token_program(req.token_program.as_deref())?;
+    let mint_authority = parse_pubkey("mint_authority", &req.mint_authority)?;
+    let mint = parse_pubkey("mint", &req.mint)?;
+    let freeze_authority = parse_optional_pubkey("freezeAuthority", req.freeze_authority.as_deref())?;
+
+    // InitializeMint2 drops the rent sysvar account; the data differs only
+    // in the discriminant.
+    let build = if req.use_initialize_mint2 {
+        token_instruction::initialize_mint2
+    } else {
+        token_instruction::initialize_mint
+    };
+
+    build(
+        &token_program,
+        &mint,
+        &mint_authority,
+        freeze_authority.as_ref(),
+        req.decimals,
+    ).map_err(|e| ApiError::internal(format!("Failed to build instruction: {}", e)))
+}
+
+async fn initialize_token_mint(
+    Json(req): Json<CreateTokenOutputRequest>
+) -> ApiResult {
+    let output = parse_transaction_output(&req.output, req.recent_blockhash.as_deref())?;
+    if output.is_none() && req.recent_blockhash.is_some() {
+        return Err(ApiError::bad_request("recentBlockhash requires output \"transaction\""));
+    }
+    let ix = build_initialize_mint(&req.mint)?;
+
+    let mut extra = json!({});
+    extend_with_transaction(&mut extra, std::slice::from_ref(&ix), output)?;
+    instruction_response_with(&ix, extra)
+}
+
+/// Like /token/create, but preceded by the system create_account that
+/// allocates and funds the mint account, so both can go in one transaction.
+async fn initialize_token_mint_full(
+    Json(req): Json<CreateTokenFullRequest>
+) -> ApiResult {
+    let payer = parse_pubkey("payer", &req.payer)?;
+    let initialize_ix = build_initialize_mint(&req.mint)?;
+    let mint = initialize_ix.accounts[0].pubkey;
+
+    let space = Mint::LEN;
+    let lamports = Rent::default().minimum_balance(space);
+    let create_ix = system_instruction::create_account(
+        &payer,
+        &mint,
+        lamports,
+        space as u64,
+        &initialize_ix.program_id,
+    );
+
+    instructions_response(&[create_ix, initialize_ix], json!({
+        "space": space,
+        "lamports": lamports
+    }))
+}
+
+async fn mint_tokens(
+    Json(req): Json<MintTokenRequest>
+) -> ApiResult {
+    let token_program = parse_token_program(req.token_program.as_deref())?;
+    let mint = parse_pubkey("mint", &req.mint)?;
+    let destination = parse_pubkey("destination", &req.destination)?;
+    let authority = parse_pubkey("authority", &req.authority)?;
+    require_nonzero_amount(req.amount)?;
+
+    let signers = parse_multisig_signers(&req.signers)?;
+    let signer_refs: Vec<&Pubkey> = signers.iter().collect();
+
+    let ix = token_instruction::mint_to(
+        &token_program,
+        &mint,
+        &destination,
+        &authority,
+        &signer_refs,
+        req.amount,
+    ).map_err(|e| ApiError::internal(format!("Failed to build instruction: {}", e)))?;
+
+    instruction_response(&ix)
+}
+
+async fn burn_tokens(
+    Json(req): Json<BurnTokenRequest>
+) -> ApiResult {
+    let token_program = parse_token_program(req.token_program.as_deref())?;
+    let account = parse_pubkey("account", &req.account)?;
+    let mint = parse_pubkey("mint", &req.mint)?;
+    let owner = parse_pubkey("owner", &req.owner)?;
+    require_nonzero_amount(req.amount)?;
+
+    let signers = parse_multisig_signers(&req.signers)?;
+    let signer_refs: Vec<&Pubkey> = signers.iter().collect();
+
+    let ix = token_instruction::burn(
+        &token_program,
+        &account,
+        &mint,
+        &owner,
+        &signer_refs,
+        req.amount,
+    ).map_err(|e| ApiError::internal(format!("Failed to build instruction: {}", e)))?;
+
+    instruction_response(&ix)
+}
+
+async fn mint_tokens_checked(
+    Json(req): Json<MintTokenCheckedRequest>
+) -> ApiResult {
+    let token_program = parse_token_program(req.token_program.as_deref())?;
+    let mint = parse_pubkey("mint", &req.mint)?;
+    let destination = parse_pubkey("destination", &req.destination)?;
+    let authority = parse_pubkey("authority", &req.authority)?;
+    require_nonzero_amount(req.amount)?;
+    validate_decimals(req.decimals)?;
+
+    let signers = parse_multisig_signers(&req.signers)?;
+    let signer_refs: Vec<&Pubkey> = signers.iter().collect();
+
+    let ix = token_instruction::mint_to_checked(
+        &token_program,
+        &mint,
+        &destination,
+        &authority,
+        &signer_refs,
+        req.amount,
+        req.decimals,
+    ).map_err(|e| ApiError::internal(format!("Failed to build instruction: {}", e)))?;
+
+    instruction_response(&ix)
+}
+
+async fn burn_tokens_checked(
+    Json(req): Json<BurnTokenCheckedRequest>
+) -> ApiResult {
+    let token_program = parse_token_program(req.token_program.as_deref())?;
+    let account = parse_pubkey("account", &req.account)?;
+    let mint = parse_pubkey("mint", &req.mint)?;
+    let owner = parse_pubkey("owner", &req.owner)?;
+    require_nonzero_amount(req.amount)?;
+    validate_decimals(req.decimals)?;
+
+    let signers = parse_multisig_signers(&req.signers)?;
+    let signer_refs: Vec<&Pubkey> = signers.iter().collect();
+
+    let ix = token_instruction::burn_checked(
+        &token_program,
+        &account,
+        &mint,
+        &owner,
+        &signer_refs,
+        req.amount,
+        req.decimals,
+    ).map_err(|e| ApiError::internal(format!("Failed to build instruction: {}", e)))?;
+
+    instruction_response(&ix)
+}
+
+async fn initialize_token_account(
+    Json(req): Json<InitializeTokenAccountRequest>
+) -> ApiResult {
+    let token_program = parse_token_program(req.token_program.as_deref())?;
+    let account = parse_pubkey("account", &req.account)?;
+    let mint = parse_pubkey("mint", &req.mint)?;
+    let owner = parse_pubkey("owner", &req.owner)?;
+
+    let ix = token_instruction::initialize_account3(
+        &token_program,
+        &account,
+        &mint,
+        &owner,
+    ).map_err(|e| ApiError::internal(format!("Failed to build instruction: {}", e)))?;
+
+    instruction_response_with(&ix, json!({ "space": TokenAccount::LEN }))
+}
+
+async fn create_associated_token_account(
+    Json(req): Json<CreateAssociatedTokenAccountRequest>
+) -> ApiResult {
+    let token_program = parse_token_program(req.token_program.as_deref())?;
+    let payer = parse_pubkey("payer", &req.payer)?;
+    let owner = parse_pubkey("owner", &req.owner)?;
+    let mint = parse_pubkey("mint", &req.mint)?;
+
+    let ata = get_associated_token_address_with_program_id(&owner, &mint, &token_program);
+    let ix = ata_instruction::create_associated_token_account(
+        &payer,
+        &owner,
+        &mint,
+        &token_program,
+    );
+
+    instruction_response_with(&ix, json!({ "associated_token_address": ata.to_string() }))
+}
+
+/// Same as /token/ata/create, but succeeds if the ATA already exists, so
+/// retried transactions don't fail.
+async fn create_associated_token_account_idempotent(
+    Json(req): Json<CreateAssociatedTokenAccountRequest>
+) -> ApiResult {
+    let token_program = parse_token_program(req.token_program.as_deref())?;
+    let payer = parse_pubkey("payer", &req.payer)?;
+    let owner = parse_pubkey("owner", &req.owner)?;
+    let mint = parse_pubkey("mint", &req.mint)?;
+
+    let ata = get_associated_token_address_with_program_id(&owner, &mint, &token_program);
+    let ix = ata_instruction::create_associated_token_account_idempotent(
+        &payer,
+        &owner,
+        &mint,
+        &token_program,
+    );
+
+    instruction_response_with(&ix, json!({ "associated_token_address": ata.to_string() }))
+}
+
+/// Derives the ATA address only. Owners may be off-curve (PDAs): the
+/// derivation is a plain find_program_address over the owner's bytes.
+async fn derive_associated_token_account(
+    Json(req): Json<DeriveAssociatedTokenAccountRequest>
+) -> ApiResult {
+    let owner = parse_pubkey("owner", &req.owner)?;
+    let mint = parse_pubkey("mint", &req.mint)?;
+    let token_program = parse_token_program(req.token_program.as_deref())?;
+
+    let ata = get_associated_token_address_with_program_id(&owner, &mint, &token_program);
+
+    success(json!({
+        "associated_token_address": ata.to_string(),
+        "owner": owner.to_string(),
+        "mint": mint.to_string(),
+        "token_program": token_program.to_string()
+    }))
+}
+
+async fn approve_delegate(
+    Json(req): Json<ApproveRequest>
+) -> ApiResult {
+    let token_program = parse_token_program(req.token_program.as_deref())?;
+    let source = parse_pubkey("source", &req.source)?;
+    let delegate = parse_pubkey("delegate", &req.delegate)?;
+    let owner = parse_pubkey("owner", &req.owner)?;
+    if req.amount == 0 {
+        return Err(ApiError::bad_request(
+            "amount must be greater than 0; use /token/revoke to remove a delegation",
+        ));
+    }
+
+    let signers = parse_multisig_signers(&req.signers)?;
+    let signer_refs: Vec<&Pubkey> = signers.iter().collect();
+
+    let ix = match (&req.mint, req.decimals) {
+        (Some(mint), Some(decimals)) => {
+            let mint = parse_pubkey("mint", mint)?;
+            token_instruction::approve_checked(
+                &token_program,
+                &source,
+                &mint,
+                &delegate,
+                &owner,
+                &signer_refs,
+                req.amount,
+                decimals,
+            )
+        }
+        (Some(_), None) => {
+            return Err(ApiError::bad_request("decimals is required when mint is provided"));
+        }
+        (None, Some(_)) => {
+            return Err(ApiError::bad_request("decimals is only accepted together with mint"));
+        }
+        (None, None) => token_instruction::approve(
+            &token_program,
+            &source,
+            &delegate,
+            &owner,
+            &signer_refs,
+            req.amount,
+        ),
+    }.map_err(|e| ApiError::internal(format!("Failed to build instruction: {}", e)))?;
+
+    instruction_response(&ix)
+}
+
+async fn revoke_delegate(
+    Json(req): Json<RevokeRequest>
+) -> ApiResult {
+    let token_program = parse_token_program(req.token_program.as_deref())?;
+    let source = parse_pubkey("source", &req.source)?;
+    let owner = parse_pubkey("owner", &req.owner)?;
+
+    let signers = parse_multisig_signers(&req.signers)?;
+    let signer_refs: Vec<&Pubkey> = signers.iter().collect();
+
+    let ix = token_instruction::revoke(
+        &token_program,
+        &source,
+        &owner,
+        &signer_refs,
+    ).map_err(|e| ApiError::internal(format!("Failed to build instruction: {}", e)))?;
+
+    instruction_response(&ix)
+}
+
+async fn freeze_token_account(
+    Json(req): Json<FreezeAccountRequest>
+) -> ApiResult {
+    let token_program = parse_token_program(req.token_program.as_deref())?;
+    let account = parse_pubkey("account", &req.account)?;
+    let mint = parse_pubkey("mint", &req.mint)?;
+    let freeze_authority = parse_pubkey("freezeAuthority", &req.freeze_authority)?;
+
+    let signers = parse_multisig_signers(&req.signers)?;
+    let signer_refs: Vec<&Pubkey> = signers.iter().collect();
+
+    let ix = token_instruction::freeze_account(
+        &token_program,
+        &account,
+        &mint,
+        &freeze_authority,
+        &signer_refs,
+    ).map_err(|e| ApiError::internal(format!("Failed to build instruction: {}", e)))?;
+
+    instruction_response(&ix)
+}
+
+async fn thaw_token_account(
+    Json(req): Json<FreezeAccountRequest>
+) -> ApiResult {
+    let token_program = parse_token_program(req.token_program.as_deref())?;
+    let account = parse_pubkey("account", &req.account)?;
+    let mint = parse_pubkey("mint", &req.mint)?;
+    let freeze_authority = parse_pubkey("freezeAuthority", &req.freeze_authority)?;
+
+    let signers = parse_multisig_signers(&req.signers)?;
+    let signer_refs: Vec<&Pubkey> = signers.iter().collect();
+
+    let ix = token_instruction::thaw_account(
+        &token_program,
+        &account,
+        &mint,
+        &freeze_authority,
+        &signer_refs,
+    ).map_err(|e| ApiError::internal(format!("Failed to build instruction: {}", e)))?;
+
+    instruction_response(&ix)
+}
+
+async fn close_token_account(
+    Json(req): Json<CloseAccountRequest>
+) -> ApiResult {
+    let token_program = parse_token_program(req.token_program.as_deref())?;
+    let account = parse_pubkey("account", &req.account)?;
+    let destination = parse_pubkey("destination", &req.destination)?;
+    let owner = parse_pubkey("owner", &req.owner)?;
+    if account == destination {
+        return Err(ApiError::bad_request("destination must differ from the account being closed"));
+    }
+
+    let signers = parse_multisig_signers(&req.signers)?;
+    let signer_refs: Vec<&Pubkey> = signers.iter().collect();
+
+    let ix = token_instruction::close_account(
+        &token_program,
+        &account,
+        &destination,
+        &owner,
+        &signer_refs,
+    ).map_err(|e| ApiError::internal(format!("Failed to build instruction: {}", e)))?;
+
+    instruction_response(&ix)
+}
+
+async fn set_authority(
+    Json(req): Json<SetAuthorityRequest>
+) -> ApiResult {
+    let token_program = parse_token_program(req.token_program.as_deref())?;
+    let account = parse_pubkey("account", &req.account)?;
+    let current_authority = parse_pubkey("currentAuthority", &req.current_authority)?;
+    let authority_type: AuthorityTypeParam =
+        parse_enum("authorityType", &req.authority_type, AuthorityTypeParam::ACCEPTED)?;
+    let new_authority = parse_optional_pubkey("newAuthority", req.new_authority.as_deref())?;
+
+    let signers = parse_multisig_signers(&req.signers)?;
+    let signer_refs: Vec<&Pubkey> = signers.iter().collect();
+
+    let ix = token_instruction::set_authority(
+        &token_program,
+        &account,
+        new_authority.as_ref(),
+        authority_type.into(),
+        &current_authority,
+        &signer_refs,
+    ).map_err(|e| ApiError::internal(format!("Failed to build instruction: {}", e)))?;
+
+    instruction_response(&ix)
+}
+
+async fn initialize_multisig(
+    Json(req): Json<InitializeMultisigRequest>
+) -> ApiResult {
+    let token_program = parse_token_program(req.token_program.as_deref())?;
+    let multisig = parse_pubkey("multisigAccount", &req.multisig_account)?;
+    let signers = parse_multisig_signers(&req.signers)?;
+    if req.m == 0 || usize::from(req.m) > signers.len() {
+        return Err(ApiError::bad_request(format!(
+            "m must be between 1 and the number of signers ({}), got {}",
+            signers.len(),
+            req.m
+        )));
+    }
+
+    let signer_refs: Vec<&Pubkey> = signers.iter().collect();
+    let ix = token_instruction::initialize_multisig2(
+        &token_program,
+        &multisig,
+        &signer_refs,
+        req.m,
+    ).map_err(|e| ApiError::internal(format!("Failed to build instruction: {}", e)))?;
+
+    instruction_response_with(&ix, json!({ "space": Multisig::LEN }))
+}
+
+async fn sync_native(
+    Json(req): Json<SyncNativeRequest>
+) -> ApiResult {
+    let token_program = parse_token_program(req.token_program.as_deref())?;
+    let account = parse_pubkey("account", &req.account)?;
+
+    let ix = token_instruction::sync_native(&token_program, &account)
+        .map_err(|e| ApiError::internal(format!("Failed to build instruction: {}", e)))?;
+
+    let native_mint = if token_program == spl_token_2022::id() {
+        spl_token_2022::native_mint::id()
+    } else {
+        spl_token::native_mint::id()
+    };
+
+    instruction_response_with(&ix, json!({ "native_mint": native_mint.to_string() }))
+}
+
+/// Returns create-ATA, transfer and sync_native in the order the runtime
+/// needs them: the ATA must exist before it can be funded and synced. The
+/// create is idempotent so wrapping again into an existing ATA still works.
+async fn wrap_sol(
+    Json(req): Json<WrapSolRequest>
+) -> ApiResult {
+    let owner = parse_pubkey("owner", &req.owner)?;
+    let payer = parse_pubkey("payer", &req.payer)?;
+    require_nonzero_lamports(req.lamports)?;
+
+    let native_mint = spl_token::native_mint::id();
+    let ata = get_associated_token_address(&owner, &native_mint);
+    let sync_ix = token_instruction::sync_native(&spl_token::id(), &ata)
+        .map_err(|e| ApiError::internal(format!("Failed to build instruction: {}", e)))?;
+
+    let ixs = [
+        ata_instruction::create_associated_token_account_idempotent(&payer, &owner, &native_mint, &spl_token::id()),
+        system_instruction::transfer(&owner, &ata, req.lamports),
+        sync_ix,
+    ];
+
+    instructions_response(&ixs, json!({ "associated_token_address": ata.to_string() }))
+}
+
+async fn unwrap_sol(
+    Json(req): Json<UnwrapSolRequest>
+) -> ApiResult {
+    let owner = parse_pubkey("owner", &req.owner)?;
+    let account = match &req.account {
+        Some(account) => parse_pubkey("account", account)?,
+        None => get_associated_token_address(&owner, &spl_token::native_mint::id()),
+    };
+
+    let ix = token_instruction::close_account(
+        &spl_token::id(),
+        &account,
+        &owner,
+        &owner,
+        &[],
+    ).map_err(|e| ApiError::internal(format!("Failed to build instruction: {}", e)))?;
+
+    instruction_response_with(&ix, json!({ "token_account": account.to_string() }))
+}
+
+async fn initialize_transfer_fee(
+    Json(req): Json<InitializeTransferFeeRequest>
+) -> ApiResult {
+    let mint = parse_pubkey("mint", &req.mint)?;
+    let config_authority = parse_optional_pubkey(
+        "transferFeeConfigAuthority",
+        req.transfer_fee_config_authority.as_deref(),
+    )?;
+    let withdraw_authority = parse_optional_pubkey(
+        "withdrawWithheldAuthority",
+        req.withdraw_withheld_authority.as_deref(),
+    )?;
+    validate_fee_basis_points(req.fee_basis_points)?;
+
+    let ix = transfer_fee_instruction::initialize_transfer_fee_config(
+        &spl_token_2022::id(),
+        &mint,
+        config_authority.as_ref(),
+        withdraw_authority.as_ref(),
+        req.fee_basis_points,
+        req.maximum_fee,
+    ).map_err(|e| ApiError::internal(format!("Failed to build instruction: {}", e)))?;
+
+    instruction_response(&ix)
+}
+
+/// Builds TransferCheckedWithFee. When the mint's fee config is supplied
+/// the expected fee is checked here, since the program rejects a mismatch.
+async fn transfer_with_fee(
+    Json(req): Json<TransferWithFeeRequest>
+) -> ApiResult {
+    let source = parse_pubkey("source", &req.source)?;
+    let mint = parse_pubkey("mint", &req.mint)?;
+    let destination = parse_pubkey("destination", &req.destination)?;
+    let owner = parse_pubkey("owner", &req.owner)?;
+    require_nonzero_amount(req.amount)?;
+    validate_decimals(req.decimals)?;
+
+    match (req.fee_basis_points, req.maximum_fee) {
+        (Some(fee_basis_points), Some(maximum_fee)) => {
+            validate_fee_basis_points(fee_basis_points)?;
+            let config = TransferFee {
+                epoch: 0.into(),
+                maximum_fee: maximum_fee.into(),
+                transfer_fee_basis_points: fee_basis_points.into(),
+            };
+            let expected = config
+                .calculate_fee(req.amount)
+                .ok_or_else(|| ApiError::bad_request("fee calculation overflowed"))?;
+            if expected != req.fee {
+                return Err(ApiError::bad_request(format!(
+                    "fee {} does not match the configured fee of {} for amount {}",
+                    req.fee, expected, req.amount
+                )));
+            }
+        }
+        (None, None) => {}
+        _ => {
+            return Err(ApiError::bad_request(
+                "feeBasisPoints and maximumFee must be provided together",
+            ));
+        }
+    }
+
+    let signers = parse_multisig_signers(&req.signers)?;
+    let signer_refs: Vec<&Pubkey> = signers.iter().collect();
+
+    let ix = transfer_fee_instruction::transfer_checked_with_fee(
+        &spl_token_2022::id(),
+        &source,
+        &mint,
+        &destination,
+        &owner,
+        &signer_refs,
+        req.amount,
+        req.decimals,
+        req.fee,
+    ).map_err(|e| ApiError::internal(format!("Failed to build instruction: {}", e)))?;
+
+    instruction_response(&ix)
+}
+
+/// More source accounts than this can't fit in a single transaction.
+const MAX_WITHHELD_SOURCES: usize = 25;
+
+/// Withdraws withheld fees from the listed token accounts, or from the mint
+/// itself when `sources` is empty.
+async fn withdraw_withheld_tokens(
+    Json(req): Json<WithdrawWithheldRequest>
+) -> ApiResult {
+    let mint = parse_pubkey("mint", &req.mint)?;
+    let destination = parse_pubkey("destination", &req.destination)?;
+    let authority = parse_pubkey("authority", &req.authority)?;
+    if req.sources.len() > MAX_WITHHELD_SOURCES {
+        return Err(ApiError::bad_request(format!(
+            "at most {} sources are allowed per instruction, got {}",
+            MAX_WITHHELD_SOURCES,
+            req.sources.len()
+        )));
+    }
+    let sources = parse_pubkey_list("sources", &req.sources)?;
+    let signers = parse_multisig_signers(&req.signers)?;
+    let signer_refs: Vec<&Pubkey> = signers.iter().collect();
+
+    let ix = if sources.is_empty() {
+        transfer_fee_instruction::withdraw_withheld_tokens_from_mint(
+            &spl_token_2022::id(),
+            &mint,
+            &destination,
+            &authority,
+            &signer_refs,
+        )
+    } else {
+        let source_refs: Vec<&Pubkey> = sources.iter().collect();
+        transfer_fee_instruction::withdraw_withheld_tokens_from_accounts(
+            &spl_token_2022::id(),
+            &mint,
+            &destination,
+            &authority,
+            &signer_refs,
+            &source_refs,
+        )
+    }.map_err(|e| ApiError::internal(format!("Failed to build instruction: {}", e)))?;
+
+    instruction_response(&ix)
+}
+
+async fn initialize_interest_bearing_mint(
+    Json(req): Json<InitializeInterestBearingRequest>
+) -> ApiResult {
+    let mint = parse_pubkey("mint", &req.mint)?;
+    let rate_authority = parse_optional_pubkey("rateAuthority", req.rate_authority.as_deref())?;
+    let rate = parse_interest_rate(req.rate)?;
+
+    let ix = interest_bearing_instruction::initialize(
+        &spl_token_2022::id(),
+        &mint,
+        rate_authority,
+        rate,
+    ).map_err(|e| ApiError::internal(format!("Failed to build instruction: {}", e)))?;
+
+    instruction_response(&ix)
+}
+
+async fn update_interest_rate(
+    Json(req): Json<UpdateInterestRateRequest>
+) -> ApiResult {
+    let mint = parse_pubkey("mint", &req.mint)?;
+    let rate_authority = parse_pubkey("rateAuthority", &req.rate_authority)?;
+    let rate = parse_interest_rate(req.rate)?;
+    let signers = parse_multisig_signers(&req.signers)?;
+    let signer_refs: Vec<&Pubkey> = signers.iter().collect();
+
+    let ix = interest_bearing_instruction::update_rate(
+        &spl_token_2022::id(),
+        &mint,
+        &rate_authority,
+        &signer_refs,
+        rate,
+    ).map_err(|e| ApiError::internal(format!("Failed to build instruction: {}", e)))?;
+
+    instruction_response(&ix)
+}
+
+async fn enable_memo_transfer(
+    Json(req): Json<MemoTransferRequest>
+) -> ApiResult {
+    let account = parse_pubkey("account", &req.account)?;
+    let owner = parse_pubkey("owner", &req.owner)?;
+    let signers = parse_multisig_signers(&req.signers)?;
+    let signer_refs: Vec<&Pubkey> = signers.iter().collect();
+
+    let ix = memo_transfer_instruction::enable_required_transfer_memos(
+        &spl_token_2022::id(),
+        &account,
+        &owner,
+        &signer_refs,
+    ).map_err(|e| ApiError::internal(format!("Failed to build instruction: {}", e)))?;
+
+    instruction_response(&ix)
+}
+
+async fn disable_memo_transfer(
+    Json(req): Json<MemoTransferRequest>
+) -> ApiResult {
+    let account = parse_pubkey("account", &req.account)?;
+    let owner = parse_pubkey("owner", &req.owner)?;
+    let signers = parse_multisig_signers(&req.signers)?;
+    let signer_refs: Vec<&Pubkey> = signers.iter().collect();
+
+    let ix = memo_transfer_instruction::disable_required_transfer_memos(
+        &spl_token_2022::id(),
+        &account,
+        &owner,
+        &signer_refs,
+    ).map_err(|e| ApiError::internal(format!("Failed to build instruction: {}", e)))?;
+
+    instruction_response(&ix)
+}
+
+const MINT_EXTENSION_ORDER_NOTE: &str =
+    "mint extensions must be initialized before InitializeMint, in the same transaction";
+
+/// Returns the NonTransferable extension init on its own, or followed by
+/// InitializeMint when decimals and mintAuthority are supplied.
+async fn initialize_non_transferable_mint(
+    Json(req): Json<InitializeNonTransferableRequest>
+) -> ApiResult {
+    let mint = parse_pubkey("mint", &req.mint)?;
+    let extension_ix = token_instruction::initialize_non_transferable_mint(&spl_token_2022::id(), &mint)
+        .map_err(|e| ApiError::internal(format!("Failed to build instruction: {}", e)))?;
+
+    let (decimals, mint_authority) = match (req.decimals, &req.mint_authority) {
+        (None, None) => {
+            return instruction_response_with(&extension_ix, json!({ "note": MINT_EXTENSION_ORDER_NOTE }));
+        }
+        (Some(decimals), Some(mint_authority)) => (decimals, mint_authority),
+        _ => {
+            return Err(ApiError::bad_request("decimals and mintAuthority must be provided together"));
+        }
+    };
+    validate_decimals(decimals)?;
+    let mint_authority = parse_pubkey("mintAuthority", mint_authority)?;
+    let freeze_authority = parse_optional_pubkey("freezeAuthority", req.freeze_authority.as_deref())?;
+
+    let mint_ix = token_instruction::initialize_mint(
+        &spl_token_2022::id(),
+        &mint,
+        &mint_authority,
+        freeze_authority.as_ref(),
+        decimals,
+    ).map_err(|e| ApiError::internal(format!("Failed to build instruction: {}", e)))?;
+
+    instructions_response(&[extension_ix, mint_ix], json!({ "note": MINT_EXTENSION_ORDER_NOTE }))
+}
+
+async fn initialize_default_account_state(
+    Json(req): Json<InitializeDefaultAccountStateRequest>
+) -> ApiResult {
+    let mint = parse_pubkey("mint", &req.mint)?;
+    let state: AccountStateParam = parse_enum("state", &req.state, AccountStateParam::ACCEPTED)?;
+
+    let ix = default_account_state_instruction::initialize_default_account_state(
+        &spl_token_2022::id(),
+        &mint,
+        &state.into(),
+    ).map_err(|e| ApiError::internal(format!("Failed to build instruction: {}", e)))?;
+
+    instruction_response_with(&ix, json!({ "note": MINT_EXTENSION_ORDER_NOTE }))
+}
+
+async fn update_default_account_state(
+    Json(req): Json<UpdateDefaultAccountStateRequest>
+) -> ApiResult {
+    let mint = parse_pubkey("mint", &req.mint)?;
+    let freeze_authority = parse_pubkey("freezeAuthority", &req.freeze_authority)?;
+    let state: AccountStateParam = parse_enum("state", &req.state, AccountStateParam::ACCEPTED)?;
+    let signers = parse_multisig_signers(&req.signers)?;
+    let signer_refs: Vec<&Pubkey> = signers.iter().collect();
+
+    let ix = default_account_state_instruction::update_default_account_state(
+        &spl_token_2022::id(),
+        &mint,
+        &freeze_authority,
+        &signer_refs,
+        &state.into(),
+    ).map_err(|e| ApiError::internal(format!("Failed to build instruction: {}", e)))?;
+
+    instruction_response(&ix)
+}
+
+const PERMANENT_DELEGATE_WARNING: &str =
+    "the permanent delegate can transfer or burn any amount from every token account of this mint";
+
+async fn initialize_permanent_delegate(
+    Json(req): Json<InitializePermanentDelegateRequest>
+) -> ApiResult {
+    let mint = parse_pubkey("mint", &req.mint)?;
+    let delegate = parse_pubkey("delegate", &req.delegate)?;
+
+    let ix = token_instruction::initialize_permanent_delegate(&spl_token_2022::id(), &mint, &delegate)
+        .map_err(|e| ApiError::internal(format!("Failed to build instruction: {}", e)))?;
+
+    instruction_response_with(&ix, json!({
+        "warning": PERMANENT_DELEGATE_WARNING,
+        "note": MINT_EXTENSION_ORDER_NOTE
+    }))
+}
+
+/// Field limits follow the Metaplex conventions wallets and explorers expect.
+const MAX_METADATA_NAME_LEN: usize = 32;
+const MAX_METADATA_SYMBOL_LEN: usize = 10;
+const MAX_METADATA_URI_LEN: usize = 200;
+
+fn validate_metadata_field(field: &str, value: &str, max_len: usize) -> Result<(), ApiError> {
+    if value.len() > max_len {
+        return Err(ApiError::bad_request(format!(
+            "{} must be at most {} bytes, got {}",
+            field,
+            max_len,
+            value.len()
+        )));
+    }
+    Ok(())
+}
+
+async fn initialize_metadata_pointer(
+    Json(req): Json<InitializeMetadataPointerRequest>
+) -> ApiResult {
+    let mint = parse_pubkey("mint", &req.mint)?;
+    let authority = parse_optional_pubkey("authority", req.authority.as_deref())?;
+    let metadata_address = parse_optional_pubkey("metadataAddress", req.metadata_address.as_deref())?;
+
+    let ix = metadata_pointer_instruction::initialize(
+        &spl_token_2022::id(),
+        &mint,
+        authority,
+        metadata_address,
+    ).map_err(|e| ApiError::internal(format!("Failed to build instruction: {}", e)))?;
+
+    instruction_response_with(&ix, json!({ "note": MINT_EXTENSION_ORDER_NOTE }))
+}
+
+/// Builds the embedded-metadata Initialize instruction. With `decimals`, the
+/// full sequence is returned: metadata pointer (at the mint itself), then
+/// InitializeMint, then metadata Initialize, which needs an initialized mint.
+async fn initialize_token_metadata(
+    Json(req): Json<InitializeTokenMetadataRequest>
+) -> ApiResult {
+    let mint = parse_pubkey("mint", &req.mint)?;
+    let mint_authority = parse_pubkey("mintAuthority", &req.mint_authority)?;
+    let update_authority = parse_pubkey("updateAuthority", &req.update_authority)?;
+    validate_metadata_field("name", &req.name, MAX_METADATA_NAME_LEN)?;
+    validate_metadata_field("symbol", &req.symbol, MAX_METADATA_SYMBOL_LEN)?;
+    validate_metadata_field("uri", &req.uri, MAX_METADATA_URI_LEN)?;
+
+    let mut ixs = Vec::new();
+    if let Some(decimals) = req.decimals {
+        validate_decimals(decimals)?;
+        ixs.push(metadata_pointer_instruction::initialize(
+            &spl_token_2022::id(),
+            &mint,
+            Some(update_authority),
+            Some(mint),
+        ).map_err(|e| ApiError::internal(format!("Failed to build instruction: {}", e)))?);
+        ixs.push(token_instruction::initialize_mint(
+            &spl_token_2022::id(),
+            &mint,
+            &mint_authority,
+            None,
+            decimals,
+        ).map_err(|e| ApiError::internal(format!("Failed to build instruction: {}", e)))?);
+    }
+    ixs.push(token_metadata_instruction::initialize(
+        &spl_token_2022::id(),
+        &mint,
+        &update_authority,
+        &mint,
+        &mint_authority,
+        req.name,
+        req.symbol,
+        req.uri,
+    ));
+
+    instructions_response(&ixs, json!({}))
+}
+
+async fn initialize_mint_close_authority(
+    Json(req): Json<InitializeMintCloseAuthorityRequest>
+) -> ApiResult {
+    let mint = parse_pubkey("mint", &req.mint)?;
+    let close_authority = parse_optional_pubkey("closeAuthority", req.close_authority.as_deref())?;
+
+    let ix = token_instruction::initialize_mint_close_authority(
+        &spl_token_2022::id(),
+        &mint,
+        close_authority.as_ref(),
+    ).map_err(|e| ApiError::internal(format!("Failed to build instruction: {}", e)))?;
+
+    instruction_response_with(&ix, json!({ "note": MINT_EXTENSION_ORDER_NOTE }))
+}
+
+/// CloseAccount against a mint; the program only allows this when the
+/// MintCloseAuthority extension is set and the supply is zero.
+async fn close_mint(
+    Json(req): Json<CloseMintRequest>
+) -> ApiResult {
+    let mint = parse_pubkey("mint", &req.mint)?;
+    let destination = parse_pubkey("destination", &req.destination)?;
+    let authority = parse_pubkey("authority", &req.authority)?;
+    if mint == destination {
+        return Err(ApiError::bad_request("destination must differ from the mint being closed"));
+    }
+    let signers = parse_multisig_signers(&req.signers)?;
+    let signer_refs: Vec<&Pubkey> = signers.iter().collect();
+
+    let ix = token_instruction::close_account(
+        &spl_token_2022::id(),
+        &mint,
+        &destination,
+        &authority,
+        &signer_refs,
+    ).map_err(|e| ApiError::internal(format!("Failed to build instruction: {}", e)))?;
+
+    instruction_response(&ix)
+}
+
+async fn convert_token_amount(
+    Json(req): Json<ConvertAmountRequest>
+) -> ApiResult {
+    validate_decimals(req.decimals)?;
+
+    let amount = match (&req.ui_amount, &req.amount) {
+        (Some(ui_amount), None) => parse_ui_amount("uiAmount", ui_amount, req.decimals)?,
+        (None, Some(amount)) => {
+            if amount.is_empty() || !amount.bytes().all(|b| b.is_ascii_digit()) {
+                return Err(ApiError::bad_request(format!(
+                    "amount must be a non-negative integer string, got {:?}",
+                    amount
+                )));
+            }
+            amount.parse().map_err(|_| {
+                ApiError::bad_request(format!("amount exceeds the maximum of {}", u64::MAX))
+            })?
+        }
+        _ => return Err(ApiError::bad_request("exactly one of uiAmount or amount is required")),
+    };
+
+    success(json!({
+        "amount": amount.to_string(),
+        "ui_amount": format_ui_amount(amount, req.decimals),
+        "decimals": req.decimals
+    }))
+}
+
+async fn token_account_size(
+    Json(req): Json<TokenAccountSizeRequest>
+) -> ApiResult {
+    let extensions = req.extensions
+        .iter()
+        .enumerate()
+        .map(|(i, name)| {
+            parse_enum::<AccountExtensionParam>(
+                &format!("extensions[{}]", i),
+                name,
+                AccountExtensionParam::ACCEPTED,
+            ).map(ExtensionType::from)
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let space = ExtensionType::try_calculate_account_len::<spl_token_2022::state::Account>(&extensions)
+        .map_err(|e| ApiError::bad_request(format!("Failed to calculate account size: {}", e)))?;
+
+    success(json!({
+        "space": space,
+        "lamports": Rent::default().minimum_balance(space)
+    }))
+}
+
+/// Wallet-to-wallet token transfer: derives both ATAs, creates the
+/// destination ATA idempotently (paid by the owner), then transfers.
+async fn transfer_between_wallets(
+    Json(req): Json<WalletTransferRequest>
+) -> ApiResult {
+    let token_program = parse_token_program(req.token_program.as_deref())?;
+    let owner = parse_pubkey("ownerWallet", &req.owner_wallet)?;
+    let destination_wallet = parse_pubkey("destinationWallet", &req.destination_wallet)?;
+    let mint = parse_pubkey("mint", &req.mint)?;
+    require_nonzero_amount(req.amount)?;
+
+    let source = get_associated_token_address_with_program_id(&owner, &mint, &token_program);
+    let destination = get_associated_token_address_with_program_id(&destination_wallet, &mint, &token_program);
+
+    let create_ix = ata_instruction::create_associated_token_account_idempotent(
+        &owner,
+        &destination_wallet,
+        &mint,
+        &token_program,
+    );
+    let transfer_ix = match req.decimals {
+        Some(decimals) => {
+            validate_decimals(decimals)?;
+            token_instruction::transfer_checked(
+                &token_program,
+                &source,
+                &mint,
+                &destination,
+                &owner,
+                &[],
+                req.amount,
+                decimals,
+            )
+        }
+        None => {
+            #[allow(deprecated)]
+            token_instruction::transfer(
+                &token_program,
+                &source,
+                &destination,
+                &owner,
+                &[],
+                req.amount,
+            )
+        }
+    }.map_err(|e| ApiError::internal(format!("Failed to build instruction: {}", e)))?;
+
+    instructions_response(&[create_ix, transfer_ix], json!({
+        "source_token_account": source.to_string(),
+        "destination_token_account": destination.to_string()
+    }))
+}
+
+async fn transfer_spl_tokens(
+    State(keystore): State<Keystore>,
+    Json(req): Json<SendTokenRequest>
+) -> ApiResult {
+    let token_program = parse_token_program(req.token_program.as_deref())?;
+    let destination = parse_pubkey("destination", &req.destination)?;
+    let mint = parse_pubkey("mint", &req.mint)?;
+    let (output, signing) = parse_output_or_keystore_signing(
+        &req.output,
+        "ownerKeyLabel",
+        req.owner_key_label.as_deref(),
+        req.recent_blockhash.as_deref(),
+    )?;
+    if signing.is_some() && (req.delegate.is_some() || !req.signers.is_empty()) {
+        return Err(ApiError::bad_request(
+            "ownerKeyLabel signs as the owner and cannot be combined with delegate or multisig signers",
+        ));
+    }
+    let owner = match (signing, req.owner.as_deref()) {
+        (Some((label, _)), owner) => keystore_signer_pubkey(&keystore, label, "owner", "ownerKeyLabel", owner)?,
+        (None, Some(owner)) => parse_pubkey("owner", owner)?,
+        (None, None) => return Err(ApiError::bad_request("owner or ownerKeyLabel is required")),
+    };
+    let source = match req.source.as_deref() {
+        Some(source) => parse_pubkey("source", source)?,
+        None => get_associated_token_address_with_program_id(&owner, &mint, &token_program),
+    };
+    if source == destination {
+        return Err(ApiError::bad_request("source and destination must be different token accounts"));
+    }
+    let amount = match (req.amount, &req.ui_amount) {
+        (Some(amount), None) => amount,
+        (None, Some(ui_amount)) => {
+            let decimals = req.decimals
+                .ok_or_else(|| ApiError::bad_request("decimals is required with uiAmount"))?;
+            validate_decimals(decimals)?;
+            parse_ui_amount("uiAmount", ui_amount, decimals)?
+        }
+        _ => return Err(ApiError::bad_request("exactly one of amount or uiAmount is required")),
+    };
+
+    let signers = parse_multisig_signers(&req.signers)?;
+    let signer_refs: Vec<&Pubkey> = signers.iter().collect();
+
+    // An approved delegate signs in place of the owner, who then doesn't
+    // appear in the account list at all.
+    let authority = match &req.delegate {
+        Some(_) if !signers.is_empty() => {
+            return Err(ApiError::bad_request(
+                "delegate cannot be combined with multisig signers",
+            ));
+        }
+        Some(delegate) => parse_pubkey("delegate", delegate)?,
+        None => owner,
+    };
+
+    let (ix, instruction) = match req.decimals {
+        Some(decimals) => {
+            validate_decimals(decimals)?;
+            let ix = token_instruction::transfer_checked(
+                &token_program,
+                &source,
+                &mint,
+                &destination,
+                &authority,
+                &signer_refs,
+                amount,
+                decimals,
+            ).map_err(|e| ApiError::internal(format!("Failed to build instruction: {}", e)))?;
+            (ix, "transfer_checked")
+        }
+        None => {
+            // Plain Transfer is deprecated in token-2022 in favour of
+            // TransferChecked but both programs still accept it.
+            #[allow(deprecated)]
+            let ix = token_instruction::transfer(
+                &token_program,
+                &source,
+                &destination,
+                &authority,
+                &signer_refs,
+                amount,
+            ).map_err(|e| ApiError::internal(format!("Failed to build instruction: {}", e)))?;
+            (ix, "transfer")
+        }
+    };
+
+    let accounts: Vec<_> = ix.accounts.iter().map(|meta| {
+        json!({
+            "pubkey": meta.pubkey.to_string(),
+            "isSigner": meta.is_signer,
+        })
+    }).collect();
+
+    let instruction_data = BASE64.encode(&ix.data);
+
+    // "transfer" lists source, destination, owner; "transfer_checked" adds
+    // the mint as a fourth account between source and destination.
+    let mut data = json!({
+        "program_id": ix.program_id.to_string(),
+        "accounts": accounts,
+        "instruction_data": instruction_data,
+        "instruction": instruction
+    });
+    if let Some((label, blockhash)) = signing {
+        let signed = keystore.with_keypair(label, |keypair| {
+            signed_transaction_json(std::slice::from_ref(&ix), keypair, blockhash)
+        })?;
+        if let (Some(data), serde_json::Value::Object(signed)) = (data.as_object_mut(), signed) {
+            data.extend(signed);
+        }
+    }
+    extend_with_transaction(&mut data, std::slice::from_ref(&ix), output)?;
+
+    success(data)
+}
+
+
+/// The runtime caps a transaction's compute budget at this many units.
+const MAX_COMPUTE_UNIT_LIMIT: u64 = 1_400_000;
+
+/// Builds set_compute_unit_limit followed by set_compute_unit_price, skipping
+/// whichever is not requested. `limit_field` names the limit in errors.
+fn compute_budget_instructions(
+    limit_field: &str,
+    unit_limit: Option<u64>,
+    micro_lamports: Option<u64>,
+) -> Result<Vec<Instruction>, ApiError> {
+    let mut ixs = Vec::new();
+    if let Some(unit_limit) = unit_limit {
+        if unit_limit > MAX_COMPUTE_UNIT_LIMIT {
+            return Err(ApiError::bad_request(format!(
+                "{} must be at most {}, got {}",
+                limit_field, MAX_COMPUTE_UNIT_LIMIT, unit_limit
+            )));
+        }
+        ixs.push(ComputeBudgetInstruction::set_compute_unit_limit(unit_limit as u32));
+    }
+    if let Some(micro_lamports) = micro_lamports {
+        ixs.push(ComputeBudgetInstruction::set_compute_unit_price(micro_lamports));
+    }
+    Ok(ixs)
+}
+
+async fn compute_budget(Json(req): Json<ComputeBudgetRequest>) -> ApiResult {
+    if req.unit_limit.is_none() && req.micro_lamports.is_none() {
+        return Err(ApiError::bad_request("unitLimit or microLamports is required"));
+    }
+    let ixs = compute_budget_instructions("unitLimit", req.unit_limit, req.micro_lamports)?;
+
+    instructions_response(&ixs, json!({}))
+}
+
+async fn address_with_seed(Json(req): Json<AddressWithSeedRequest>) -> ApiResult {
+    let base = parse_pubkey("base", &req.base)?;
+    let owner = parse_pubkey("owner", &req.owner)?;
+    let address = derive_address_with_seed(&base, "seed", &req.seed, &owner)?;
+
+    success(json!({ "address": address.to_string() }))
+}
+
+/// Always answers 200: an unusable address is a result here, not a request
+/// error. `on_curve` is only meaningful for valid addresses — an off-curve key
+/// has no private key, so it's a PDA (or a mistake), never a wallet.
+async fn validate_address(Json(req): Json<ValidateAddressRequest>) -> ApiResult {
+    let address = req.address.trim();
+    let bytes = match bs58::decode(address).into_vec() {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            return success(json!({
+                "valid": false,
+                "reason": format!("not valid base58: {}", e),
+                "bytes_len": null,
+                "on_curve": null
+            }));
+        }
+    };
+    let Ok(pubkey) = Pubkey::try_from(bytes.as_slice()) else {
+        return success(json!({
+            "valid": false,
+            "reason": format!("decodes to {} bytes, expected 32", bytes.len()),
+            "bytes_len": bytes.len(),
+            "on_curve": null
+        }));
+    };
+
+    success(json!({
+        "valid": true,
+        "address": pubkey.to_string(),
+        "bytes_len": bytes.len(),
+        "on_curve": pubkey.is_on_curve()
+    }))
+}
+
+/// Turns typed seeds into raw bytes, enforcing the runtime's limits. The bump
+/// is always appended as a final seed, so callers get one fewer than MAX_SEEDS.
+fn pda_seed_bytes(seeds: &[PdaSeedParam]) -> Result<Vec<Vec<u8>>, ApiError> {
+    if seeds.len() > MAX_SEEDS - 1 {
+        return Err(ApiError::bad_request(format!(
+            "seeds must contain at most {} entries ({} including the bump), got {}",
+            MAX_SEEDS - 1,
+            MAX_SEEDS,
+            seeds.len()
+        )));
+    }
+    seeds
+        .iter()
+        .enumerate()
+        .map(|(i, seed)| {
+            let bytes = match seed {
+                PdaSeedParam::String(value) => value.as_bytes().to_vec(),
+                PdaSeedParam::Pubkey(value) => parse_pubkey(&format!("seeds[{}].pubkey", i), value)?.to_bytes().to_vec(),
+                PdaSeedParam::BytesBase64(value) => BASE64
+                    .decode(value)
+                    .map_err(|e| ApiError::bad_request(format!("Invalid seeds[{}].bytesBase64: {}", i, e)))?,
+                PdaSeedParam::U64Le(value) => value.to_le_bytes().to_vec(),
+            };
+            if bytes.len() > MAX_SEED_LEN {
+                return Err(ApiError::bad_request(format!(
+                    "seeds[{}] must be at most {} bytes, got {}",
+                    i,
+                    MAX_SEED_LEN,
+                    bytes.len()
+                )));
+            }
+            Ok(bytes)
+        })
+        .collect()
+}
+
+/// Without a bump this searches like find_program_address; with one it checks
+/// that exact bump, which is what programs do when the bump is stored on chain.
+async fn derive_pda(Json(req): Json<DerivePdaRequest>) -> ApiResult {
+    let program_id = parse_pubkey("programId", &req.program_id)?;
+    let seeds = pda_seed_bytes(&req.seeds)?;
+    let mut seed_refs: Vec<&[u8]> = seeds.iter().map(Vec::as_slice).collect();
+
+    let (address, bump) = match req.bump {
+        Some(bump) => {
+            let bump_seed = [bump];
+            seed_refs.push(&bump_seed);
+            let address = Pubkey::create_program_address(&seed_refs, &program_id).map_err(|_| {
+                ApiError::bad_request(format!(
+                    "seeds with bump {} produce a point on the ed25519 curve, which is not a valid program address",
+                    bump
+                ))
+            })?;
+            (address, bump)
+        }
+        None => Pubkey::try_find_program_address(&seed_refs, &program_id)
+            .ok_or_else(|| ApiError::bad_request("no bump produces a valid program address for these seeds"))?,
+    };
+
+    success(json!({
+        "address": address.to_string(),
+        "bump": bump
+    }))
+}
+
+/// The memo program rejects non-UTF-8 data on chain, so base64 input must
+/// still decode to valid UTF-8.
+async fn build_memo(Json(req): Json<MemoRequest>) -> ApiResult {
+    let memo = decode_message("message", &req.message, req.encoding.as_deref())?;
+    if std::str::from_utf8(&memo).is_err() {
+        return Err(ApiError::bad_request("message must decode to valid UTF-8"));
+    }
+    validate_memo("message", &memo)?;
+    let signers = parse_pubkey_list("signers", &req.signers)?;
+    let signer_refs: Vec<&Pubkey> = signers.iter().collect();
+
+    let ix = spl_memo::build_memo(&memo, &signer_refs);
+
+    instruction_response_with(&ix, json!({ "length": memo.len() }))
+}
+
+async fn create_account(
+    Json(req): Json<CreateAccountRequest>
+) -> ApiResult {
+    let payer = parse_pubkey("payer", &req.payer)?;
+    let new_account = parse_pubkey("newAccount", &req.new_account)?;
+    let owner = parse_pubkey("owner", &req.owner)?;
+    validate_space(req.space)?;
+    let lamports = resolve_lamports(req.lamports, req.space);
+
+    let ix = system_instruction::create_account(&payer, &new_account, lamports, req.space, &owner);
+
+    instruction_response_with(&ix, json!({
+        "lamports": lamports,
+        "space": req.space
+    }))
+}
+
+async fn create_account_with_seed(
+    Json(req): Json<CreateAccountWithSeedRequest>
+) -> ApiResult {
+    let payer = parse_pubkey("payer", &req.payer)?;
+    let base = parse_pubkey("base", &req.base)?;
+    let owner = parse_pubkey("owner", &req.owner)?;
+    validate_space(req.space)?;
+    let address = derive_address_with_seed(&base, "seed", &req.seed, &owner)?;
+    let lamports = resolve_lamports(req.lamports, req.space);
+
+    let ix = system_instruction::create_account_with_seed(
+        &payer,
+        &address,
+        &base,
+        &req.seed,
+        lamports,
+        req.space,
+        &owner,
+    );
+
+    instruction_response_with(&ix, json!({
+        "address": address.to_string(),
+        "lamports": lamports,
+        "space": req.space
+    }))
+}
+
+async fn allocate_account(Json(req): Json<AllocateRequest>) -> ApiResult {
+    let account = parse_pubkey("account", &req.account)?;
+    validate_space(req.space)?;
+
+    let ix = if req.base.is_some() || req.seed.is_some() {
+        let owner = req.owner.as_deref()
+            .ok_or_else(|| ApiError::bad_request("owner is required when allocating with a seed"))?;
+        let owner = parse_pubkey("owner", owner)?;
+        let base = parse_seed_base(&account, req.base.as_deref(), req.seed.as_deref(), &owner)?
+            .expect("base and seed are present");
+        system_instruction::allocate_with_seed(
+            &account,
+            &base,
+            req.seed.as_deref().unwrap_or_default(),
+            req.space,
+            &owner,
+        )
+    } else {
+        system_instruction::allocate(&account, req.space)
+    };
+
+    instruction_response(&ix)
+}
+
+async fn assign_account(Json(req): Json<AssignRequest>) -> ApiResult {
+    let account = parse_pubkey("account", &req.account)?;
+    let owner = parse_pubkey("owner", &req.owner)?;
+
+    let ix = match parse_seed_base(&account, req.base.as_deref(), req.seed.as_deref(), &owner)? {
+        Some(base) => system_instruction::assign_with_seed(
+            &account,
+            &base,
+            req.seed.as_deref().unwrap_or_default(),
+            &owner,
+        ),
+        None => system_instruction::assign(&account, &owner),
+    };
+
+    instruction_response(&ix)
+}
+
+/// Returns create_account (sized and funded for nonce state) followed by
+/// initialize_nonce_account, in the order they must execute.
+async fn create_nonce_account(Json(req): Json<CreateNonceRequest>) -> ApiResult {
+    let payer = parse_pubkey("payer", &req.payer)?;
+    let nonce_account = parse_pubkey("nonceAccount", &req.nonce_account)?;
+    let authority = parse_pubkey("authority", &req.authority)?;
+    let space = NonceState::size() as u64;
+    let lamports = resolve_lamports(req.lamports, space);
+
+    let ixs = system_instruction::create_nonce_account(&payer, &nonce_account, &authority, lamports);
+
+    instructions_response(&ixs, json!({
+        "nonce_account": nonce_account.to_string(),
+        "space": space,
+        "lamports": lamports
+    }))
+}
+
+async fn advance_nonce(Json(req): Json<AdvanceNonceRequest>) -> ApiResult {
+    let nonce_account = parse_pubkey("nonceAccount", &req.nonce_account)?;
+    let authority = parse_pubkey("authority", &req.authority)?;
+
+    let ix = system_instruction::advance_nonce_account(&nonce_account, &authority);
+
+    instruction_response(&ix)
+}
+
+async fn withdraw_nonce(Json(req): Json<WithdrawNonceRequest>) -> ApiResult {
+    let nonce_account = parse_pubkey("nonceAccount", &req.nonce_account)?;
+    let authority = parse_pubkey("authority", &req.authority)?;
+    let to = parse_pubkey("to", &req.to)?;
+    require_nonzero_lamports(req.lamports)?;
+
+    let ix = system_instruction::withdraw_nonce_account(&nonce_account, &authority, &to, req.lamports);
+
+    instruction_response(&ix)
+}
+
+async fn authorize_nonce(Json(req): Json<AuthorizeNonceRequest>) -> ApiResult {
+    let nonce_account = parse_pubkey("nonceAccount", &req.nonce_account)?;
+    let current_authority = parse_pubkey("currentAuthority", &req.current_authority)?;
+    let new_authority = parse_pubkey("newAuthority", &req.new_authority)?;
+    if current_authority == new_authority {
+        return Err(ApiError::bad_request("newAuthority must differ from currentAuthority"));
+    }
+
+    let ix = system_instruction::authorize_nonce_account(&nonce_account, &current_authority, &new_authority);
+
+    instruction_response(&ix)
+}
+
+/// The stake program's minimum delegation while the raise-to-1-SOL feature
+/// remains inactive on mainnet.
+const MIN_STAKE_DELEGATION: u64 = 1;
+
+/// Returns create_account, initialize and delegate_stake, in execution order.
+async fn create_and_delegate_stake(
+    Json(req): Json<CreateAndDelegateStakeRequest>
+) -> ApiResult {
+    let payer = parse_pubkey("payer", &req.payer)?;
+    let stake_account = parse_pubkey("stakeAccount", &req.stake_account)?;
+    let staker = parse_pubkey("staker", &req.staker)?;
+    let withdrawer = parse_pubkey("withdrawer", &req.withdrawer)?;
+    let vote_account = parse_pubkey("voteAccount", &req.vote_account)?;
+
+    let space = StakeStateV2::size_of() as u64;
+    let rent = Rent::default().minimum_balance(StakeStateV2::size_of());
+    let minimum = rent + MIN_STAKE_DELEGATION;
+    if req.lamports < minimum {
+        return Err(ApiError::bad_request(format!(
+            "lamports must be at least {} (rent-exempt reserve {} plus minimum delegation {}), got {}",
+            minimum, rent, MIN_STAKE_DELEGATION, req.lamports
+        )));
+    }
+
+    let ixs = stake_instruction::create_account_and_delegate_stake(
+        &payer,
+        &stake_account,
+        &vote_account,
+        &Authorized { staker, withdrawer },
+        &Lockup::default(),
+        req.lamports,
+    );
+
+    instructions_response(&ixs, json!({
+        "space": space,
+        "rent_exempt_reserve": rent
+    }))
+}
+
+async fn deactivate_stake(Json(req): Json<DeactivateStakeRequest>) -> ApiResult {
+    let stake_account = parse_pubkey("stakeAccount", &req.stake_account)?;
+    let staker = parse_pubkey("staker", &req.staker)?;
+
+    let ix = stake_instruction::deactivate_stake(&stake_account, &staker);
+
+    instruction_response(&ix)
+}
+
+/// A custodian is only needed to withdraw from a stake account whose lockup
+/// is still in force; it is appended as an extra signer.
+async fn withdraw_stake(Json(req): Json<WithdrawStakeRequest>) -> ApiResult {
+    let stake_account = parse_pubkey("stakeAccount", &req.stake_account)?;
+    let withdrawer = parse_pubkey("withdrawer", &req.withdrawer)?;
+    let to = parse_pubkey("to", &req.to)?;
+    let custodian = parse_optional_pubkey("custodian", req.custodian.as_deref())?;
+    require_nonzero_lamports(req.lamports)?;
+
+    let ix = stake_instruction::withdraw(&stake_account, &withdrawer, &to, req.lamports, custodian.as_ref());
+
+    instruction_response(&ix)
+}
+
+/// Returns allocate, assign and split; the new stake account must sign the
+/// first two.
+async fn split_stake(Json(req): Json<SplitStakeRequest>) -> ApiResult {
+    let stake_account = parse_pubkey("stakeAccount", &req.stake_account)?;
+    let staker = parse_pubkey("staker", &req.staker)?;
+    let new_stake_account = parse_pubkey("newStakeAccount", &req.new_stake_account)?;
+    require_nonzero_lamports(req.lamports)?;
+    if stake_account == new_stake_account {
+        return Err(ApiError::bad_request("newStakeAccount must differ from stakeAccount"));
+    }
+
+    let ixs = stake_instruction::split(&stake_account, &staker, req.lamports, &new_stake_account);
+
+    instructions_response(&ixs, json!({}))
+}
+
+async fn merge_stake(Json(req): Json<MergeStakeRequest>) -> ApiResult {
+    let destination = parse_pubkey("destination", &req.destination)?;
+    let source = parse_pubkey("source", &req.source)?;
+    let staker = parse_pubkey("staker", &req.staker)?;
+    if destination == source {
+        return Err(ApiError::bad_request("source must differ from destination"));
+    }
+
+    let ixs = stake_instruction::merge(&destination, &source, &staker);
+
+    instructions_response(&ixs, json!({}))
+}
+
+async fn authorize_stake(Json(req): Json<AuthorizeStakeRequest>) -> ApiResult {
+    let stake_account = parse_pubkey("stakeAccount", &req.stake_account)?;
+    let current_authority = parse_pubkey("currentAuthority", &req.current_authority)?;
+    let new_authority = parse_pubkey("newAuthority", &req.new_authority)?;
+    let authority_type: StakeAuthorizeParam =
+        parse_enum("authorityType", &req.authority_type, StakeAuthorizeParam::ACCEPTED)?;
+    let custodian = parse_optional_pubkey("custodian", req.custodian.as_deref())?;
+
+    let ix = stake_instruction::authorize(
+        &stake_account,
+        &current_authority,
+        &new_authority,
+        authority_type.into(),
+        custodian.as_ref(),
+    );
+
+    instruction_response(&ix)
+}
+
+/// The table address is derived from the authority and recent slot, so the
+/// slot must be recent enough for the runtime to still find it in SlotHashes.
+async fn create_lookup_table(Json(req): Json<CreateLookupTableRequest>) -> ApiResult {
+    let authority = parse_pubkey("authority", &req.authority)?;
+    let payer = parse_pubkey("payer", &req.payer)?;
+
+    let (ix, lookup_table) = lookup_table_instruction::create_lookup_table(authority, payer, req.recent_slot);
+
+    instruction_response_with(&ix, json!({ "lookup_table_address": lookup_table.to_string() }))
+}
+
+/// Addresses per extend instruction that still leave room in a transaction
+/// for signatures and the other accounts.
+const MAX_LOOKUP_TABLE_EXTEND_ADDRESSES: usize = 30;
+
+/// Splits `addresses` into as many extend instructions as needed, each
+/// appending up to MAX_LOOKUP_TABLE_EXTEND_ADDRESSES in input order.
+async fn extend_lookup_table(Json(req): Json<ExtendLookupTableRequest>) -> ApiResult {
+    let lookup_table = parse_pubkey("lookupTable", &req.lookup_table)?;
+    let authority = parse_pubkey("authority", &req.authority)?;
+    let payer = parse_pubkey("payer", &req.payer)?;
+    if req.addresses.is_empty() {
+        return Err(ApiError::bad_request("addresses must not be empty"));
+    }
+    if req.addresses.len() > LOOKUP_TABLE_MAX_ADDRESSES {
+        return Err(ApiError::bad_request(format!(
+            "a lookup table holds at most {} addresses, got {}",
+            LOOKUP_TABLE_MAX_ADDRESSES,
+            req.addresses.len()
+        )));
+    }
+    let addresses = parse_pubkey_list("addresses", &req.addresses)?;
+
+    let ixs: Vec<Instruction> = addresses
+        .chunks(MAX_LOOKUP_TABLE_EXTEND_ADDRESSES)
+        .map(|chunk| {
+            lookup_table_instruction::extend_lookup_table(lookup_table, authority, Some(payer), chunk.to_vec())
+        })
+        .collect();
+
+    instructions_response(&ixs, json!({}))
+}
+
+async fn deactivate_lookup_table(Json(req): Json<DeactivateLookupTableRequest>) -> ApiResult {
+    let lookup_table = parse_pubkey("lookupTable", &req.lookup_table)?;
+    let authority = parse_pubkey("authority", &req.authority)?;
+
+    let ix = lookup_table_instruction::deactivate_lookup_table(lookup_table, authority);
+
+    instruction_response(&ix)
+}
+
+/// Only succeeds on chain once the table has been deactivated and the
+/// deactivation slot has aged out of SlotHashes.
+async fn close_lookup_table(Json(req): Json<CloseLookupTableRequest>) -> ApiResult {
+    let lookup_table = parse_pubkey("lookupTable", &req.lookup_table)?;
+    let authority = parse_pubkey("authority", &req.authority)?;
+    let recipient = parse_pubkey("recipient", &req.recipient)?;
+    if recipient == lookup_table {
+        return Err(ApiError::bad_request("recipient must differ from lookupTable"));
+    }
+
+    let ix = lookup_table_instruction::close_lookup_table(lookup_table, authority, recipient);
+
+    instruction_response(&ix)
+}
+
+/// A null newAuthority makes the program permanently immutable, so callers
+/// must opt in with confirmImmutable.
+async fn set_upgrade_authority(Json(req): Json<SetUpgradeAuthorityRequest>) -> ApiResult {
+    let program_id = parse_pubkey("programId", &req.program_id)?;
+    let current_authority = parse_pubkey("currentAuthority", &req.current_authority)?;
+    let new_authority = parse_optional_pubkey("newAuthority", req.new_authority.as_deref())?;
+    if new_authority.is_none() && !req.confirm_immutable {
+        return Err(ApiError::bad_request(
+            "newAuthority is null, which makes the program immutable forever; set confirmImmutable to true to proceed",
+        ));
+    }
+
+    let (program_data, _) =
+        Pubkey::find_program_address(&[program_id.as_ref()], &bpf_loader_upgradeable::id());
+    let ix = bpf_loader_upgradeable::set_upgrade_authority(&program_id, &current_authority, new_authority.as_ref());
+
+    instruction_response_with(&ix, json!({ "program_data_address": program_data.to_string() }))
+}
+
+async fn transfer_sol(
+    State(keystore): State<Keystore>,
+    Json(req): Json<SendSolRequest>
+) -> ApiResult {
+    let (output, signing) = parse_output_or_keystore_signing(
+        &req.output,
+        "fromKeyLabel",
+        req.from_key_label.as_deref(),
+        req.recent_blockhash.as_deref(),
+    )?;
+    let from = match (signing, req.from.as_deref()) {
+        (Some((label, _)), from) => keystore_signer_pubkey(&keystore, label, "from", "fromKeyLabel", from)?,
+        (None, Some(from)) => parse_pubkey("from", from)?,
+        (None, None) => return Err(ApiError::bad_request("from or fromKeyLabel is required")),
+    };
+
+    let to = parse_pubkey("to", &req.to)?;
+
+    let lamports = match (req.lamports, req.amount_sol.as_deref()) {
+        (Some(lamports), None) => lamports,
+        (None, Some(amount_sol)) => parse_ui_amount("amountSol", amount_sol, LAMPORTS_DECIMALS)?,
+        (Some(_), Some(_)) => {
+            return Err(ApiError::bad_request("provide either lamports or amountSol, not both"));
+        }
+        (None, None) => return Err(ApiError::bad_request("lamports or amountSol is required")),
+    };
+
+    let mut ixs = vec![system_instruction::transfer(&from, &to, lamports)];
+    if let Some(memo) = req.memo.as_deref() {
+        validate_memo("memo", memo.as_bytes())?;
+        ixs.push(spl_memo::build_memo(memo.as_bytes(), &[&from]));
+    }
+
+    let mut extra = json!({ "lamports": lamports });
+    if let Some((label, blockhash)) = signing {
+        let signed = keystore.with_keypair(label, |keypair| signed_transaction_json(&ixs, keypair, blockhash))?;
+        if let (Some(extra), serde_json::Value::Object(signed)) = (extra.as_object_mut(), signed) {
+            extra.extend(signed);
+        }
+    }
+    extend_with_transaction(&mut extra, &ixs, output)?;
+
+    if req.memo.is_some() {
+        return instructions_response(&ixs, extra);
+    }
+
+    let ix = &ixs[0];
+    let accounts: Vec<_> = ix.accounts.iter().map(|meta| {
+        meta.pubkey.to_string()
+    }).collect();
+
+    let instruction_data = BASE64.encode(&ix.data);
+
+    let mut data = json!({
+        "program_id": ix.program_id.to_string(),
+        "accounts": accounts,
+        "instruction_data": instruction_data
+    });
+    if let (Some(data), serde_json::Value::Object(extra)) = (data.as_object_mut(), extra) {
+        data.extend(extra);
+    }
+
+    success(data)
+}
+
+async fn transfer_sol_with_seed(
+    Json(req): Json<SendSolWithSeedRequest>
+) -> ApiResult {
+    let from_base = parse_pubkey("fromBase", &req.from_base)?;
+    let from_owner = parse_pubkey("fromOwner", &req.from_owner)?;
+    let to = parse_pubkey("to", &req.to)?;
+    require_nonzero_lamports(req.lamports)?;
+    let from = derive_address_with_seed(&from_base, "fromSeed", &req.from_seed, &from_owner)?;
+
+    let ix = system_instruction::transfer_with_seed(
+        &from,
+        &from_base,
+        req.from_seed,
+        &from_owner,
+        &to,
+        req.lamports,
+    );
+
+    instruction_response_with(&ix, json!({ "from": from.to_string() }))
+}
+
+/// More transfers than this won't fit in a single transaction.
+const MAX_BATCH_TRANSFERS: usize = 20;
+
+/// Fans out one system transfer per entry, preserving input order.
+async fn transfer_sol_batch(
+    Json(req): Json<SendSolBatchRequest>
+) -> ApiResult {
+    let from = parse_pubkey("from", &req.from)?;
+    if req.transfers.is_empty() {
+        return Err(ApiError::bad_request("transfers must not be empty"));
+    }
+    if req.transfers.len() > MAX_BATCH_TRANSFERS {
+        return Err(ApiError::bad_request(format!(
+            "at most {} transfers are allowed per batch, got {}",
+            MAX_BATCH_TRANSFERS,
+            req.transfers.len()
+        )));
+    }
+
+    let mut seen = std::collections::HashSet::new();
+    let mut ixs = Vec::with_capacity(req.transfers.len());
+    for (i, entry) in req.transfers.iter().enumerate() {
+        let to = parse_pubkey(&format!("transfers[{}].to", i), &entry.to)?;
+        if entry.lamports == 0 {
+            return Err(ApiError::bad_request(format!(
+                "transfers[{}].lamports must be greater than 0",
+                i
+            )));
+        }
+        if !seen.insert(to) && !req.allow_duplicates {
+            return Err(ApiError::bad_request(format!(
+                "transfers[{}].to duplicates an earlier recipient; set allowDuplicates to permit this",
+                i
+            )));
+        }
+        ixs.push(system_instruction::transfer(&from, &to, entry.lamports));
+    }
+
+    instructions_response(&ixs, json!({}))
+}
+
+
+const DEFAULT_MAX_MESSAGE_BYTES: usize = 64 * 1024;
+
+/// Largest decoded message the signing endpoints accept, overridable with the
+/// MAX_MESSAGE_BYTES environment variable.
+fn max_message_bytes() -> usize {
+    static LIMIT: std::sync::OnceLock<usize> = std::sync::OnceLock::new();
+    *LIMIT.get_or_init(|| {
+        std::env::var("MAX_MESSAGE_BYTES")
+            .ok()
+            .and_then(|value| value.parse().ok())
+            .unwrap_or(DEFAULT_MAX_MESSAGE_BYTES)
+    })
+}
+
+/// Request body cap for the signing routes: room for a hex-encoded message
+/// at the limit (the largest encoding) plus the other fields, so oversized
+/// bodies are rejected before they are buffered.
+fn signing_body_limit() -> usize {
+    max_message_bytes() * 2 + 16 * 1024
+}
+
+fn check_message_size(message: &[u8]) -> Result<(), ApiError> {
+    let limit = max_message_bytes();
+    if message.len() > limit {
+        return Err(ApiError::payload_too_large(format!(
+            "message is {} bytes, exceeding the limit of {} bytes",
+            message.len(),
+            limit
+        )));
+    }
+    Ok(())
+}
+
+/// Accepts either a 32-byte ed25519 seed or the 64-byte secret returned by
+/// /keypair. With `encoding` set to base64 or hex, arbitrary binary payloads
+/// such as serialized transaction messages can be signed.
+async fn sign_message_with_ed25519(
+    State(keystore): State<Keystore>,
+    Json(req): Json<SignMessageRequest>
+) -> ApiResult {
+    if req.message.is_empty() || req.signer.is_empty() {
+        return Err(ApiError::bad_request("Missing required fields"));
+    }
+
+    let message = decode_message("message", &req.message, req.encoding.as_deref())?;
+    check_message_size(&message)?;
+    let signature_encoding = parse_signature_encoding(req.signature_encoding.as_deref())?;
+    let expected_pubkey = parse_optional_pubkey("expectedPubkey", req.expected_pubkey.as_deref())?;
+
+    let (pubkey, signature) = sign_message_as(&keystore, &req.signer, &message, expected_pubkey).await?;
+
+    let mut data = signature_json(&signature, signature_encoding);
+    data["public_key"] = json!(pubkey.to_string());
+    data["message"] = json!(req.message);
+    data["encoding"] = json!(req.encoding.as_deref().unwrap_or("utf8"));
+    success(data)
+}
+
+/// More signers than this is almost certainly a mistake, and each one may
+/// cost an Argon2 derivation.
+const MAX_MESSAGE_SIGNERS: usize = 16;
+
+/// Signs one message with every signer, in order. Any bad signer fails the
+/// whole request, naming its index, since a partial set of signatures is of
+/// no use to the caller.
+async fn sign_message_multi(
+    State(keystore): State<Keystore>,
+    Json(req): Json<SignMessageMultiRequest>
+) -> ApiResult {
+    if req.message.is_empty() {
+        return Err(ApiError::bad_request("Missing required fields"));
+    }
+    if req.signers.is_empty() || req.signers.len() > MAX_MESSAGE_SIGNERS {
+        return Err(ApiError::bad_request(format!(
+            "signers must contain between 1 and {} entries, got {}",
+            MAX_MESSAGE_SIGNERS,
+            req.signers.len()
+        )));
+    }
+
+    let message = decode_message("message", &req.message, req.encoding.as_deref())?;
+    check_message_size(&message)?;
+    let signature_encoding = parse_signature_encoding(req.signature_encoding.as_deref())?;
+
+    let mut signatures = Vec::with_capacity(req.signers.len());
+    for (i, signer) in req.signers.iter().enumerate() {
+        if signer.is_empty() {
+            return Err(ApiError::bad_request(format!(
+                "signers[{}]: secret, encryptedSecret or keyLabel is required",
+                i
+            )));
+        }
+        let (pubkey, signature) = sign_message_as(&keystore, signer, &message, None)
+            .await
+            .map_err(|e| ApiError { status: e.status, message: format!("signers[{}]: {}", i, e.message) })?;
+        signatures.push(json!({
+            "pubkey": pubkey.to_string(),
+            "signature": encode_signature(&signature, signature_encoding)
+        }));
+    }
+
+    success(json!({
+        "signatures": signatures,
+        "message": req.message,
+        "encoding": req.encoding.as_deref().unwrap_or("utf8")
+    }))
+}
+
+impl MessageSigner {
+    fn is_empty(&self) -> bool {
+        !(self.secret.as_ref().is_some_and(|secret| !secret.is_empty())
+            || self.encrypted_secret.as_ref().is_some_and(|secret| !secret.is_empty())
+            || self.key_label.is_some())
+    }
+}
+
+/// The single signing path behind /message/sign and /message/sign-multi.
+async fn sign_message_as(
+    keystore: &Keystore,
+    signer: &MessageSigner,
+    message: &[u8],
+    expected_pubkey: Option<Pubkey>,
+) -> Result<(Pubkey, solana_sdk::signature::Signature), ApiError> {
+    let sign = |keypair: &Keypair| {
+        if expected_pubkey.is_some_and(|expected| keypair.pubkey() != expected) {
+            return Err(ApiError::bad_request("secret does not correspond to expectedPubkey"));
+        }
+        Ok((keypair.pubkey(), keypair.sign_message(message)))
+    };
+    match &signer.key_label {
+        Some(_) if signer.secret.is_some() || signer.encrypted_secret.is_some() => {
+            Err(ApiError::bad_request("provide only one of secret, encryptedSecret or keyLabel"))
+        }
+        Some(label) => keystore.with_keypair(label, sign),
+        None => {
+            let secret_bytes = resolve_signing_secret(signer).await?;
+            let (keypair, _) = keypair_from_secret("secret", &secret_bytes)?;
+            sign(&keypair)
+        }
+    }
+}
+
+/// Yields the signing key from either a plain `secret` or an
+/// `encryptedSecret` + `passphrase` pair as returned by the keypair endpoints.
+async fn resolve_signing_secret(signer: &MessageSigner) -> Result<SecretBytes, ApiError> {
+    match (&signer.secret, &signer.encrypted_secret) {
+        (Some(_), Some(_)) => Err(ApiError::bad_request("provide either secret or encryptedSecret, not both")),
+        (Some(secret), None) => decode_secret("secret", secret, signer.secret_encoding.as_deref()),
+        (None, Some(encrypted)) => {
+            if signer.secret_encoding.is_some() {
+                return Err(ApiError::bad_request("secretEncoding does not apply to encryptedSecret"));
+            }
+            let passphrase = signer
+                .passphrase
+                .as_ref()
+                .ok_or_else(|| ApiError::bad_request("passphrase is required with encryptedSecret"))?;
+            let encrypted = encrypted.clone();
+            let passphrase = Zeroizing::new(passphrase.to_string());
+            run_blocking(move || decrypt_secret(&encrypted, &passphrase)).await
+        }
+        (None, None) => Err(ApiError::bad_request("Missing required fields")),
+    }
+}
+
+/// Decodes a 64-byte signature given as base64 (what /message/sign returns)
+/// or base58 (what explorers and the CLI show). A 64-byte base64 value always
+/// ends in "==", which base58 cannot contain, so a full-length decode in
+/// either alphabet is unambiguous; otherwise the length error reports the
+/// likelier interpretation.
+fn decode_signature(field: &str, value: &str) -> Result<solana_sdk::signature::Signature, ApiError> {
+    let base64 = BASE64.decode(value).ok();
+    let base58 = bs58::decode(value).into_vec().ok();
+    let bytes = match (base64, base58) {
+        (Some(bytes), _) if bytes.len() == 64 => bytes,
+        (_, Some(bytes)) if bytes.len() == 64 => bytes,
+        (Some(bytes), _) if value.ends_with('=') => bytes,
+        (_, Some(bytes)) | (Some(bytes), None) => bytes,
+        (None, None) => {
+            return Err(ApiError::bad_request(format!("Invalid {}: expected base64 or base58", field)));
+        }
+    };
+    solana_sdk::signature::Signature::try_from(bytes.as_slice()).map_err(|_| {
+        ApiError::bad_request(format!("{} must be 64 bytes, got {}", field, bytes.len()))
+    })
+}
+
+fn parse_signature_encoding(encoding: Option<&str>) -> Result<SignatureEncodingParam, ApiError> {
+    match encoding {
+        Some(encoding) => parse_enum("signatureEncoding", encoding, SignatureEncodingParam::ACCEPTED),
+        None => Ok(SignatureEncodingParam::Base64),
+    }
+}
+
+fn encode_signature(signature: &solana_sdk::signature::Signature, encoding: SignatureEncodingParam) -> String {
+    match encoding {
+        SignatureEncodingParam::Base64 => BASE64.encode(signature.as_ref()),
+        SignatureEncodingParam::Base58 => signature.to_string(),
+        SignatureEncodingParam::Hex => hex::encode(signature.as_ref()),
+    }
+}
+
+/// Signature fields for signing responses: `signature` in the requested
+/// encoding, plus base58 and base64 forms unconditionally so clients written
+/// against either convention keep working.
+fn signature_json(
+    signature: &solana_sdk::signature::Signature,
+    encoding: SignatureEncodingParam,
+) -> serde_json::Value {
+    json!({
+        "signature": encode_signature(signature, encoding),
+        "signature_base58": encode_signature(signature, SignatureEncodingParam::Base58),
+        "signature_base64": encode_signature(signature, SignatureEncodingParam::Base64)
+    })
+}
+
+/// Decodes a signature in a declared encoding, naming it in the error.
+fn decode_signature_as(
+    field: &str,
+    value: &str,
+    encoding: SignatureEncodingParam,
+) -> Result<solana_sdk::signature::Signature, ApiError> {
+    let (name, decoded) = match encoding {
+        SignatureEncodingParam::Base64 => ("base64", BASE64.decode(value).map_err(|e| e.to_string())),
+        SignatureEncodingParam::Base58 => ("base58", bs58::decode(value).into_vec().map_err(|e| e.to_string())),
+        SignatureEncodingParam::Hex => ("hex", hex::decode(value).map_err(|e| e.to_string())),
+    };
+    let bytes = decoded.map_err(|e| ApiError::bad_request(format!("Invalid {} ({}): {}", field, name, e)))?;
+    solana_sdk::signature::Signature::try_from(bytes.as_slice()).map_err(|_| {
+        ApiError::bad_request(format!("{} must be 64 bytes, got {}", field, bytes.len()))
+    })
+}
+
+/// The ed25519 group order L, little-endian.
+const ED25519_GROUP_ORDER: [u8; 32] = [
+    0xed, 0xd3, 0xf5, 0x5c, 0x1a, 0x63, 0x12, 0x58, 0xd6, 0x9c, 0xf7, 0xa2, 0xde, 0xf9, 0xde, 0x14,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x10,
+];
+
+/// Whether the signature's s half (its last 32 bytes, little-endian) is
+/// below L. Strict verifiers, the runtime's included, reject s >= L, which
+/// would otherwise let anyone derive a second valid signature from the first.
+fn has_canonical_s(signature: &solana_sdk::signature::Signature) -> bool {
+    let s = &signature.as_ref()[32..];
+    s.iter().rev().cmp(ED25519_GROUP_ORDER.iter().rev()) == std::cmp::Ordering::Less
+}
+
+/// Without an explicit `encoding`, 128 hex digits are read as hex and
+/// anything else as base64 or base58 (see decode_signature).
+async fn convert_signature(Json(req): Json<ConvertSignatureRequest>) -> ApiResult {
+    let value = req.signature.trim();
+    let encoding = match req.encoding.as_deref() {
+        Some(encoding) => parse_enum("encoding", encoding, SignatureEncodingParam::ACCEPTED)?,
+        None if value.len() == 128 && value.bytes().all(|b| b.is_ascii_hexdigit()) => SignatureEncodingParam::Hex,
+        None if value.ends_with('=') || BASE64.decode(value).is_ok_and(|bytes| bytes.len() == 64) => {
+            SignatureEncodingParam::Base64
+        }
+        None => SignatureEncodingParam::Base58,
+    };
+    let signature = decode_signature_as("signature", value, encoding)?;
+    let detected = match encoding {
+        SignatureEncodingParam::Base64 => "base64",
+        SignatureEncodingParam::Base58 => "base58",
+        SignatureEncodingParam::Hex => "hex",
+    };
+
+    success(json!({
+        "encoding": detected,
+        "base58": encode_signature(&signature, SignatureEncodingParam::Base58),
+        "base64": encode_signature(&signature, SignatureEncodingParam::Base64),
+        "hex": encode_signature(&signature, SignatureEncodingParam::Hex),
+        "s_canonical": has_canonical_s(&signature)
+    }))
+}
+
+/// Verifies over the decoded message bytes, using the same `encoding` rules
+/// as /message/sign.
+async fn verify_message(Json(req): Json<VerifyMessageRequest>) -> ApiResult {
+    let message = decode_message("message", &req.message, req.encoding.as_deref())?;
+    check_message_size(&message)?;
+    let signature = decode_signature("signature", &req.signature)?;
+    let pubkey = parse_pubkey("pubkey", &req.pubkey)?;
+
+    let valid = signature.verify(pubkey.as_ref(), &message);
+
+    success(json!({ "valid": valid }))
+}
+
+// Layout of the ed25519 precompile's instruction data: a count byte and a
+// padding byte, then one 14-byte offsets record per signature, then the
+// referenced bytes. u16::MAX as an instruction index means "this instruction".
+const ED25519_OFFSETS_START: usize = 2;
+const ED25519_OFFSETS_SIZE: usize = 14;
+const ED25519_DATA_START: usize = ED25519_OFFSETS_START + ED25519_OFFSETS_SIZE;
+const ED25519_CURRENT_INSTRUCTION: u16 = u16::MAX;
+
+/// Builds the single-signature ed25519 precompile instruction with the public
+/// key, signature and message all inline, matching the layout of
+/// solana_sdk::ed25519_instruction::new_ed25519_instruction.
+fn build_ed25519_verify_instruction(public_key: &[u8; 32], signature: &[u8; 64], message: &[u8]) -> Instruction {
+    let public_key_offset = ED25519_DATA_START;
+    let signature_offset = public_key_offset + public_key.len();
+    let message_data_offset = signature_offset + signature.len();
+
+    let mut data = Vec::with_capacity(message_data_offset + message.len());
+    data.extend_from_slice(&[1, 0]);
+    for field in [
+        signature_offset as u16,
+        ED25519_CURRENT_INSTRUCTION,
+        public_key_offset as u16,
+        ED25519_CURRENT_INSTRUCTION,
+        message_data_offset as u16,
+        message.len() as u16,
+        ED25519_CURRENT_INSTRUCTION,
+    ] {
+        data.extend_from_slice(&field.to_le_bytes());
+    }
+    data.extend_from_slice(public_key);
+    data.extend_from_slice(signature);
+    data.extend_from_slice(message);
+
+    Instruction {
+        program_id: solana_sdk::ed25519_program::id(),
+        accounts: vec![],
+        data,
+    }
+}
+
+/// Decodes a base58 value that must be exactly `N` bytes long.
+fn decode_base58_array<const N: usize>(field: &str, value: &str) -> Result<[u8; N], ApiError> {
+    let bytes = bs58::decode(value)
+        .into_vec()
+        .map_err(|e| ApiError::bad_request(format!("Invalid {}: {}", field, e)))?;
+    <[u8; N]>::try_from(bytes.as_slice()).map_err(|_| {
+        ApiError::bad_request(format!("{} must be {} bytes, got {}", field, N, bytes.len()))
+    })
+}
+
+/// Decodes a base64 value that must be exactly `N` bytes long.
+fn decode_base64_array<const N: usize>(field: &str, value: &str) -> Result<[u8; N], ApiError> {
+    let bytes = BASE64
+        .decode(value)
+        .map_err(|e| ApiError::bad_request(format!("Invalid {}: {}", field, e)))?;
+    <[u8; N]>::try_from(bytes.as_slice()).map_err(|_| {
+        ApiError::bad_request(format!("{} must be {} bytes, got {}", field, N, bytes.len()))
+    })
+}
+
+async fn ed25519_verify_instruction(
+    Json(req): Json<Ed25519VerifyInstructionRequest>
+) -> ApiResult {
+    let message = decode_message("message", &req.message, req.encoding.as_deref())?;
+    let signature: [u8; 64] = decode_base64_array("signature", &req.signature)?;
+    let public_key: [u8; 32] = decode_base58_array("publicKey", &req.public_key)?;
+    let max_message_len = usize::from(u16::MAX) - ED25519_DATA_START - public_key.len() - signature.len();
+    if message.len() > max_message_len {
+        return Err(ApiError::bad_request(format!(
+            "message must be at most {} bytes, got {}",
+            max_message_len,
+            message.len()
+        )));
+    }
+
+    let ix = build_ed25519_verify_instruction(&public_key, &signature, &message);
+
+    instruction_response_with(&ix, json!({
+        "offsets": {
+            "public_key_offset": ED25519_DATA_START,
+            "signature_offset": ED25519_DATA_START + public_key.len(),
+            "message_data_offset": ED25519_DATA_START + public_key.len() + signature.len(),
+            "message_data_size": message.len()
+        }
+    }))
+}
+
+// Layout of the secp256k1 precompile's instruction data: a count byte, one
+// 11-byte offsets record per signature, then the referenced bytes. Unlike
+// ed25519, the indexes are absolute positions in the transaction.
+const SECP256K1_DATA_START: usize = 1 + solana_sdk::secp256k1_instruction::SIGNATURE_OFFSETS_SERIALIZED_SIZE;
+const ETH_ADDRESS_SIZE: usize = solana_sdk::secp256k1_instruction::HASHED_PUBKEY_SERIALIZED_SIZE;
+
+/// Builds the single-signature secp256k1 precompile instruction with the
+/// address, signature, recovery id and message inline. `instruction_index` is
+/// the position this instruction will occupy in the transaction.
+fn build_secp256k1_verify_instruction(
+    eth_address: &[u8; ETH_ADDRESS_SIZE],
+    signature: &[u8; 64],
+    recovery_id: u8,
+    message: &[u8],
+    instruction_index: u8,
+) -> Instruction {
+    let eth_address_offset = SECP256K1_DATA_START;
+    let signature_offset = eth_address_offset + eth_address.len();
+    let message_data_offset = signature_offset + signature.len() + 1;
+
+    let mut data = Vec::with_capacity(message_data_offset + message.len());
+    data.push(1);
+    data.extend_from_slice(&(signature_offset as u16).to_le_bytes());
+    data.push(instruction_index);
+    data.extend_from_slice(&(eth_address_offset as u16).to_le_bytes());
+    data.push(instruction_index);
+    data.extend_from_slice(&(message_data_offset as u16).to_le_bytes());
+    data.extend_from_slice(&(message.len() as u16).to_le_bytes());
+    data.push(instruction_index);
+    data.extend_from_slice(eth_address);
+    data.extend_from_slice(signature);
+    data.push(recovery_id);
+    data.extend_from_slice(message);
+
+    Instruction {
+        program_id: solana_sdk::secp256k1_program::id(),
+        accounts: vec![],
+        data,
+    }
+}
+
+async fn secp256k1_verify_instruction(
+    Json(req): Json<Secp256k1VerifyInstructionRequest>
+) -> ApiResult {
+    let message = BASE64
+        .decode(&req.message)
+        .map_err(|e| ApiError::bad_request(format!("Invalid message: {}", e)))?;
+    let signature: [u8; 64] = decode_base64_array("signature", &req.signature)?;
+    if req.recovery_id > 3 {
+        return Err(ApiError::bad_request(format!(
+            "recoveryId must be between 0 and 3, got {}",
+            req.recovery_id
+        )));
+    }
+    let eth_address = hex::decode(req.eth_address.trim_start_matches("0x"))
+        .map_err(|e| ApiError::bad_request(format!("Invalid ethAddress: {}", e)))?;
+    let eth_address: [u8; ETH_ADDRESS_SIZE] = eth_address.as_slice().try_into().map_err(|_| {
+        ApiError::bad_request(format!(
+            "ethAddress must be {} bytes, got {}",
+            ETH_ADDRESS_SIZE,
+            eth_address.len()
+        ))
+    })?;
+    let max_message_len = usize::from(u16::MAX) - SECP256K1_DATA_START - ETH_ADDRESS_SIZE - signature.len() - 1;
+    if message.len() > max_message_len {
+        return Err(ApiError::bad_request(format!(
+            "message must be at most {} bytes, got {}",
+            max_message_len,
+            message.len()
+        )));
+    }
+
+    let ix = build_secp256k1_verify_instruction(
+        &eth_address,
+        &signature,
+        req.recovery_id,
+        &message,
+        req.instruction_index,
+    );
+
+    instruction_response(&ix)
+}
+
+/// Parses an English BIP39 phrase, reporting which word is unknown rather
+/// than just that the phrase is invalid. Returns the phrase normalized to
+/// single spaces, which is what the seed is derived from.
+fn parse_mnemonic(phrase: &str) -> Result<Zeroizing<String>, ApiError> {
+    let words: Zeroizing<Vec<String>> = Zeroizing::new(phrase.split_whitespace().map(str::to_lowercase).collect());
+    if words.len() != 12 && words.len() != 24 {
+        return Err(ApiError::bad_request(format!(
+            "mnemonic must have 12 or 24 words, got {}",
+            words.len()
+        )));
+    }
+    let normalized = Zeroizing::new(words.join(" "));
+    bip39::Mnemonic::parse_in_normalized(bip39::Language::English, &normalized).map_err(|e| match e {
+        bip39::Error::UnknownWord(i) => ApiError::bad_request(format!(
+            "mnemonic word {} ({:?}) is not in the BIP39 English word list",
+            i + 1,
+            words[i]
+        )),
+        bip39::Error::InvalidChecksum => ApiError::bad_request("mnemonic checksum is invalid"),
+        e => ApiError::bad_request(format!("Invalid mnemonic: {}", e)),
+    })?;
+    Ok(normalized)
+}
+
+/// Hardened index bit; ed25519 SLIP-0010 has no non-hardened derivation.
+const HARDENED_OFFSET: u32 = 1 << 31;
+
+/// Parses an absolute path such as "m/44'/501'/3'/0'". solana-sdk silently
+/// hardens every segment, which would derive a different key than the caller
+/// asked for, so unhardened segments are rejected instead.
+fn parse_derivation_path(path: &str) -> Result<solana_sdk::derivation_path::DerivationPath, ApiError> {
+    let mut segments = path.split('/');
+    if segments.next() != Some("m") {
+        return Err(ApiError::bad_request(format!(
+            "derivationPath must start with \"m/\", got {:?}",
+            path
+        )));
+    }
+    for (i, segment) in segments.enumerate() {
+        let index = segment.strip_suffix('\'').ok_or_else(|| {
+            ApiError::bad_request(format!(
+                "derivationPath segment {} ({:?}) is not hardened; ed25519 (SLIP-0010) only supports hardened segments such as {}'",
+                i + 1,
+                segment,
+                segment
+            ))
+        })?;
+        match index.parse::<u32>() {
+            Ok(index) if index < HARDENED_OFFSET => {}
+            _ => {
+                return Err(ApiError::bad_request(format!(
+                    "derivationPath segment {} ({:?}) must be an index below {} followed by '",
+                    i + 1,
+                    segment,
+                    HARDENED_OFFSET
+                )));
+            }
+        }
+    }
+    solana_sdk::derivation_path::DerivationPath::from_absolute_path_str(path)
+        .map_err(|e| ApiError::bad_request(format!("Invalid derivationPath: {}", e)))
+}
+
+/// Derives a keypair from a 64-byte BIP39 seed. Without a path this matches
+/// solana-keygen, which uses the first 32 bytes of the seed directly.
+fn derive_keypair(seed: &[u8], derivation_path: Option<&str>) -> Result<Keypair, ApiError> {
+    match derivation_path {
+        None => solana_sdk::signer::keypair::keypair_from_seed(seed),
+        Some(path) => {
+            let path = parse_derivation_path(path)?;
+            solana_sdk::signer::keypair::keypair_from_seed_and_derivation_path(seed, Some(path))
+        }
+    }
+    .map_err(|e| ApiError::internal(format!("Failed to derive keypair: {}", e)))
+}
+
+async fn keypair_from_mnemonic(Json(req): Json<KeypairFromMnemonicRequest>) -> ApiResult {
+    let output = parse_secret_output(
+        req.format.as_deref(),
+        req.encryption_passphrase,
+        "encryptionPassphrase",
+    )?;
+    let phrase = parse_mnemonic(&req.mnemonic)?;
+    let seed = Zeroizing::new(solana_sdk::signer::keypair::generate_seed_from_seed_phrase_and_passphrase(
+        &phrase,
+        req.passphrase.as_deref().unwrap_or_default(),
+    ));
+    let derivation_path = match (req.derivation_path, req.account_index) {
+        (Some(_), Some(_)) => {
+            return Err(ApiError::bad_request("provide either derivationPath or accountIndex, not both"));
+        }
+        (Some(path), None) => Some(path),
+        (None, Some(index)) if index >= HARDENED_OFFSET => {
+            return Err(ApiError::bad_request(format!(
+                "accountIndex must be below {}, got {}",
+                HARDENED_OFFSET, index
+            )));
+        }
+        (None, Some(index)) => Some(format!("m/44'/501'/{}'/0'", index)),
+        (None, None) => None,
+    };
+    let keypair = derive_keypair(&seed, derivation_path.as_deref())?;
+
+    success(keypair_output(keypair, output).await?)
+}
+
+/// Generates a phrase from OS entropy and returns it with the keypair that
+/// solana-keygen would recover from it (no passphrase, no derivation path).
+async fn generate_mnemonic(body: Bytes) -> ApiResult {
+    let req: GenerateMnemonicRequest = parse_optional_body(&body)?;
+    let format = parse_secret_format(req.format.as_deref())?;
+    let entropy_len = match req.word_count.unwrap_or(12) {
+        12 => 16,
+        24 => 32,
+        other => {
+            return Err(ApiError::bad_request(format!("wordCount must be 12 or 24, got {}", other)));
+        }
+    };
+    let mut entropy = Zeroizing::new(vec![0u8; entropy_len]);
+    rand::RngCore::fill_bytes(&mut rand::rngs::OsRng, &mut entropy);
+    let mnemonic = bip39::Mnemonic::from_entropy(&entropy)
+        .map_err(|e| ApiError::internal(format!("Failed to generate mnemonic: {}", e)))?;
+
+    let phrase = Zeroizing::new(mnemonic.to_string());
+    let seed = Zeroizing::new(solana_sdk::signer::keypair::generate_seed_from_seed_phrase_and_passphrase(&phrase, ""));
+    let keypair = derive_keypair(&seed, None)?;
+
+    let mut data = keypair_json(&keypair, format);
+    data["mnemonic"] = json!(phrase.as_str());
+    success(data)
+}
+
+/// Shared state for the router; handlers extract the part they need.
+#[derive(Clone, Default)]
+struct AppState {
+    keystore: Keystore,
+    test_keys: Option<TestKeypairSource>,
+}
+
+impl FromRef<AppState> for Keystore {
+    fn from_ref(state: &AppState) -> Self {
+        state.keystore.clone()
+    }
+}
+
+impl FromRef<AppState> for Option<TestKeypairSource> {
+    fn from_ref(state: &AppState) -> Self {
+        state.test_keys.clone()
+    }
+}
+
+fn app(state: AppState) -> Router {
+    Router::new()
+        .route("/keypair", post(generate_new_keypair))
+        .route("//keypair", post(generate_new_keypair))
+        .route("/keypair/from-seed", post(keypair_from_seed))
+        .route("//keypair/from-seed", post(keypair_from_seed))
+        .route("/keypair/from-mnemonic", post(keypair_from_mnemonic))
+        .route("//keypair/from-mnemonic", post(keypair_from_mnemonic))
+        .route("/keypair/mnemonic", post(generate_mnemonic))
+        .route("//keypair/mnemonic", post(generate_mnemonic))
+        .route("/keypair/verify", post(verify_keypair))
+        .route("//keypair/verify", post(verify_keypair))
+        .route("/token/create", post(initialize_token_mint))
+        .route("//token/create", post(initialize_token_mint))
+        .route("/token/create/full", post(initialize_token_mint_full))
+        .route("//token/create/full", post(initialize_token_mint_full))
+        .route("/token/mint", post(mint_tokens))
+        .route("//token/mint", post(mint_tokens))
+        .route("/token/burn", post(burn_tokens))
+        .route("//token/burn", post(burn_tokens))
+        .route("/token/mint-checked", post(mint_tokens_checked))
+        .route("//token/mint-checked", post(mint_tokens_checked))
+        .route("/token/burn-checked", post(burn_tokens_checked))
+        .route("//token/burn-checked", post(burn_tokens_checked))
+        .route("/token/account/initialize", post(initialize_token_account))
+        .route("//token/account/initialize", post(initialize_token_account))
+        .route("/token/account/size", post(token_account_size))
+        .route("//token/account/size", post(token_account_size))
+        .route("/token/ata/create", post(create_associated_token_account))
+        .route("//token/ata/create", post(create_associated_token_account))
+        .route("/token/ata/create-idempotent", post(create_associated_token_account_idempotent))
+        .route("//token/ata/create-idempotent", post(create_associated_token_account_idempotent))
+        .route("/token/ata/derive", post(derive_associated_token_account))
+        .route("//token/ata/derive", post(derive_associated_token_account))
+        .route("/token/approve", post(approve_delegate))
+        .route("//token/approve", post(approve_delegate))
+        .route("/token/revoke", post(revoke_delegate))
+        .route("//token/revoke", post(revoke_delegate))
+        .route("/token/freeze", post(freeze_token_account))
+        .route("//token/freeze", post(freeze_token_account))
+        .route("/token/thaw", post(thaw_token_account))
+        .route("//token/thaw", post(thaw_token_account))
+        .route("/token/close", post(close_token_account))
+        .route("//token/close", post(close_token_account))
+        .route("/token/set-authority", post(set_authority))
+        .route("//token/set-authority", post(set_authority))
+        .route("/token/multisig/initialize", post(initialize_multisig))
+        .route("//token/multisig/initialize", post(initialize_multisig))
+        .route("/token/sync-native", post(sync_native))
+        .route("//token/sync-native", post(sync_native))
+        .route("/token/amount/convert", post(convert_token_amount))
+        .route("//token/amount/convert", post(convert_token_amount))
+        .route("/send/sol", post(transfer_sol))
+        .route("//send/sol", post(transfer_sol))
+        .route("/send/sol/with-seed", post(transfer_sol_with_seed))
+        .route("//send/sol/with-seed", post(transfer_sol_with_seed))
+        .route("/send/sol/batch", post(transfer_sol_batch))
+        .route("//send/sol/batch", post(transfer_sol_batch))
+        .route("/send/token", post(transfer_spl_tokens))
+        .route("//send/token", post(transfer_spl_tokens))
+        .route("/token/transfer", post(transfer_between_wallets))
+        .route("//token/transfer", post(transfer_between_wallets))
+        .route("/sol/wrap", post(wrap_sol))
+        .route("//sol/wrap", post(wrap_sol))
+        .route("/sol/unwrap", post(unwrap_sol))
+        .route("//sol/unwrap", post(unwrap_sol))
+        .route("/token2022/transfer-fee/initialize", post(initialize_transfer_fee))
+        .route("//token2022/transfer-fee/initialize", post(initialize_transfer_fee))
+        .route("/token2022/transfer-fee/transfer", post(transfer_with_fee))
+        .route("//token2022/transfer-fee/transfer", post(transfer_with_fee))
+        .route("/token2022/transfer-fee/withdraw-withheld", post(withdraw_withheld_tokens))
+        .route("//token2022/transfer-fee/withdraw-withheld", post(withdraw_withheld_tokens))
+        .route("/token2022/interest-bearing/initialize", post(initialize_interest_bearing_mint))
+        .route("//token2022/interest-bearing/initialize", post(initialize_interest_bearing_mint))
+        .route("/token2022/interest-bearing/update-rate", post(update_interest_rate))
+        .route("//token2022/interest-bearing/update-rate", post(update_interest_rate))
+        .route("/token2022/memo-transfer/enable", post(enable_memo_transfer))
+        .route("//token2022/memo-transfer/enable", post(enable_memo_transfer))
+        .route("/token2022/memo-transfer/disable", post(disable_memo_transfer))
+        .route("//token2022/memo-transfer/disable", post(disable_memo_transfer))
+        .route("/token2022/non-transferable/initialize", post(initialize_non_transferable_mint))
+        .route("//token2022/non-transferable/initialize", post(initialize_non_transferable_mint))
+        .route("/token2022/default-account-state/initialize", post(initialize_default_account_state))
+        .route("//token2022/default-account-state/initialize", post(initialize_default_account_state))
+        .route("/token2022/default-account-state/update", post(update_default_account_state))
+        .route("//token2022/default-account-state/update", post(update_default_account_state))
+        .route("/token2022/permanent-delegate/initialize", post(initialize_permanent_delegate))
+        .route("//token2022/permanent-delegate/initialize", post(initialize_permanent_delegate))
+        .route("/token2022/metadata-pointer/initialize", post(initialize_metadata_pointer))
+        .route("//token2022/metadata-pointer/initialize", post(initialize_metadata_pointer))
+        .route("/token2022/metadata/initialize", post(initialize_token_metadata))
+        .route("//token2022/metadata/initialize", post(initialize_token_metadata))
+        .route("/token2022/mint-close-authority/initialize", post(initialize_mint_close_authority))
+        .route("//token2022/mint-close-authority/initialize", post(initialize_mint_close_authority))
+        .route("/token2022/mint/close", post(close_mint))
+        .route("//token2022/mint/close", post(close_mint))
+        .route("/compute-budget", post(compute_budget))
+        .route("//compute-budget", post(compute_budget))
+        .route("/memo", post(build_memo))
+        .route("//memo", post(build_memo))
+        .route("/address/with-seed", post(address_with_seed))
+        .route("//address/with-seed", post(address_with_seed))
+        .route("/address/validate", post(validate_address))
+        .route("//address/validate", post(validate_address))
+        .route("/pda/derive", post(derive_pda))
+        .route("//pda/derive", post(derive_pda))
+        .route("/account/create", post(create_account))
+        .route("//account/create", post(create_account))
+        .route("/account/create-with-seed", post(create_account_with_seed))
+        .route("//account/create-with-seed", post(create_account_with_seed))
+        .route("/account/allocate", post(allocate_account))
+        .route("//account/allocate", post(allocate_account))
+        .route("/account/assign", post(assign_account))
+        .route("//account/assign", post(assign_account))
+        .route("/nonce/create", post(create_nonce_account))
+        .route("//nonce/create", post(create_nonce_account))
+        .route("/nonce/advance", post(advance_nonce))
+        .route("//nonce/advance", post(advance_nonce))
+        .route("/nonce/withdraw", post(withdraw_nonce))
+        .route("//nonce/withdraw", post(withdraw_nonce))
+        .route("/nonce/authorize", post(authorize_nonce))
+        .route("//nonce/authorize", post(authorize_nonce))
+        .route("/stake/create-and-delegate", post(create_and_delegate_stake))
+        .route("//stake/create-and-delegate", post(create_and_delegate_stake))
+        .route("/stake/deactivate", post(deactivate_stake))
+        .route("//stake/deactivate", post(deactivate_stake))
+        .route("/stake/withdraw", post(withdraw_stake))
+        .route("//stake/withdraw", post(withdraw_stake))
+        .route("/stake/split", post(split_stake))
+        .route("//stake/split", post(split_stake))
+        .route("/stake/merge", post(merge_stake))
+        .route("//stake/merge", post(merge_stake))
+        .route("/stake/authorize", post(authorize_stake))
+        .route("//stake/authorize", post(authorize_stake))
+        .route("/lookup-table/create", post(create_lookup_table))
+        .route("//lookup-table/create", post(create_lookup_table))
+        .route("/lookup-table/extend", post(extend_lookup_table))
+        .route("//lookup-table/extend", post(extend_lookup_table))
+        .route("/lookup-table/deactivate", post(deactivate_lookup_table))
+        .route("//lookup-table/deactivate", post(deactivate_lookup_table))
+        .route("/lookup-table/close", post(close_lookup_table))
+        .route("//lookup-table/close", post(close_lookup_table))
+        .route("/program/set-upgrade-authority", post(set_upgrade_authority))
+        .route("//program/set-upgrade-authority", post(set_upgrade_authority))
+        .route("/message/sign", post(sign_message_with_ed25519).layer(DefaultBodyLimit::max(signing_body_limit())))
+        .route("//message/sign", post(sign_message_with_ed25519).layer(DefaultBodyLimit::max(signing_body_limit())))
+        .route("/message/sign-multi", post(sign_message_multi).layer(DefaultBodyLimit::max(signing_body_limit())))
+        .route("//message/sign-multi", post(sign_message_multi).layer(DefaultBodyLimit::max(signing_body_limit())))
+        .route("/message/verify", post(verify_message).layer(DefaultBodyLimit::max(signing_body_limit())))
+        .route("//message/verify", post(verify_message).layer(DefaultBodyLimit::max(signing_body_limit())))
+        .route("/signature/convert", post(convert_signature))
+        .route("//signature/convert", post(convert_signature))
+        .route("/ed25519/verify-instruction", post(ed25519_verify_instruction))
+        .route("//ed25519/verify-instruction", post(ed25519_verify_instruction))
+        .route("/secp256k1/verify-instruction", post(secp256k1_verify_instruction))
+        .route("//secp256k1/verify-instruction", post(secp256k1_verify_instruction))
+        .route("/transaction/build", post(transaction::build_transaction))
+        .route("//transaction/build", post(transaction::build_transaction))
+        .route("/transaction/sign", post(transaction::sign_transaction))
+        .route("//transaction/sign", post(transaction::sign_transaction))
+        .route("/transaction/partial-sign", post(transaction::partial_sign_transaction))
+        .route("//transaction/partial-sign", post(transaction::partial_sign_transaction))
+        .route("/transaction/decode", post(transaction::decode_transaction))
+        .route("//transaction/decode", post(transaction::decode_transaction))
+        .route("/transaction/size", post(transaction::transaction_size))
+        .route("//transaction/size", post(transaction::transaction_size))
+        .route("/transaction/signers", post(transaction::transaction_signers))
+        .route("//transaction/signers", post(transaction::transaction_signers))
+        .route("/transaction/update-blockhash", post(transaction::update_blockhash))
+        .route("//transaction/update-blockhash", post(transaction::update_blockhash))
+        .route("/instruction/encode", post(parsed_instruction::encode_instruction))
+        .route("//instruction/encode", post(parsed_instruction::encode_instruction))
+        .route("/instruction/decode", post(parsed_instruction::decode_instruction))
+        .route("//instruction/decode", post(parsed_instruction::decode_instruction))
+        .route("/transaction/verify-signatures", post(transaction::verify_signatures))
+        .route("//transaction/verify-signatures", post(transaction::verify_signatures))
+        .route("/transaction/attach-signature", post(transaction::attach_signature_endpoint))
+        .route("//transaction/attach-signature", post(transaction::attach_signature_endpoint))
+        .route("/transaction/assemble", post(transaction::assemble_transaction))
+        .route("//transaction/assemble", post(transaction::assemble_transaction))
+        .route("/message/compile", post(transaction::compile_message_endpoint))
+        .route("//message/compile", post(transaction::compile_message_endpoint))
+        .route("/keystore/import", post(keystore::import_key))
+        .route("//keystore/import", post(keystore::import_key))
+        .route("/keystore/list", get(keystore::list_keys))
+        .route("//keystore/list", get(keystore::list_keys))
+        .route("/keystore/:label", delete(keystore::delete_key))
+        .route("//keystore/:label", delete(keystore::delete_key))
+        .with_state(state)
+}
+
+#[tokio::main]
+async fn main() {
+    let test_keys = match TestKeypairSource::from_env_values(
+        std::env::var(KEYPAIR_TEST_SEED_VAR).ok().as_deref(),
+        std::env::var(KEYPAIR_TEST_SEED_ACK_VAR).ok().as_deref(),
+    ) {
+        Ok(test_keys) => test_keys,
+        Err(e) => {
+            eprintln!("Refusing to start: {}", e);
+            std::process::exit(1);
+        }
+    };
+    if test_keys.is_some() {
+        println!("WARNING: deterministic keypair mode is on; /keypair output is predictable");
+    }
+    let app = app(AppState { test_keys, ..AppState::default() });
+
+    let addr = SocketAddr::from(([0, 0, 0, 0], 8080));
+    println!("Listening on {}", addr);
+    
+    let listener = TcpListener::bind(addr).await.unwrap();
+    axum::serve(listener, app)
+        .await
         .unwrap();
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use spl_token::instruction as token_instruction;
+
+    async fn into_parts(resp: impl IntoResponse) -> (StatusCode, serde_json::Value) {
+        let resp = resp.into_response();
+        let status = resp.status();
+        let bytes = axum::body::to_bytes(resp.into_body(), usize::MAX).await.unwrap();
+        (status, serde_json::from_slice(&bytes).unwrap())
+    }
+
+    fn request<T: serde::de::DeserializeOwned>(body: serde_json::Value) -> Json<T> {
+        Json(serde_json::from_value(body).unwrap())
+    }
+
+    #[tokio::test]
+    async fn mint_to_matches_spl_token_builder() {
+        let mint = Pubkey::new_unique();
+        let destination = Pubkey::new_unique();
+        let authority = Pubkey::new_unique();
+
+        let (status, body) = into_parts(mint_tokens(request(json!({
+            "mint": mint.to_string(),
+            "destination": destination.to_string(),
+            "authority": authority.to_string(),
+            "amount": 1_000
+        }))).await).await;
+
+        let expected = token_instruction::mint_to(
+            &spl_token::id(), &mint, &destination, &authority, &[], 1_000,
+        ).unwrap();
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["data"]["program_id"], spl_token::id().to_string());
+        assert_eq!(body["data"]["instruction_data"], BASE64.encode(&expected.data));
+        assert_eq!(body["data"]["accounts"], json!([
+            { "pubkey": mint.to_string(), "is_signer": false, "is_writable": true },
+            { "pubkey": destination.to_string(), "is_signer": false, "is_writable": true },
+            { "pubkey": authority.to_string(), "is_signer": true, "is_writable": false },
+        ]));
+    }
+
+    #[tokio::test]
+    async fn mint_to_rejects_invalid_pubkey_and_zero_amount() {
+        let valid = Pubkey::new_unique().to_string();
+
+        let (status, body) = into_parts(mint_tokens(request(json!({
+            "mint": valid,
+            "destination": "not-a-pubkey",
+            "authority": valid,
+            "amount": 5
+        }))).await).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["success"], false);
+        assert!(body["error"].as_str().unwrap().starts_with("Invalid destination"));
+
+        let (status, body) = into_parts(mint_tokens(request(json!({
+            "mint": valid,
+            "destination": valid,
+            "authority": valid,
+            "amount": 0
+        }))).await).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["error"], "amount must be greater than 0");
+    }
+
+    #[tokio::test]
+    async fn burn_reports_failing_field() {
+        let account = Pubkey::new_unique();
+        let mint = Pubkey::new_unique();
+        let owner = Pubkey::new_unique();
+
+        let (status, body) = into_parts(burn_tokens(request(json!({
+            "account": account.to_string(),
+            "mint": mint.to_string(),
+            "owner": owner.to_string(),
+            "amount": 42
+        }))).await).await;
+        let expected = token_instruction::burn(
+            &spl_token::id(), &account, &mint, &owner, &[], 42,
+        ).unwrap();
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["data"]["instruction_data"], BASE64.encode(&expected.data));
+        assert_eq!(body["data"]["accounts"][2]["is_signer"], true);
+
+        let (status, body) = into_parts(burn_tokens(request(json!({
+            "account": account.to_string(),
+            "mint": mint.to_string(),
+            "owner": "bad",
+            "amount": 42
+        }))).await).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert!(body["error"].as_str().unwrap().starts_with("Invalid owner"));
+    }
+
+    #[tokio::test]
+    async fn initialize_account3_includes_account_size() {
+        let account = Pubkey::new_unique();
+        let mint = Pubkey::new_unique();
+        let owner = Pubkey::new_unique();
+
+        let (status, body) = into_parts(initialize_token_account(request(json!({
+            "account": account.to_string(),
+            "mint": mint.to_string(),
+            "owner": owner.to_string()
+        }))).await).await;
+
+        let expected = token_instruction::initialize_account3(
+            &spl_token::id(), &account, &mint, &owner,
+        ).unwrap();
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["data"]["space"], 165);
+        assert_eq!(body["data"]["instruction_data"], BASE64.encode(&expected.data));
+        assert_eq!(body["data"]["accounts"].as_array().unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn ata_create_returns_derived_address() {
+        let payer = Pubkey::new_unique();
+        let owner = Pubkey::new_unique();
+        let mint = Pubkey::new_unique();
+
+        let (status, body) = into_parts(create_associated_token_account(request(json!({
+            "payer": payer.to_string(),
+            "owner": owner.to_string(),
+            "mint": mint.to_string()
+        }))).await).await;
+
+        let ata = get_associated_token_address(&owner, &mint);
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["data"]["associated_token_address"], ata.to_string());
+        assert_eq!(body["data"]["program_id"], spl_associated_token_account::id().to_string());
+        assert_eq!(body["data"]["accounts"][1]["pubkey"], ata.to_string());
+    }
+
+    #[tokio::test]
+    async fn ata_derive_supports_pda_owner_and_program_override() {
+        let (pda_owner, _) = Pubkey::find_program_address(&[b"vault"], &spl_token::id());
+        let mint = Pubkey::new_unique();
+        let token_2022 = spl_token_2022::id();
+
+        let (status, body) = into_parts(derive_associated_token_account(request(json!({
+            "owner": pda_owner.to_string(),
+            "mint": mint.to_string()
+        }))).await).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(
+            body["data"]["associated_token_address"],
+            get_associated_token_address(&pda_owner, &mint).to_string()
+        );
+
+        let (_, body) = into_parts(derive_associated_token_account(request(json!({
+            "owner": pda_owner.to_string(),
+            "mint": mint.to_string(),
+            "tokenProgram": "token-2022"
+        }))).await).await;
+        assert_eq!(
+            body["data"]["associated_token_address"],
+            get_associated_token_address_with_program_id(&pda_owner, &mint, &token_2022).to_string()
+        );
+        assert_eq!(body["data"]["token_program"], token_2022.to_string());
+    }
+
+    #[tokio::test]
+    async fn approve_switches_to_checked_variant_with_mint() {
+        let source = Pubkey::new_unique();
+        let delegate = Pubkey::new_unique();
+        let owner = Pubkey::new_unique();
+        let mint = Pubkey::new_unique();
+
+        let (_, body) = into_parts(approve_delegate(request(json!({
+            "source": source.to_string(),
+            "delegate": delegate.to_string(),
+            "owner": owner.to_string(),
+            "amount": 10
+        }))).await).await;
+        let plain = token_instruction::approve(
+            &spl_token::id(), &source, &delegate, &owner, &[], 10,
+        ).unwrap();
+        assert_eq!(body["data"]["instruction_data"], BASE64.encode(&plain.data));
+
+        let (_, body) = into_parts(approve_delegate(request(json!({
+            "source": source.to_string(),
+            "delegate": delegate.to_string(),
+            "owner": owner.to_string(),
+            "amount": 10,
+            "mint": mint.to_string(),
+            "decimals": 6
+        }))).await).await;
+        let checked = token_instruction::approve_checked(
+            &spl_token::id(), &source, &mint, &delegate, &owner, &[], 10, 6,
+        ).unwrap();
+        assert_eq!(body["data"]["instruction_data"], BASE64.encode(&checked.data));
+        assert_eq!(body["data"]["accounts"][1]["pubkey"], mint.to_string());
+
+        let (status, body) = into_parts(approve_delegate(request(json!({
+            "source": source.to_string(),
+            "delegate": delegate.to_string(),
+            "owner": owner.to_string(),
+            "amount": 10,
+            "decimals": 6
+        }))).await).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["error"], "decimals is only accepted together with mint");
+    }
+
+    #[tokio::test]
+    async fn revoke_matches_spl_token_builder() {
+        let source = Pubkey::new_unique();
+        let owner = Pubkey::new_unique();
+
+        let (status, body) = into_parts(revoke_delegate(request(json!({
+            "source": source.to_string(),
+            "owner": owner.to_string()
+        }))).await).await;
+
+        let expected = token_instruction::revoke(&spl_token::id(), &source, &owner, &[]).unwrap();
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["data"]["instruction_data"], BASE64.encode(&expected.data));
+        assert_eq!(body["data"]["accounts"], json!([
+            { "pubkey": source.to_string(), "is_signer": false, "is_writable": true },
+            { "pubkey": owner.to_string(), "is_signer": true, "is_writable": false },
+        ]));
+    }
+
+    #[tokio::test]
+    async fn revoke_rejects_invalid_source() {
+        let (status, body) = into_parts(revoke_delegate(request(json!({
+            "source": "0OIl",
+            "owner": Pubkey::new_unique().to_string()
+        }))).await).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert!(body["error"].as_str().unwrap().starts_with("Invalid source"));
+    }
+
+    /// Shared by the freeze and thaw tests, which take identical inputs.
+    fn freeze_fixture() -> (Pubkey, Pubkey, Pubkey, serde_json::Value) {
+        let account = Pubkey::new_unique();
+        let mint = Pubkey::new_unique();
+        let authority = Pubkey::new_unique();
+        let body = json!({
+            "account": account.to_string(),
+            "mint": mint.to_string(),
+            "freezeAuthority": authority.to_string()
+        });
+        (account, mint, authority, body)
+    }
+
+    fn freeze_accounts_json(account: &Pubkey, mint: &Pubkey, authority: &Pubkey) -> serde_json::Value {
+        json!([
+            { "pubkey": account.to_string(), "is_signer": false, "is_writable": true },
+            { "pubkey": mint.to_string(), "is_signer": false, "is_writable": false },
+            { "pubkey": authority.to_string(), "is_signer": true, "is_writable": false },
+        ])
+    }
+
+    #[tokio::test]
+    async fn freeze_matches_spl_token_builder() {
+        let (account, mint, authority, body) = freeze_fixture();
+
+        let (status, body) = into_parts(freeze_token_account(request(body)).await).await;
+
+        let expected = token_instruction::freeze_account(
+            &spl_token::id(), &account, &mint, &authority, &[],
+        ).unwrap();
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["data"]["instruction_data"], BASE64.encode(&expected.data));
+        assert_eq!(body["data"]["accounts"], freeze_accounts_json(&account, &mint, &authority));
+    }
+
+    #[tokio::test]
+    async fn freeze_reports_invalid_authority_field() {
+        let (_, _, _, mut body) = freeze_fixture();
+        body["freezeAuthority"] = json!("nope");
+
+        let (status, body) = into_parts(freeze_token_account(request(body)).await).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert!(body["error"].as_str().unwrap().starts_with("Invalid freezeAuthority"));
+    }
+
+    #[tokio::test]
+    async fn thaw_matches_spl_token_discriminant() {
+        let (account, mint, authority, body) = freeze_fixture();
+
+        let (status, body) = into_parts(thaw_token_account(request(body)).await).await;
+
+        let data = BASE64.decode(body["data"]["instruction_data"].as_str().unwrap()).unwrap();
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(data, token_instruction::TokenInstruction::ThawAccount.pack());
+        assert_eq!(data, vec![11]);
+        assert_eq!(body["data"]["accounts"], freeze_accounts_json(&account, &mint, &authority));
+    }
+
+    #[tokio::test]
+    async fn close_orders_account_destination_owner() {
+        let account = Pubkey::new_unique();
+        let destination = Pubkey::new_unique();
+        let owner = Pubkey::new_unique();
+
+        let (status, body) = into_parts(close_token_account(request(json!({
+            "account": account.to_string(),
+            "destination": destination.to_string(),
+            "owner": owner.to_string()
+        }))).await).await;
+
+        let expected = token_instruction::close_account(
+            &spl_token::id(), &account, &destination, &owner, &[],
+        ).unwrap();
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["data"]["instruction_data"], BASE64.encode(&expected.data));
+        assert_eq!(body["data"]["accounts"], json!([
+            { "pubkey": account.to_string(), "is_signer": false, "is_writable": true },
+            { "pubkey": destination.to_string(), "is_signer": false, "is_writable": true },
+            { "pubkey": owner.to_string(), "is_signer": true, "is_writable": false },
+        ]));
+    }
+
+    #[tokio::test]
+    async fn close_rejects_destination_equal_to_account() {
+        let account = Pubkey::new_unique().to_string();
+
+        let (status, _) = into_parts(close_token_account(request(json!({
+            "account": account,
+            "destination": account,
+            "owner": Pubkey::new_unique().to_string()
+        }))).await).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn set_authority_maps_each_authority_type() {
+        use token_instruction::AuthorityType;
+
+        let account = Pubkey::new_unique();
+        let current = Pubkey::new_unique();
+        let new_authority = Pubkey::new_unique();
+
+        for (name, authority_type) in [
+            ("mintTokens", AuthorityType::MintTokens),
+            ("freezeAccount", AuthorityType::FreezeAccount),
+            ("accountOwner", AuthorityType::AccountOwner),
+            ("closeAccount", AuthorityType::CloseAccount),
+        ] {
+            let (status, body) = into_parts(set_authority(request(json!({
+                "account": account.to_string(),
+                "currentAuthority": current.to_string(),
+                "authorityType": name,
+                "newAuthority": new_authority.to_string()
+            }))).await).await;
+
+            let expected = token_instruction::set_authority(
+                &spl_token::id(), &account, Some(&new_authority), authority_type, &current, &[],
+            ).unwrap();
+            assert_eq!(status, StatusCode::OK, "{}", name);
+            assert_eq!(body["data"]["instruction_data"], BASE64.encode(&expected.data), "{}", name);
+        }
+    }
+
+    #[tokio::test]
+    async fn set_authority_null_new_authority_revokes() {
+        let account = Pubkey::new_unique();
+        let current = Pubkey::new_unique();
+
+        for body in [
+            json!({
+                "account": account.to_string(),
+                "currentAuthority": current.to_string(),
+                "authorityType": "mintTokens",
+                "newAuthority": null
+            }),
+            json!({
+                "account": account.to_string(),
+                "currentAuthority": current.to_string(),
+                "authorityType": "mintTokens"
+            }),
+        ] {
+            let (_, body) = into_parts(set_authority(request(body)).await).await;
+            let data = BASE64.decode(body["data"]["instruction_data"].as_str().unwrap()).unwrap();
+            // [discriminant, authority type, COption tag]
+            assert_eq!(data, vec![6, 0, 0]);
+        }
+    }
+
+    #[tokio::test]
+    async fn set_authority_lists_valid_types_on_unknown_value() {
+        let (status, body) = into_parts(set_authority(request(json!({
+            "account": Pubkey::new_unique().to_string(),
+            "currentAuthority": Pubkey::new_unique().to_string(),
+            "authorityType": "owner"
+        }))).await).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert!(body["error"].as_str().unwrap()
+            .ends_with("expected one of: mintTokens, freezeAccount, accountOwner, closeAccount"));
+    }
+
+    #[tokio::test]
+    async fn send_token_uses_transfer_checked_when_decimals_given() {
+        let destination = Pubkey::new_unique();
+        let mint = Pubkey::new_unique();
+        let owner = Pubkey::new_unique();
+
+        let (status, body) = into_parts(transfer_spl_tokens(State(Keystore::default()), request(json!({
+            "destination": destination.to_string(),
+            "mint": mint.to_string(),
+            "owner": owner.to_string(),
+            "amount": 250,
+            "decimals": 2
+        }))).await).await;
+
+        let source = get_associated_token_address(&owner, &mint);
+        let expected = token_instruction::transfer_checked(
+            &spl_token::id(), &source, &mint, &destination, &owner, &[], 250, 2,
+        ).unwrap();
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["data"]["instruction"], "transfer_checked");
+        assert_eq!(body["data"]["instruction_data"], BASE64.encode(&expected.data));
+        assert_eq!(body["data"]["accounts"].as_array().unwrap().len(), 4);
+        assert_eq!(body["data"]["accounts"][0], json!({ "pubkey": source.to_string(), "isSigner": false }));
+        assert_eq!(body["data"]["accounts"][1], json!({ "pubkey": mint.to_string(), "isSigner": false }));
+        assert_eq!(body["data"]["accounts"][2], json!({ "pubkey": destination.to_string(), "isSigner": false }));
+
+        let (status, _) = into_parts(transfer_spl_tokens(State(Keystore::default()), request(json!({
+            "destination": destination.to_string(),
+            "mint": mint.to_string(),
+            "owner": owner.to_string(),
+            "amount": 250,
+            "decimals": 10
+        }))).await).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn send_token_rejects_bad_accounts_and_takes_explicit_source() {
+        let destination = Pubkey::new_unique();
+        let mint = Pubkey::new_unique();
+        let owner = Pubkey::new_unique();
+        let send = |extra: serde_json::Value| {
+            let mut body = json!({
+                "destination": destination.to_string(),
+                "mint": mint.to_string(),
+                "owner": owner.to_string(),
+                "amount": 7
+            });
+            body.as_object_mut().unwrap().extend(extra.as_object().unwrap().clone());
+            transfer_spl_tokens(State(Keystore::default()), request(body))
+        };
+
+        for field in ["destination", "owner", "mint", "source"] {
+            let (status, body) = into_parts(send(json!({ field: "not-a-pubkey" })).await).await;
+            assert_eq!(status, StatusCode::BAD_REQUEST);
+            assert!(body["error"].as_str().unwrap().starts_with(&format!("Invalid {}", field)), "{}", body["error"]);
+        }
+
+        // An explicit source replaces the owner's associated token account.
+        let source = Pubkey::new_unique();
+        let (status, body) = into_parts(send(json!({ "source": source.to_string() })).await).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["data"]["instruction"], "transfer");
+        assert_eq!(body["data"]["accounts"][0]["pubkey"], source.to_string());
+        assert_eq!(body["data"]["accounts"][1]["pubkey"], destination.to_string());
+
+        let (status, body) = into_parts(send(json!({ "source": destination.to_string() })).await).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["error"], "source and destination must be different token accounts");
+    }
+
+    #[tokio::test]
+    async fn checked_mint_and_burn_match_spl_token_builders() {
+        let mint = Pubkey::new_unique();
+        let account = Pubkey::new_unique();
+        let authority = Pubkey::new_unique();
+
+        let (status, body) = into_parts(mint_tokens_checked(request(json!({
+            "mint": mint.to_string(),
+            "destination": account.to_string(),
+            "authority": authority.to_string(),
+            "amount": 7,
+            "decimals": 9
+        }))).await).await;
+        let expected = token_instruction::mint_to_checked(
+            &spl_token::id(), &mint, &account, &authority, &[], 7, 9,
+        ).unwrap();
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["data"]["instruction_data"], BASE64.encode(&expected.data));
+
+        let (status, body) = into_parts(burn_tokens_checked(request(json!({
+            "account": account.to_string(),
+            "mint": mint.to_string(),
+            "owner": authority.to_string(),
+            "amount": 7,
+            "decimals": 9
+        }))).await).await;
+        let expected = token_instruction::burn_checked(
+            &spl_token::id(), &account, &mint, &authority, &[], 7, 9,
+        ).unwrap();
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["data"]["instruction_data"], BASE64.encode(&expected.data));
+
+        let (status, _) = into_parts(burn_tokens_checked(request(json!({
+            "account": account.to_string(),
+            "mint": mint.to_string(),
+            "owner": authority.to_string(),
+            "amount": 7,
+            "decimals": 12
+        }))).await).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn multisig_initialize_validates_threshold_and_signers() {
+        let multisig = Pubkey::new_unique();
+        let signers: Vec<Pubkey> = (0..3).map(|_| Pubkey::new_unique()).collect();
+        let signer_strings: Vec<String> = signers.iter().map(|pk| pk.to_string()).collect();
+
+        let (status, body) = into_parts(initialize_multisig(request(json!({
+            "multisigAccount": multisig.to_string(),
+            "signers": signer_strings,
+            "m": 2
+        }))).await).await;
+        let signer_refs: Vec<&Pubkey> = signers.iter().collect();
+        let expected = token_instruction::initialize_multisig2(
+            &spl_token::id(), &multisig, &signer_refs, 2,
+        ).unwrap();
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["data"]["instruction_data"], BASE64.encode(&expected.data));
+        assert_eq!(body["data"]["space"], Multisig::LEN);
+
+        let (status, _) = into_parts(initialize_multisig(request(json!({
+            "multisigAccount": multisig.to_string(),
+            "signers": signer_strings,
+            "m": 4
+        }))).await).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+
+        let twelve: Vec<String> = (0..12).map(|_| Pubkey::new_unique().to_string()).collect();
+        let (status, _) = into_parts(initialize_multisig(request(json!({
+            "multisigAccount": multisig.to_string(),
+            "signers": twelve,
+            "m": 1
+        }))).await).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+
+        let (status, body) = into_parts(initialize_multisig(request(json!({
+            "multisigAccount": multisig.to_string(),
+            "signers": [signer_strings[0], "bogus"],
+            "m": 1
+        }))).await).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert!(body["error"].as_str().unwrap().starts_with("Invalid signers[1]"));
+    }
+
+    #[tokio::test]
+    async fn multisig_signers_follow_owner_as_signer_metas() {
+        let destination = Pubkey::new_unique();
+        let multisig_owner = Pubkey::new_unique();
+        let signers = [Pubkey::new_unique(), Pubkey::new_unique()];
+
+        let (status, body) = into_parts(transfer_spl_tokens(State(Keystore::default()), request(json!({
+            "destination": destination.to_string(),
+            "mint": Pubkey::new_unique().to_string(),
+            "owner": multisig_owner.to_string(),
+            "amount": 5,
+            "signers": [signers[0].to_string(), signers[1].to_string()]
+        }))).await).await;
+
+        assert_eq!(status, StatusCode::OK);
+        let accounts = body["data"]["accounts"].as_array().unwrap();
+        assert_eq!(accounts.len(), 5);
+        assert_eq!(accounts[2], json!({ "pubkey": multisig_owner.to_string(), "isSigner": false }));
+        assert_eq!(accounts[3], json!({ "pubkey": signers[0].to_string(), "isSigner": true }));
+        assert_eq!(accounts[4], json!({ "pubkey": signers[1].to_string(), "isSigner": true }));
+
+        let (status, body) = into_parts(mint_tokens(request(json!({
+            "mint": Pubkey::new_unique().to_string(),
+            "destination": destination.to_string(),
+            "authority": multisig_owner.to_string(),
+            "amount": 5,
+            "signers": [signers[0].to_string(), "x"]
+        }))).await).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert!(body["error"].as_str().unwrap().starts_with("Invalid signers[1]"));
+    }
+
+    #[tokio::test]
+    async fn sync_native_returns_native_mint() {
+        let account = Pubkey::new_unique();
+
+        let (status, body) = into_parts(sync_native(request(json!({
+            "account": account.to_string()
+        }))).await).await;
+
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["data"]["native_mint"], "So11111111111111111111111111111111111111112");
+        assert_eq!(body["data"]["instruction_data"], BASE64.encode([17]));
+        assert_eq!(body["data"]["accounts"], json!([
+            { "pubkey": account.to_string(), "is_signer": false, "is_writable": true },
+        ]));
+    }
+
+    #[tokio::test]
+    async fn wrap_sol_orders_create_transfer_sync() {
+        let owner = Pubkey::new_unique();
+        let payer = Pubkey::new_unique();
+        let ata = get_associated_token_address(&owner, &spl_token::native_mint::id());
+
+        let (status, body) = into_parts(wrap_sol(request(json!({
+            "owner": owner.to_string(),
+            "payer": payer.to_string(),
+            "lamports": 1_000_000
+        }))).await).await;
+
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["data"]["associated_token_address"], ata.to_string());
+        let instructions = body["data"]["instructions"].as_array().unwrap();
+        let program_ids: Vec<_> = instructions.iter().map(|ix| ix["program_id"].clone()).collect();
+        assert_eq!(program_ids, vec![
+            json!(spl_associated_token_account::id().to_string()),
+            json!(solana_program::system_program::id().to_string()),
+            json!(spl_token::id().to_string()),
+        ]);
+        let create = ata_instruction::create_associated_token_account_idempotent(
+            &payer, &owner, &spl_token::native_mint::id(), &spl_token::id(),
+        );
+        assert_eq!(instructions[0]["instruction_data"], BASE64.encode(&create.data));
+        assert_eq!(instructions[1]["accounts"][1]["pubkey"], ata.to_string());
+        assert_eq!(instructions[2]["accounts"][0]["pubkey"], ata.to_string());
+    }
+
+    #[tokio::test]
+    async fn unwrap_sol_closes_explicit_or_derived_account() {
+        let owner = Pubkey::new_unique();
+        let explicit = Pubkey::new_unique();
+        let derived = get_associated_token_address(&owner, &spl_token::native_mint::id());
+
+        for (body, expected_account) in [
+            (json!({ "owner": owner.to_string() }), derived),
+            (json!({ "owner": owner.to_string(), "account": explicit.to_string() }), explicit),
+        ] {
+            let (status, body) = into_parts(unwrap_sol(request(body)).await).await;
+
+            let expected = token_instruction::close_account(
+                &spl_token::id(), &expected_account, &owner, &owner, &[],
+            ).unwrap();
+            assert_eq!(status, StatusCode::OK);
+            assert_eq!(body["data"]["token_account"], expected_account.to_string());
+            assert_eq!(body["data"]["accounts"][0]["pubkey"], expected_account.to_string());
+            assert_eq!(body["data"]["accounts"][1]["pubkey"], owner.to_string());
+            assert_eq!(body["data"]["instruction_data"], BASE64.encode(&expected.data));
+        }
+    }
+
+    #[tokio::test]
+    async fn token_program_field_switches_program_id() {
+        let mint = Pubkey::new_unique();
+        let authority = Pubkey::new_unique();
+
+        for (token_program, expected) in [
+            (json!(null), spl_token::id()),
+            (json!("token"), spl_token::id()),
+            (json!("token-2022"), spl_token_2022::id()),
+            (json!(spl_token_2022::id().to_string()), spl_token_2022::id()),
+        ] {
+            let (status, body) = into_parts(initialize_token_mint(request(json!({
+                "mintAuthority": authority.to_string(),
+                "mint": mint.to_string(),
+                "decimals": 6,
+                "tokenProgram": token_program
+            }))).await).await;
+            assert_eq!(status, StatusCode::OK);
+            assert_eq!(body["data"]["program_id"], expected.to_string());
+
+            let (_, body) = into_parts(transfer_spl_tokens(State(Keystore::default()), request(json!({
+                "destination": Pubkey::new_unique().to_string(),
+                "mint": mint.to_string(),
+                "owner": authority.to_string(),
+                "amount": 1,
+                "decimals": 6,
+                "tokenProgram": token_program
+            }))).await).await;
+            assert_eq!(body["data"]["program_id"], expected.to_string());
+        }
+
+        let (status, body) = into_parts(initialize_token_mint(request(json!({
+            "mintAuthority": authority.to_string(),
+            "mint": mint.to_string(),
+            "decimals": 6,
+            "tokenProgram": "token-2023"
+        }))).await).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert!(body["error"].as_str().unwrap().contains("expected one of: token, token-2022"));
+    }
+
+    #[tokio::test]
+    async fn classic_token_output_is_unchanged_by_token_2022_builders() {
+        let mint = Pubkey::new_unique();
+        let authority = Pubkey::new_unique();
+
+        let (_, body) = into_parts(initialize_token_mint(request(json!({
+            "mintAuthority": authority.to_string(),
+            "mint": mint.to_string(),
+            "decimals": 6
+        }))).await).await;
+
+        let expected = token_instruction::initialize_mint(
+            &spl_token::id(), &mint, &authority, None, 6,
+        ).unwrap();
+        assert_eq!(body["data"]["instruction_data"], BASE64.encode(&expected.data));
+        assert_eq!(body["data"]["accounts"].as_array().unwrap().len(), expected.accounts.len());
+    }
+
+    #[tokio::test]
+    async fn transfer_fee_initialize_validates_basis_points() {
+        let mint = Pubkey::new_unique();
+        let authority = Pubkey::new_unique();
+
+        let (status, body) = into_parts(initialize_transfer_fee(request(json!({
+            "mint": mint.to_string(),
+            "feeBasisPoints": 50,
+            "maximumFee": 5_000,
+            "transferFeeConfigAuthority": authority.to_string(),
+            "withdrawWithheldAuthority": null
+        }))).await).await;
+        let expected = transfer_fee_instruction::initialize_transfer_fee_config(
+            &spl_token_2022::id(), &mint, Some(&authority), None, 50, 5_000,
+        ).unwrap();
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["data"]["program_id"], spl_token_2022::id().to_string());
+        assert_eq!(body["data"]["instruction_data"], BASE64.encode(&expected.data));
+
+        let (status, _) = into_parts(initialize_transfer_fee(request(json!({
+            "mint": mint.to_string(),
+            "feeBasisPoints": 10_001,
+            "maximumFee": 5_000
+        }))).await).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn transfer_with_fee_checks_expected_fee_against_config() {
+        let body = |fee: u64| json!({
+            "source": Pubkey::new_unique().to_string(),
+            "mint": Pubkey::new_unique().to_string(),
+            "destination": Pubkey::new_unique().to_string(),
+            "owner": Pubkey::new_unique().to_string(),
+            "amount": 10_000,
+            "decimals": 6,
+            "fee": fee,
+            "feeBasisPoints": 100,
+            "maximumFee": 1_000_000
+        });
+
+        let (status, _) = into_parts(transfer_with_fee(request(body(100))).await).await;
+        assert_eq!(status, StatusCode::OK);
+
+        let mut uncapped = body(100);
+        uncapped["maximumFee"] = json!(u64::MAX.to_string());
+        let (status, _) = into_parts(transfer_with_fee(request(uncapped)).await).await;
+        assert_eq!(status, StatusCode::OK);
+
+        let (status, body) = into_parts(transfer_with_fee(request(body(99))).await).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["error"], "fee 99 does not match the configured fee of 100 for amount 10000");
+    }
+
+    #[tokio::test]
+    async fn withdraw_withheld_picks_mint_or_accounts_variant() {
+        let mint = Pubkey::new_unique();
+        let destination = Pubkey::new_unique();
+        let authority = Pubkey::new_unique();
+        let sources = [Pubkey::new_unique(), Pubkey::new_unique()];
+
+        let (_, body) = into_parts(withdraw_withheld_tokens(request(json!({
+            "mint": mint.to_string(),
+            "destination": destination.to_string(),
+            "authority": authority.to_string(),
+            "sources": []
+        }))).await).await;
+        let from_mint = transfer_fee_instruction::withdraw_withheld_tokens_from_mint(
+            &spl_token_2022::id(), &mint, &destination, &authority, &[],
+        ).unwrap();
+        assert_eq!(body["data"]["instruction_data"], BASE64.encode(&from_mint.data));
+
+        let (_, body) = into_parts(withdraw_withheld_tokens(request(json!({
+            "mint": mint.to_string(),
+            "destination": destination.to_string(),
+            "authority": authority.to_string(),
+            "sources": [sources[0].to_string(), sources[1].to_string()]
+        }))).await).await;
+        let from_accounts = transfer_fee_instruction::withdraw_withheld_tokens_from_accounts(
+            &spl_token_2022::id(), &mint, &destination, &authority, &[], &[&sources[0], &sources[1]],
+        ).unwrap();
+        assert_eq!(body["data"]["instruction_data"], BASE64.encode(&from_accounts.data));
+        assert_eq!(body["data"]["accounts"][4]["pubkey"], sources[1].to_string());
+
+        let too_many: Vec<String> = (0..26).map(|_| Pubkey::new_unique().to_string()).collect();
+        let (status, _) = into_parts(withdraw_withheld_tokens(request(json!({
+            "mint": mint.to_string(),
+            "destination": destination.to_string(),
+            "authority": authority.to_string(),
+            "sources": too_many
+        }))).await).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn interest_bearing_rate_must_fit_i16() {
+        let mint = Pubkey::new_unique();
+        let authority = Pubkey::new_unique();
+
+        let (status, body) = into_parts(initialize_interest_bearing_mint(request(json!({
+            "mint": mint.to_string(),
+            "rateAuthority": authority.to_string(),
+            "rate": -250
+        }))).await).await;
+        let expected = interest_bearing_instruction::initialize(
+            &spl_token_2022::id(), &mint, Some(authority), -250,
+        ).unwrap();
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["data"]["instruction_data"], BASE64.encode(&expected.data));
+
+        let (status, body) = into_parts(update_interest_rate(request(json!({
+            "mint": mint.to_string(),
+            "rateAuthority": authority.to_string(),
+            "rate": 40_000
+        }))).await).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["error"], "rate must be between -32768 and 32767 basis points, got 40000");
+    }
+
+    #[tokio::test]
+    async fn memo_transfer_enable_and_disable_differ_only_in_data() {
+        let account = Pubkey::new_unique();
+        let owner = Pubkey::new_unique();
+        let body = json!({ "account": account.to_string(), "owner": owner.to_string() });
+
+        let (_, enabled) = into_parts(enable_memo_transfer(request(body.clone())).await).await;
+        let (_, disabled) = into_parts(disable_memo_transfer(request(body)).await).await;
+
+        let expected_accounts = json!([
+            { "pubkey": account.to_string(), "is_signer": false, "is_writable": true },
+            { "pubkey": owner.to_string(), "is_signer": true, "is_writable": false },
+        ]);
+        assert_eq!(enabled["data"]["accounts"], expected_accounts);
+        assert_eq!(disabled["data"]["accounts"], expected_accounts);
+
+        let enable_data = BASE64.decode(enabled["data"]["instruction_data"].as_str().unwrap()).unwrap();
+        let disable_data = BASE64.decode(disabled["data"]["instruction_data"].as_str().unwrap()).unwrap();
+        // Both share the MemoTransferExtension discriminant; the sub-instruction differs.
+        assert_eq!(enable_data[0], disable_data[0]);
+        assert_ne!(enable_data, disable_data);
+        assert_eq!(enable_data, memo_transfer_instruction::enable_required_transfer_memos(
+            &spl_token_2022::id(), &account, &owner, &[],
+        ).unwrap().data);
+    }
+
+    #[tokio::test]
+    async fn non_transferable_optionally_appends_initialize_mint() {
+        let mint = Pubkey::new_unique();
+        let authority = Pubkey::new_unique();
+
+        let (status, body) = into_parts(initialize_non_transferable_mint(request(json!({
+            "mint": mint.to_string()
+        }))).await).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["data"]["program_id"], spl_token_2022::id().to_string());
+        assert!(body["data"]["note"].is_string());
+
+        let (status, body) = into_parts(initialize_non_transferable_mint(request(json!({
+            "mint": mint.to_string(),
+            "decimals": 0,
+            "mintAuthority": authority.to_string()
+        }))).await).await;
+        assert_eq!(status, StatusCode::OK);
+        let instructions = body["data"]["instructions"].as_array().unwrap();
+        let extension = spl_token_2022::instruction::initialize_non_transferable_mint(
+            &spl_token_2022::id(), &mint,
+        ).unwrap();
+        let initialize = spl_token_2022::instruction::initialize_mint(
+            &spl_token_2022::id(), &mint, &authority, None, 0,
+        ).unwrap();
+        assert_eq!(instructions[0]["instruction_data"], BASE64.encode(&extension.data));
+        assert_eq!(instructions[1]["instruction_data"], BASE64.encode(&initialize.data));
+
+        let (status, _) = into_parts(initialize_non_transferable_mint(request(json!({
+            "mint": mint.to_string(),
+            "decimals": 0
+        }))).await).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn default_account_state_maps_state_strings() {
+        use spl_token_2022::state::AccountState;
+
+        let mint = Pubkey::new_unique();
+        let authority = Pubkey::new_unique();
+
+        let (status, body) = into_parts(initialize_default_account_state(request(json!({
+            "mint": mint.to_string(),
+            "state": "frozen"
+        }))).await).await;
+        let expected = default_account_state_instruction::initialize_default_account_state(
+            &spl_token_2022::id(), &mint, &AccountState::Frozen,
+        ).unwrap();
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["data"]["instruction_data"], BASE64.encode(&expected.data));
+
+        let (status, body) = into_parts(update_default_account_state(request(json!({
+            "mint": mint.to_string(),
+            "freezeAuthority": authority.to_string(),
+            "state": "initialized"
+        }))).await).await;
+        let expected = default_account_state_instruction::update_default_account_state(
+            &spl_token_2022::id(), &mint, &authority, &[], &AccountState::Initialized,
+        ).unwrap();
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["data"]["instruction_data"], BASE64.encode(&expected.data));
+
+        let (status, body) = into_parts(initialize_default_account_state(request(json!({
+            "mint": mint.to_string(),
+            "state": "uninitialized"
+        }))).await).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert!(body["error"].as_str().unwrap().ends_with("expected one of: initialized, frozen"));
+    }
+
+    #[tokio::test]
+    async fn permanent_delegate_includes_warning() {
+        let mint = Pubkey::new_unique();
+        let delegate = Pubkey::new_unique();
+
+        let (status, body) = into_parts(initialize_permanent_delegate(request(json!({
+            "mint": mint.to_string(),
+            "delegate": delegate.to_string()
+        }))).await).await;
+
+        let expected = spl_token_2022::instruction::initialize_permanent_delegate(
+            &spl_token_2022::id(), &mint, &delegate,
+        ).unwrap();
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["data"]["instruction_data"], BASE64.encode(&expected.data));
+        assert_eq!(body["data"]["warning"], PERMANENT_DELEGATE_WARNING);
+    }
+
+    #[tokio::test]
+    async fn token_metadata_round_trips_borsh_data() {
+        use spl_token_metadata_interface::instruction::TokenMetadataInstruction;
+
+        let mint = Pubkey::new_unique();
+        let authority = Pubkey::new_unique();
+
+        let (status, body) = into_parts(initialize_token_metadata(request(json!({
+            "mint": mint.to_string(),
+            "mintAuthority": authority.to_string(),
+            "updateAuthority": authority.to_string(),
+            "name": "Example Token",
+            "symbol": "EXT",
+            "uri": "https://example.com/token.json",
+            "decimals": 6
+        }))).await).await;
+        assert_eq!(status, StatusCode::OK);
+
+        let instructions = body["data"]["instructions"].as_array().unwrap();
+        assert_eq!(instructions.len(), 3);
+        let pointer = metadata_pointer_instruction::initialize(
+            &spl_token_2022::id(), &mint, Some(authority), Some(mint),
+        ).unwrap();
+        assert_eq!(instructions[0]["instruction_data"], BASE64.encode(&pointer.data));
+
+        let data = BASE64.decode(instructions[2]["instruction_data"].as_str().unwrap()).unwrap();
+        match TokenMetadataInstruction::unpack(&data).unwrap() {
+            TokenMetadataInstruction::Initialize(init) => {
+                assert_eq!(init.name, "Example Token");
+                assert_eq!(init.symbol, "EXT");
+                assert_eq!(init.uri, "https://example.com/token.json");
+            }
+            other => panic!("unexpected instruction {:?}", other),
+        }
+
+        let (status, body) = into_parts(initialize_token_metadata(request(json!({
+            "mint": mint.to_string(),
+            "mintAuthority": authority.to_string(),
+            "updateAuthority": authority.to_string(),
+            "name": "Example Token",
+            "symbol": "TOOLONGSYMBOL",
+            "uri": ""
+        }))).await).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["error"], "symbol must be at most 10 bytes, got 13");
+    }
+
+    #[tokio::test]
+    async fn mint_close_authority_and_close_mint() {
+        let mint = Pubkey::new_unique();
+        let authority = Pubkey::new_unique();
+        let destination = Pubkey::new_unique();
+
+        let (status, body) = into_parts(initialize_mint_close_authority(request(json!({
+            "mint": mint.to_string(),
+            "closeAuthority": authority.to_string()
+        }))).await).await;
+        let expected = spl_token_2022::instruction::initialize_mint_close_authority(
+            &spl_token_2022::id(), &mint, Some(&authority),
+        ).unwrap();
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["data"]["instruction_data"], BASE64.encode(&expected.data));
+
+        let (status, body) = into_parts(close_mint(request(json!({
+            "mint": mint.to_string(),
+            "destination": destination.to_string(),
+            "authority": authority.to_string()
+        }))).await).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["data"]["program_id"], spl_token_2022::id().to_string());
+        assert_eq!(body["data"]["accounts"][0]["pubkey"], mint.to_string());
+        assert_eq!(body["data"]["accounts"][1]["pubkey"], destination.to_string());
+
+        let (status, _) = into_parts(close_mint(request(json!({
+            "mint": mint.to_string(),
+            "destination": mint.to_string(),
+            "authority": authority.to_string()
+        }))).await).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+
+    #[test]
+    fn ui_amount_conversion_is_exact() {
+        assert_eq!(parse_ui_amount("uiAmount", "12.345", 6).ok(), Some(12_345_000));
+        assert_eq!(parse_ui_amount("uiAmount", "0.000001", 6).ok(), Some(1));
+        assert_eq!(parse_ui_amount("uiAmount", "7", 0).ok(), Some(7));
+        assert_eq!(parse_ui_amount("uiAmount", "18446744073.709551615", 9).ok(), Some(u64::MAX));
+        assert!(parse_ui_amount("uiAmount", "18446744073.709551616", 9).is_err());
+        assert!(parse_ui_amount("uiAmount", "1.0000001", 6).is_err());
+        assert!(parse_ui_amount("uiAmount", "-1", 6).is_err());
+        assert!(parse_ui_amount("uiAmount", "1e3", 6).is_err());
+        assert!(parse_ui_amount("uiAmount", "1.", 6).is_err());
+
+        assert_eq!(format_ui_amount(12_345_000, 6), "12.345");
+        assert_eq!(format_ui_amount(1, 9), "0.000000001");
+        assert_eq!(format_ui_amount(5_000_000, 6), "5");
+        assert_eq!(format_ui_amount(u64::MAX, 9), "18446744073.709551615");
+    }
+
+    #[tokio::test]
+    async fn amount_convert_returns_both_representations() {
+        let (status, body) = into_parts(convert_token_amount(request(json!({
+            "uiAmount": "12.345",
+            "decimals": 6
+        }))).await).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["data"], json!({ "amount": "12345000", "ui_amount": "12.345", "decimals": 6 }));
+
+        let (_, body) = into_parts(convert_token_amount(request(json!({
+            "amount": "12345000",
+            "decimals": 6
+        }))).await).await;
+        assert_eq!(body["data"]["ui_amount"], "12.345");
+
+        let (status, body) = into_parts(convert_token_amount(request(json!({
+            "uiAmount": "1.1234567",
+            "decimals": 6
+        }))).await).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["error"], "uiAmount has 7 fractional digits but only 6 decimals are allowed");
+    }
+
+    #[test]
+    fn u64_fields_accept_strings_and_numbers() {
+        let parse = |lamports: serde_json::Value| {
+            serde_json::from_value::<SendSolRequest>(json!({
+                "from": Pubkey::new_unique().to_string(),
+                "to": Pubkey::new_unique().to_string(),
+                "lamports": lamports
+            }))
+        };
+
+        assert_eq!(parse(json!(1_000_000)).unwrap().lamports, Some(1_000_000));
+        assert_eq!(parse(json!("18446744073709551615")).unwrap().lamports, Some(u64::MAX));
+        assert!(parse(json!("18446744073709551616")).is_err());
+        assert!(parse(json!(1.5)).is_err());
+        assert!(parse(json!(-1)).is_err());
+        assert!(parse(json!(" 42")).is_err());
+        assert!(parse(json!("+42")).is_err());
+        assert!(parse(json!("4_2")).is_err());
+
+        let err = parse(json!(2.0)).err().unwrap().to_string();
+        assert!(err.contains("must be an integer"), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn send_token_accepts_ui_amount_with_decimals() {
+        let destination = Pubkey::new_unique();
+        let mint = Pubkey::new_unique();
+        let owner = Pubkey::new_unique();
+        let body = |extra: serde_json::Value| {
+            let mut body = json!({
+                "destination": destination.to_string(),
+                "mint": mint.to_string(),
+                "owner": owner.to_string()
+            });
+            body.as_object_mut().unwrap().extend(extra.as_object().unwrap().clone());
+            body
+        };
+
+        let (status, response) = into_parts(transfer_spl_tokens(State(Keystore::default()), request(body(json!({
+            "uiAmount": "1.5",
+            "decimals": 6
+        })))).await).await;
+        let expected = token_instruction::transfer_checked(
+            &spl_token::id(), &get_associated_token_address(&owner, &mint), &mint, &destination, &owner, &[], 1_500_000, 6,
+        ).unwrap();
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(response["data"]["instruction"], "transfer_checked");
+        assert_eq!(response["data"]["instruction_data"], BASE64.encode(&expected.data));
+
+        let (status, response) = into_parts(transfer_spl_tokens(State(Keystore::default()), request(body(json!({
+            "uiAmount": "1.0000001",
+            "decimals": 6
+        })))).await).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(response["error"], "uiAmount has 7 fractional digits but only 6 decimals are allowed");
+
+        for invalid in [json!({ "amount": 5, "uiAmount": "5", "decimals": 0 }), json!({ "decimals": 0 })] {
+            let (status, response) = into_parts(transfer_spl_tokens(State(Keystore::default()), request(body(invalid))).await).await;
+            assert_eq!(status, StatusCode::BAD_REQUEST);
+            assert_eq!(response["error"], "exactly one of amount or uiAmount is required");
+        }
+    }
+
+    #[tokio::test]
+    async fn create_token_encodes_optional_freeze_authority() {
+        let mint = Pubkey::new_unique();
+        let authority = Pubkey::new_unique();
+        let other = Pubkey::new_unique();
+
+        for (freeze_authority, expected) in [
+            (json!(authority.to_string()), Some(authority)),
+            (json!(other.to_string()), Some(other)),
+            (json!(null), None),
+        ] {
+            let (status, body) = into_parts(initialize_token_mint(request(json!({
+                "mintAuthority": authority.to_string(),
+                "mint": mint.to_string(),
+                "decimals": 9,
+                "freezeAuthority": freeze_authority
+            }))).await).await;
+            assert_eq!(status, StatusCode::OK);
+
+            // [discriminant, decimals, mint authority (32), COption tag, freeze authority?]
+            let data = BASE64.decode(body["data"]["instruction_data"].as_str().unwrap()).unwrap();
+            match expected {
+                Some(freeze_authority) => {
+                    assert_eq!(data[34], 1);
+                    assert_eq!(&data[35..67], freeze_authority.as_ref());
+                }
+                None => assert_eq!(data[34], 0),
+            }
+        }
+
+        let (_, body) = into_parts(initialize_token_mint(request(json!({
+            "mintAuthority": authority.to_string(),
+            "mint": mint.to_string(),
+            "decimals": 9
+        }))).await).await;
+        let data = BASE64.decode(body["data"]["instruction_data"].as_str().unwrap()).unwrap();
+        assert_eq!(data[34], 0);
+    }
+
+    #[tokio::test]
+    async fn create_token_full_prepends_funded_create_account() {
+        let payer = Pubkey::new_unique();
+        let mint = Pubkey::new_unique();
+        let authority = Pubkey::new_unique();
+
+        let (status, body) = into_parts(initialize_token_mint_full(request(json!({
+            "payer": payer.to_string(),
+            "mintAuthority": authority.to_string(),
+            "mint": mint.to_string(),
+            "decimals": 6
+        }))).await).await;
+
+        let lamports = Rent::default().minimum_balance(Mint::LEN);
+        let create = system_instruction::create_account(
+            &payer, &mint, lamports, Mint::LEN as u64, &spl_token::id(),
+        );
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["data"]["space"], 82);
+        assert_eq!(body["data"]["lamports"], lamports);
+        let instructions = body["data"]["instructions"].as_array().unwrap();
+        assert_eq!(instructions[0]["instruction_data"], BASE64.encode(&create.data));
+        assert_eq!(instructions[1]["program_id"], spl_token::id().to_string());
+
+        let (status, _) = into_parts(initialize_token_mint_full(request(json!({
+            "payer": "bad",
+            "mintAuthority": authority.to_string(),
+            "mint": mint.to_string(),
+            "decimals": 6
+        }))).await).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn create_token_switches_to_initialize_mint2() {
+        let mint = Pubkey::new_unique();
+        let authority = Pubkey::new_unique();
+
+        for use_initialize_mint2 in [false, true] {
+            let (status, body) = into_parts(initialize_token_mint(request(json!({
+                "mintAuthority": authority.to_string(),
+                "mint": mint.to_string(),
+                "decimals": 6,
+                "useInitializeMint2": use_initialize_mint2
+            }))).await).await;
+
+            let expected = if use_initialize_mint2 {
+                token_instruction::initialize_mint2(&spl_token::id(), &mint, &authority, None, 6)
+            } else {
+                token_instruction::initialize_mint(&spl_token::id(), &mint, &authority, None, 6)
+            }.unwrap();
+            let expected_accounts: Vec<_> = expected.accounts.iter().map(|meta| json!({
+                "pubkey": meta.pubkey.to_string(),
+                "is_signer": meta.is_signer,
+                "is_writable": meta.is_writable,
+            })).collect();
+            assert_eq!(status, StatusCode::OK);
+            assert_eq!(body["data"]["instruction_data"], BASE64.encode(&expected.data));
+            assert_eq!(body["data"]["accounts"], json!(expected_accounts));
+        }
+    }
+
+    #[tokio::test]
+    async fn token_account_size_accounts_for_extensions() {
+        let (status, body) = into_parts(token_account_size(request(json!({
+            "extensions": []
+        }))).await).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["data"]["space"], 165);
+
+        let (status, body) = into_parts(token_account_size(request(json!({
+            "extensions": ["transferFeeAmount", "memoTransfer"]
+        }))).await).await;
+        let expected = ExtensionType::try_calculate_account_len::<spl_token_2022::state::Account>(
+            &[ExtensionType::TransferFeeAmount, ExtensionType::MemoTransfer],
+        ).unwrap();
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["data"]["space"], expected);
+        assert_eq!(body["data"]["lamports"], Rent::default().minimum_balance(expected));
+
+        let (status, body) = into_parts(token_account_size(request(json!({
+            "extensions": ["memoTransfer", "transferFeeConfig"]
+        }))).await).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert!(body["error"].as_str().unwrap().starts_with("Invalid extensions[1]"));
+    }
+
+    #[tokio::test]
+    async fn ata_create_idempotent_differs_only_in_data() {
+        let body = json!({
+            "payer": Pubkey::new_unique().to_string(),
+            "owner": Pubkey::new_unique().to_string(),
+            "mint": Pubkey::new_unique().to_string(),
+            "tokenProgram": "token-2022"
+        });
+
+        let (status, idempotent) =
+            into_parts(create_associated_token_account_idempotent(request(body.clone())).await).await;
+        let (_, plain) = into_parts(create_associated_token_account(request(body)).await).await;
+
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(idempotent["data"]["accounts"], plain["data"]["accounts"]);
+        assert_eq!(
+            idempotent["data"]["associated_token_address"],
+            plain["data"]["associated_token_address"]
+        );
+        assert_eq!(idempotent["data"]["instruction_data"], BASE64.encode([1]));
+        assert_ne!(idempotent["data"]["instruction_data"], plain["data"]["instruction_data"]);
+        assert_eq!(idempotent["data"]["accounts"][5]["pubkey"], spl_token_2022::id().to_string());
+    }
+
+    #[tokio::test]
+    async fn wallet_transfer_derives_atas_and_creates_destination() {
+        let owner = Pubkey::new_unique();
+        let recipient = Pubkey::new_unique();
+        let mint = Pubkey::new_unique();
+        let source = get_associated_token_address(&owner, &mint);
+        let destination = get_associated_token_address(&recipient, &mint);
+
+        let (status, body) = into_parts(transfer_between_wallets(request(json!({
+            "ownerWallet": owner.to_string(),
+            "destinationWallet": recipient.to_string(),
+            "mint": mint.to_string(),
+            "amount": "2500",
+            "decimals": 2
+        }))).await).await;
+
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["data"]["source_token_account"], source.to_string());
+        assert_eq!(body["data"]["destination_token_account"], destination.to_string());
+
+        let instructions = body["data"]["instructions"].as_array().unwrap();
+        let create = ata_instruction::create_associated_token_account_idempotent(
+            &owner, &recipient, &mint, &spl_token::id(),
+        );
+        let transfer = token_instruction::transfer_checked(
+            &spl_token::id(), &source, &mint, &destination, &owner, &[], 2500, 2,
+        ).unwrap();
+        assert_eq!(instructions[0]["instruction_data"], BASE64.encode(&create.data));
+        assert_eq!(instructions[0]["accounts"][1]["pubkey"], destination.to_string());
+        assert_eq!(instructions[1]["instruction_data"], BASE64.encode(&transfer.data));
+        assert_eq!(instructions[1]["accounts"][0]["pubkey"], source.to_string());
+    }
+
+    #[tokio::test]
+    async fn send_token_delegate_signs_instead_of_owner() {
+        let destination = Pubkey::new_unique();
+        let owner = Pubkey::new_unique();
+        let delegate = Pubkey::new_unique();
+        let body = |extra: serde_json::Value| {
+            let mut body = json!({
+                "destination": destination.to_string(),
+                "mint": Pubkey::new_unique().to_string(),
+                "owner": owner.to_string(),
+                "amount": 10
+            });
+            body.as_object_mut().unwrap().extend(extra.as_object().unwrap().clone());
+            body
+        };
+
+        let (_, owner_signed) = into_parts(transfer_spl_tokens(State(Keystore::default()), request(body(json!({})))).await).await;
+        assert_eq!(owner_signed["data"]["accounts"][2], json!({ "pubkey": owner.to_string(), "isSigner": true }));
+
+        let (status, delegate_signed) = into_parts(transfer_spl_tokens(State(Keystore::default()), request(body(json!({
+            "delegate": delegate.to_string()
+        })))).await).await;
+        assert_eq!(status, StatusCode::OK);
+        let accounts = delegate_signed["data"]["accounts"].as_array().unwrap();
+        assert_eq!(accounts[2], json!({ "pubkey": delegate.to_string(), "isSigner": true }));
+        assert!(accounts.iter().all(|meta| meta["pubkey"] != owner.to_string()));
+
+        let (status, _) = into_parts(transfer_spl_tokens(State(Keystore::default()), request(body(json!({
+            "delegate": delegate.to_string(),
+            "signers": [Pubkey::new_unique().to_string()]
+        })))).await).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn create_account_defaults_to_rent_exempt_lamports() {
+        let payer = Pubkey::new_unique();
+        let new_account = Pubkey::new_unique();
+        let owner = Pubkey::new_unique();
+
+        let (status, body) = into_parts(create_account(request(json!({
+            "payer": payer.to_string(),
+            "newAccount": new_account.to_string(),
+            "space": 200,
+            "owner": owner.to_string()
+        }))).await).await;
+
+        let lamports = Rent::default().minimum_balance(200);
+        let expected = system_instruction::create_account(&payer, &new_account, lamports, 200, &owner);
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["data"]["lamports"], lamports);
+        assert_eq!(body["data"]["instruction_data"], BASE64.encode(&expected.data));
+
+        let (_, body) = into_parts(create_account(request(json!({
+            "payer": payer.to_string(),
+            "newAccount": new_account.to_string(),
+            "lamports": 5,
+            "space": 0,
+            "owner": owner.to_string()
+        }))).await).await;
+        assert_eq!(body["data"]["lamports"], 5);
+
+        let (status, _) = into_parts(create_account(request(json!({
+            "payer": payer.to_string(),
+            "newAccount": new_account.to_string(),
+            "space": 10 * 1024 * 1024 + 1,
+            "owner": owner.to_string()
+        }))).await).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn create_account_with_seed_returns_derived_address() {
+        let payer = Pubkey::new_unique();
+        let base = Pubkey::new_unique();
+        let owner = solana_program::system_program::id();
+
+        let (status, body) = into_parts(create_account_with_seed(request(json!({
+            "payer": payer.to_string(),
+            "base": base.to_string(),
+            "seed": "user-42",
+            "lamports": 1_000_000,
+            "space": 0,
+            "owner": owner.to_string()
+        }))).await).await;
+
+        let address = Pubkey::create_with_seed(&base, "user-42", &owner).unwrap();
+        let expected = system_instruction::create_account_with_seed(
+            &payer, &address, &base, "user-42", 1_000_000, 0, &owner,
+        );
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["data"]["address"], address.to_string());
+        assert_eq!(body["data"]["instruction_data"], BASE64.encode(&expected.data));
+
+        let (status, body) = into_parts(create_account_with_seed(request(json!({
+            "payer": payer.to_string(),
+            "base": base.to_string(),
+            "seed": "x".repeat(33),
+            "space": 0,
+            "owner": owner.to_string()
+        }))).await).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["error"], "seed must be at most 32 bytes, got 33");
+    }
+
+    #[tokio::test]
+    async fn allocate_and_assign_match_system_builders() {
+        let account = Pubkey::new_unique();
+        let owner = Pubkey::new_unique();
+
+        let (status, body) = into_parts(allocate_account(request(json!({
+            "account": account.to_string(),
+            "space": "165"
+        }))).await).await;
+        assert_eq!(status, StatusCode::OK);
+        let expected = system_instruction::allocate(&account, 165);
+        assert_eq!(body["data"]["instruction_data"], BASE64.encode(&expected.data));
+
+        let (_, body) = into_parts(assign_account(request(json!({
+            "account": account.to_string(),
+            "owner": owner.to_string()
+        }))).await).await;
+        let expected = system_instruction::assign(&account, &owner);
+        assert_eq!(body["data"]["instruction_data"], BASE64.encode(&expected.data));
+
+        let base = Pubkey::new_unique();
+        let seeded = Pubkey::create_with_seed(&base, "vault", &owner).unwrap();
+        let (status, body) = into_parts(allocate_account(request(json!({
+            "account": seeded.to_string(),
+            "space": 64,
+            "base": base.to_string(),
+            "seed": "vault",
+            "owner": owner.to_string()
+        }))).await).await;
+        assert_eq!(status, StatusCode::OK);
+        let expected = system_instruction::allocate_with_seed(&seeded, &base, "vault", 64, &owner);
+        assert_eq!(body["data"]["instruction_data"], BASE64.encode(&expected.data));
+        assert_eq!(body["data"]["accounts"][1]["pubkey"], base.to_string());
+        assert_eq!(body["data"]["accounts"][1]["is_signer"], true);
+
+        let (status, body) = into_parts(assign_account(request(json!({
+            "account": account.to_string(),
+            "owner": owner.to_string(),
+            "seed": "vault"
+        }))).await).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["error"], "base and seed must be provided together");
+    }
+
+    #[tokio::test]
+    async fn transfer_sol_with_seed_derives_from_address() {
+        let base = Pubkey::new_unique();
+        let owner = solana_program::system_program::id();
+        let to = Pubkey::new_unique();
+
+        let (status, body) = into_parts(transfer_sol_with_seed(request(json!({
+            "fromBase": base.to_string(),
+            "fromSeed": "deposit-7",
+            "fromOwner": owner.to_string(),
+            "to": to.to_string(),
+            "lamports": "5000"
+        }))).await).await;
+
+        let from = Pubkey::create_with_seed(&base, "deposit-7", &owner).unwrap();
+        let expected = system_instruction::transfer_with_seed(
+            &from, &base, "deposit-7".to_string(), &owner, &to, 5000,
+        );
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["data"]["from"], from.to_string());
+        assert_eq!(body["data"]["instruction_data"], BASE64.encode(&expected.data));
+
+        let (status, body) = into_parts(transfer_sol_with_seed(request(json!({
+            "fromBase": base.to_string(),
+            "fromSeed": "deposit-7",
+            "fromOwner": owner.to_string(),
+            "to": to.to_string(),
+            "lamports": 0
+        }))).await).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["error"], "lamports must be greater than 0");
+    }
+
+    #[tokio::test]
+    async fn create_nonce_account_returns_create_then_initialize() {
+        let payer = Pubkey::new_unique();
+        let nonce = Pubkey::new_unique();
+        let authority = Pubkey::new_unique();
+
+        let (status, body) = into_parts(create_nonce_account(request(json!({
+            "payer": payer.to_string(),
+            "nonceAccount": nonce.to_string(),
+            "authority": authority.to_string()
+        }))).await).await;
+
+        let space = NonceState::size();
+        let lamports = Rent::default().minimum_balance(space);
+        let expected = system_instruction::create_nonce_account(&payer, &nonce, &authority, lamports);
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["data"]["space"], space);
+        assert_eq!(body["data"]["lamports"], lamports);
+
+        let ixs = body["data"]["instructions"].as_array().unwrap();
+        assert_eq!(ixs.len(), 2);
+        for (ix, expected) in ixs.iter().zip(&expected) {
+            assert_eq!(ix["program_id"], solana_program::system_program::id().to_string());
+            assert_eq!(ix["instruction_data"], BASE64.encode(&expected.data));
+        }
+    }
+
+    #[tokio::test]
+    async fn advance_nonce_pins_account_meta_order() {
+        let nonce = Pubkey::new_unique();
+        let authority = Pubkey::new_unique();
+
+        let (status, body) = into_parts(advance_nonce(request(json!({
+            "nonceAccount": nonce.to_string(),
+            "authority": authority.to_string()
+        }))).await).await;
+
+        assert_eq!(status, StatusCode::OK);
+        #[allow(deprecated)]
+        let recent_blockhashes = solana_program::sysvar::recent_blockhashes::id();
+        assert_eq!(body["data"]["accounts"], json!([
+            { "pubkey": nonce.to_string(), "is_signer": false, "is_writable": true },
+            { "pubkey": recent_blockhashes.to_string(), "is_signer": false, "is_writable": false },
+            { "pubkey": authority.to_string(), "is_signer": true, "is_writable": false }
+        ]));
+    }
+
+    #[tokio::test]
+    async fn withdraw_nonce_validates_fields() {
+        let nonce = Pubkey::new_unique();
+        let authority = Pubkey::new_unique();
+        let to = Pubkey::new_unique();
+
+        let (status, body) = into_parts(withdraw_nonce(request(json!({
+            "nonceAccount": nonce.to_string(),
+            "authority": authority.to_string(),
+            "to": to.to_string(),
+            "lamports": 1_500_000
+        }))).await).await;
+        let expected = system_instruction::withdraw_nonce_account(&nonce, &authority, &to, 1_500_000);
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["data"]["instruction_data"], BASE64.encode(&expected.data));
+        assert_eq!(body["data"]["accounts"].as_array().unwrap().len(), expected.accounts.len());
+
+        let (status, body) = into_parts(withdraw_nonce(request(json!({
+            "nonceAccount": nonce.to_string(),
+            "authority": authority.to_string(),
+            "to": "not-a-key",
+            "lamports": 1
+        }))).await).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert!(body["error"].as_str().unwrap().starts_with("Invalid to:"));
+    }
+
+    #[tokio::test]
+    async fn authorize_nonce_rejects_unchanged_authority() {
+        let nonce = Pubkey::new_unique();
+        let current = Pubkey::new_unique();
+        let new = Pubkey::new_unique();
+
+        let (status, body) = into_parts(authorize_nonce(request(json!({
+            "nonceAccount": nonce.to_string(),
+            "currentAuthority": current.to_string(),
+            "newAuthority": new.to_string()
+        }))).await).await;
+        let expected = system_instruction::authorize_nonce_account(&nonce, &current, &new);
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["data"]["instruction_data"], BASE64.encode(&expected.data));
+
+        let (status, body) = into_parts(authorize_nonce(request(json!({
+            "nonceAccount": nonce.to_string(),
+            "currentAuthority": current.to_string(),
+            "newAuthority": current.to_string()
+        }))).await).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["error"], "newAuthority must differ from currentAuthority");
+    }
+
+    #[tokio::test]
+    async fn transfer_sol_batch_preserves_order_and_rejects_duplicates() {
+        let from = Pubkey::new_unique();
+        let a = Pubkey::new_unique();
+        let b = Pubkey::new_unique();
+
+        let (status, body) = into_parts(transfer_sol_batch(request(json!({
+            "from": from.to_string(),
+            "transfers": [
+                { "to": a.to_string(), "lamports": 100 },
+                { "to": b.to_string(), "lamports": "200" }
+            ]
+        }))).await).await;
+        assert_eq!(status, StatusCode::OK);
+        let ixs = body["data"]["instructions"].as_array().unwrap();
+        assert_eq!(ixs.len(), 2);
+        assert_eq!(ixs[0]["instruction_data"], BASE64.encode(system_instruction::transfer(&from, &a, 100).data));
+        assert_eq!(ixs[1]["accounts"][1]["pubkey"], b.to_string());
+
+        let duplicated = json!({
+            "from": from.to_string(),
+            "transfers": [
+                { "to": a.to_string(), "lamports": 1 },
+                { "to": a.to_string(), "lamports": 2 }
+            ]
+        });
+        let (status, body) = into_parts(transfer_sol_batch(request(duplicated.clone())).await).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert!(body["error"].as_str().unwrap().starts_with("transfers[1].to duplicates"));
+
+        let mut allowed = duplicated;
+        allowed["allowDuplicates"] = json!(true);
+        let (status, _) = into_parts(transfer_sol_batch(request(allowed)).await).await;
+        assert_eq!(status, StatusCode::OK);
+
+        let too_many: Vec<_> = (0..21)
+            .map(|_| json!({ "to": Pubkey::new_unique().to_string(), "lamports": 1 }))
+            .collect();
+        let (status, body) = into_parts(transfer_sol_batch(request(json!({
+            "from": from.to_string(),
+            "transfers": too_many
+        }))).await).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["error"], "at most 20 transfers are allowed per batch, got 21");
+    }
+
+    #[tokio::test]
+    async fn transfer_sol_accepts_amount_sol() {
+        let from = Pubkey::new_unique().to_string();
+        let to = Pubkey::new_unique().to_string();
+        let send = |extra: serde_json::Value| {
+            let mut body = json!({ "from": from, "to": to });
+            body.as_object_mut().unwrap().extend(extra.as_object().unwrap().clone());
+            transfer_sol(State(Keystore::default()), request(body))
+        };
+
+        let (status, body) = into_parts(send(json!({ "amountSol": "0.000000001" })).await).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["data"]["lamports"], 1);
+
+        let (_, body) = into_parts(send(json!({ "amountSol": "18446744073.709551615" })).await).await;
+        assert_eq!(body["data"]["lamports"], u64::MAX);
+
+        let (status, body) = into_parts(send(json!({ "amountSol": "18446744073.709551616" })).await).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["error"], format!("amountSol exceeds the maximum of {} base units", u64::MAX));
+
+        let (status, body) = into_parts(send(json!({ "amountSol": "0.0000000001" })).await).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["error"], "amountSol has 10 fractional digits but only 9 decimals are allowed");
+
+        let (status, body) = into_parts(send(json!({ "amountSol": "-1" })).await).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["error"], "amountSol must not be negative");
+
+        let (status, _) = into_parts(send(json!({ "amountSol": "1", "lamports": 5 })).await).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn transfer_sol_appends_memo_instruction() {
+        let from = Pubkey::new_unique();
+        let to = Pubkey::new_unique();
+        let memo = "dépôt #42 ✓";
+
+        let (status, body) = into_parts(transfer_sol(State(Keystore::default()), request(json!({
+            "from": from.to_string(),
+            "to": to.to_string(),
+            "lamports": 10,
+            "memo": memo
+        }))).await).await;
+        assert_eq!(status, StatusCode::OK);
+        let ixs = body["data"]["instructions"].as_array().unwrap();
+        assert_eq!(ixs.len(), 2);
+        assert_eq!(ixs[0]["program_id"], solana_program::system_program::id().to_string());
+        assert_eq!(ixs[1]["program_id"], spl_memo::id().to_string());
+        assert_eq!(ixs[1]["instruction_data"], BASE64.encode(memo.as_bytes()));
+        assert_eq!(ixs[1]["accounts"][0]["pubkey"], from.to_string());
+        assert_eq!(ixs[1]["accounts"][0]["is_signer"], true);
+
+        let (_, body) = into_parts(transfer_sol(State(Keystore::default()), request(json!({
+            "from": from.to_string(),
+            "to": to.to_string(),
+            "lamports": 10
+        }))).await).await;
+        assert!(body["data"]["instructions"].is_null());
+        assert_eq!(body["data"]["accounts"][0], from.to_string());
+
+        let (status, body) = into_parts(transfer_sol(State(Keystore::default()), request(json!({
+            "from": from.to_string(),
+            "to": to.to_string(),
+            "lamports": 10,
+            "memo": "x".repeat(567)
+        }))).await).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["error"], "memo must be at most 566 bytes, got 567");
+    }
+
+    #[tokio::test]
+    async fn derive_pda_matches_known_vectors() {
+        let usdc = "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v";
+        let metaplex = "metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s";
+
+        let (status, body) = into_parts(derive_pda(request(json!({
+            "programId": metaplex,
+            "seeds": [{ "string": "metadata" }, { "pubkey": metaplex }, { "pubkey": usdc }]
+        }))).await).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["data"]["address"], "5x38Kp4hvdomTCnCrAny4UtMUt5rQBdB6px2K1Ui45Wq");
+        assert_eq!(body["data"]["bump"], 255);
+
+        let owner = Pubkey::new_unique();
+        let mint = Pubkey::from_str(usdc).unwrap();
+        let (status, body) = into_parts(derive_pda(request(json!({
+            "programId": spl_associated_token_account::id().to_string(),
+            "seeds": [
+                { "pubkey": owner.to_string() },
+                { "bytesBase64": BASE64.encode(spl_token::id()) },
+                { "pubkey": usdc }
+            ]
+        }))).await).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["data"]["address"], get_associated_token_address(&owner, &mint).to_string());
+
+        // Re-deriving with the returned bump takes the create_program_address path.
+        let bump = body["data"]["bump"].clone();
+        let (status, again) = into_parts(derive_pda(request(json!({
+            "programId": spl_associated_token_account::id().to_string(),
+            "seeds": [
+                { "pubkey": owner.to_string() },
+                { "pubkey": spl_token::id().to_string() },
+                { "pubkey": usdc }
+            ],
+            "bump": bump
+        }))).await).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(again["data"], body["data"]);
+
+        let program_id = Pubkey::new_unique();
+        let (status, body) = into_parts(derive_pda(request(json!({
+            "programId": program_id.to_string(),
+            "seeds": [{ "u64le": "42" }]
+        }))).await).await;
+        assert_eq!(status, StatusCode::OK);
+        let (expected, bump) = Pubkey::find_program_address(&[&42u64.to_le_bytes()], &program_id);
+        assert_eq!(body["data"]["address"], expected.to_string());
+        assert_eq!(body["data"]["bump"], bump);
+    }
+
+    #[tokio::test]
+    async fn validate_address_distinguishes_wallets_from_pdas() {
+        let validate = |address: String| validate_address(request(json!({ "address": address })));
+
+        let wallet = Keypair::new().pubkey().to_string();
+        let (status, body) = into_parts(validate(format!(" {}\n", wallet)).await).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["data"], json!({
+            "valid": true,
+            "address": wallet,
+            "bytes_len": 32,
+            "on_curve": true
+        }));
+
+        let (pda, _) = Pubkey::find_program_address(&[b"vault"], &spl_token::id());
+        let (_, body) = into_parts(validate(pda.to_string()).await).await;
+        assert_eq!(body["data"]["valid"], true);
+        assert_eq!(body["data"]["on_curve"], false);
+
+        let (_, body) = into_parts(validate(wallet[..wallet.len() - 4].to_string()).await).await;
+        assert_eq!(body["data"]["valid"], false);
+        assert_eq!(body["data"]["on_curve"], serde_json::Value::Null);
+        assert!(body["data"]["reason"].as_str().unwrap().ends_with("bytes, expected 32"));
+
+        let (_, body) = into_parts(validate("0OIl".to_string()).await).await;
+        assert_eq!(body["data"]["valid"], false);
+        assert_eq!(body["data"]["bytes_len"], serde_json::Value::Null);
+        assert!(body["data"]["reason"].as_str().unwrap().starts_with("not valid base58:"));
+    }
+
+    #[tokio::test]
+    async fn derive_pda_reports_invalid_seeds() {
+        let metaplex = "metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s";
+        let derive = |seeds: serde_json::Value, bump: Option<u8>| derive_pda(request(json!({
+            "programId": metaplex,
+            "seeds": seeds,
+            "bump": bump
+        })));
+
+        // Bump 0 for the USDC metadata seeds lands on the curve.
+        let (status, body) = into_parts(derive(json!([
+            { "string": "metadata" },
+            { "pubkey": metaplex },
+            { "pubkey": "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v" }
+        ]), Some(0)).await).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(
+            body["error"],
+            "seeds with bump 0 produce a point on the ed25519 curve, which is not a valid program address"
+        );
+
+        let (status, _) = into_parts(derive(json!(vec![json!({ "u64le": 1 }); 15]), None).await).await;
+        assert_eq!(status, StatusCode::OK);
+        let (status, body) = into_parts(derive(json!(vec![json!({ "u64le": 1 }); 16]), None).await).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["error"], "seeds must contain at most 15 entries (16 including the bump), got 16");
+
+        let (status, body) = into_parts(derive(json!([{ "u64le": 1 }, { "string": "x".repeat(33) }]), None).await).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["error"], "seeds[1] must be at most 32 bytes, got 33");
+
+        let (status, body) = into_parts(derive(json!([{ "bytesBase64": "%%" }]), None).await).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert!(body["error"].as_str().unwrap().starts_with("Invalid seeds[0].bytesBase64:"));
+
+        let (status, body) = into_parts(derive(json!([{ "pubkey": "nope" }]), None).await).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert!(body["error"].as_str().unwrap().starts_with("Invalid seeds[0].pubkey"));
+    }
+
+    #[tokio::test]
+    async fn address_with_seed_matches_known_vectors() {
+        let derive = |seed: &str| address_with_seed(request(json!({
+            "base": Pubkey::default().to_string(),
+            "seed": seed,
+            "owner": Pubkey::default().to_string()
+        })));
+
+        let (status, body) = into_parts(derive("limber chicken: 4/45").await).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["data"]["address"], "9h1HyLCW5dZnBVap8C5egQ9Z6pHyjsh5MNy83iPqqRuq");
+
+        // Eight 4-byte characters is exactly MAX_SEED_LEN; one more byte is not.
+        let (status, _) = into_parts(derive(&"\u{10FFFF}".repeat(8)).await).await;
+        assert_eq!(status, StatusCode::OK);
+        let (status, body) = into_parts(derive(&format!("x{}", "\u{10FFFF}".repeat(8))).await).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["error"], "seed must be at most 32 bytes, got 33");
+
+        let (status, body) = into_parts(address_with_seed(request(json!({
+            "base": Pubkey::default().to_string(),
+            "seed": "a",
+            "owner": "bogus"
+        }))).await).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert!(body["error"].as_str().unwrap().starts_with("Invalid owner:"));
+    }
+
+    #[tokio::test]
+    async fn build_memo_decodes_base64_and_lists_signers() {
+        let signer = Pubkey::new_unique();
+        let memo = "order 1138 ✓";
+
+        let (status, body) = into_parts(build_memo(request(json!({
+            "message": BASE64.encode(memo),
+            "encoding": "base64",
+            "signers": [signer.to_string()]
+        }))).await).await;
+        let expected = spl_memo::build_memo(memo.as_bytes(), &[&signer]);
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["data"]["program_id"], spl_memo::id().to_string());
+        assert_eq!(body["data"]["instruction_data"], BASE64.encode(&expected.data));
+        assert_eq!(body["data"]["length"], memo.len());
+        assert_eq!(body["data"]["accounts"][0]["is_signer"], true);
+
+        let (status, body) = into_parts(build_memo(request(json!({
+            "message": BASE64.encode([0xff, 0xfe]),
+            "encoding": "base64"
+        }))).await).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["error"], "message must decode to valid UTF-8");
+
+        let (status, body) = into_parts(build_memo(request(json!({
+            "message": "hi",
+            "encoding": "utf16"
+        }))).await).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["error"], "Invalid encoding: \"utf16\", expected one of: utf8, base64, hex");
+    }
+
+    #[tokio::test]
+    async fn compute_budget_orders_limit_before_price() {
+        let (status, body) = into_parts(compute_budget(request(json!({
+            "microLamports": "25000",
+            "unitLimit": 200_000
+        }))).await).await;
+        assert_eq!(status, StatusCode::OK);
+        let ixs = body["data"]["instructions"].as_array().unwrap();
+        assert_eq!(ixs.len(), 2);
+        assert_eq!(ixs[0]["program_id"], solana_sdk::compute_budget::id().to_string());
+        assert_eq!(
+            ixs[0]["instruction_data"],
+            BASE64.encode(ComputeBudgetInstruction::set_compute_unit_limit(200_000).data)
+        );
+        assert_eq!(
+            ixs[1]["instruction_data"],
+            BASE64.encode(ComputeBudgetInstruction::set_compute_unit_price(25_000).data)
+        );
+
+        let (_, body) = into_parts(compute_budget(request(json!({ "microLamports": 1 }))).await).await;
+        assert_eq!(body["data"]["instructions"].as_array().unwrap().len(), 1);
+
+        let (status, body) = into_parts(compute_budget(request(json!({ "unitLimit": 1_400_001 }))).await).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["error"], "unitLimit must be at most 1400000, got 1400001");
+
+        let (status, _) = into_parts(compute_budget(request(json!({}))).await).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn create_and_delegate_stake_requires_rent_plus_minimum() {
+        let payer = Pubkey::new_unique();
+        let stake = Pubkey::new_unique();
+        let staker = Pubkey::new_unique();
+        let withdrawer = Pubkey::new_unique();
+        let vote = Pubkey::new_unique();
+        let rent = Rent::default().minimum_balance(StakeStateV2::size_of());
+        let body_for = |lamports: u64| json!({
+            "payer": payer.to_string(),
+            "stakeAccount": stake.to_string(),
+            "staker": staker.to_string(),
+            "withdrawer": withdrawer.to_string(),
+            "lamports": lamports,
+            "voteAccount": vote.to_string()
+        });
+
+        let (status, body) = into_parts(create_and_delegate_stake(request(body_for(rent + 1))).await).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["data"]["space"], StakeStateV2::size_of());
+        assert_eq!(body["data"]["rent_exempt_reserve"], rent);
+        let expected = stake_instruction::create_account_and_delegate_stake(
+            &payer, &stake, &vote, &Authorized { staker, withdrawer }, &Lockup::default(), rent + 1,
+        );
+        let ixs = body["data"]["instructions"].as_array().unwrap();
+        assert_eq!(ixs.len(), 3);
+        for (ix, expected) in ixs.iter().zip(&expected) {
+            assert_eq!(ix["program_id"], expected.program_id.to_string());
+            assert_eq!(ix["instruction_data"], BASE64.encode(&expected.data));
+        }
+
+        let (status, _) = into_parts(create_and_delegate_stake(request(body_for(rent))).await).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn deactivate_and_withdraw_stake_match_sdk_metas() {
+        let stake = Pubkey::new_unique();
+        let authority = Pubkey::new_unique();
+        let to = Pubkey::new_unique();
+        let custodian = Pubkey::new_unique();
+
+        let (status, body) = into_parts(deactivate_stake(request(json!({
+            "stakeAccount": stake.to_string(),
+            "staker": authority.to_string()
+        }))).await).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(
+            body["data"],
+            instruction_to_json(&stake_instruction::deactivate_stake(&stake, &authority))
+        );
+
+        let (status, body) = into_parts(withdraw_stake(request(json!({
+            "stakeAccount": stake.to_string(),
+            "withdrawer": authority.to_string(),
+            "to": to.to_string(),
+            "lamports": 42,
+            "custodian": custodian.to_string()
+        }))).await).await;
+        assert_eq!(status, StatusCode::OK);
+        let expected = stake_instruction::withdraw(&stake, &authority, &to, 42, Some(&custodian));
+        assert_eq!(body["data"], instruction_to_json(&expected));
+        assert_eq!(body["data"]["accounts"][5]["pubkey"], custodian.to_string());
+        assert_eq!(body["data"]["accounts"][5]["is_signer"], true);
+
+        let (status, body) = into_parts(withdraw_stake(request(json!({
+            "stakeAccount": stake.to_string(),
+            "withdrawer": authority.to_string(),
+            "to": to.to_string(),
+            "lamports": 0
+        }))).await).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["error"], "lamports must be greater than 0");
+    }
+
+    #[tokio::test]
+    async fn split_and_merge_stake_return_ordered_instructions() {
+        let stake = Pubkey::new_unique();
+        let staker = Pubkey::new_unique();
+        let split = Pubkey::new_unique();
+
+        let (status, body) = into_parts(split_stake(request(json!({
+            "stakeAccount": stake.to_string(),
+            "staker": staker.to_string(),
+            "newStakeAccount": split.to_string(),
+            "lamports": "1000000000"
+        }))).await).await;
+        assert_eq!(status, StatusCode::OK);
+        let expected: Vec<_> = stake_instruction::split(&stake, &staker, 1_000_000_000, &split)
+            .iter()
+            .map(instruction_to_json)
+            .collect();
+        assert_eq!(body["data"]["instructions"], json!(expected));
+
+        let (status, body) = into_parts(merge_stake(request(json!({
+            "destination": stake.to_string(),
+            "source": split.to_string(),
+            "staker": staker.to_string()
+        }))).await).await;
+        assert_eq!(status, StatusCode::OK);
+        let expected: Vec<_> = stake_instruction::merge(&stake, &split, &staker)
+            .iter()
+            .map(instruction_to_json)
+            .collect();
+        assert_eq!(body["data"]["instructions"], json!(expected));
+
+        let (status, body) = into_parts(merge_stake(request(json!({
+            "destination": stake.to_string(),
+            "source": stake.to_string(),
+            "staker": staker.to_string()
+        }))).await).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["error"], "source must differ from destination");
+
+        let (status, _) = into_parts(split_stake(request(json!({
+            "stakeAccount": stake.to_string(),
+            "staker": staker.to_string(),
+            "newStakeAccount": split.to_string(),
+            "lamports": 0
+        }))).await).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn authorize_stake_maps_authority_types() {
+        let stake = Pubkey::new_unique();
+        let current = Pubkey::new_unique();
+        let new = Pubkey::new_unique();
+        let custodian = Pubkey::new_unique();
+
+        for (name, kind) in [("staker", StakeAuthorize::Staker), ("withdrawer", StakeAuthorize::Withdrawer)] {
+            let (status, body) = into_parts(authorize_stake(request(json!({
+                "stakeAccount": stake.to_string(),
+                "currentAuthority": current.to_string(),
+                "newAuthority": new.to_string(),
+                "authorityType": name
+            }))).await).await;
+            assert_eq!(status, StatusCode::OK);
+            let expected = stake_instruction::authorize(&stake, &current, &new, kind, None);
+            assert_eq!(body["data"], instruction_to_json(&expected));
+        }
+
+        let (_, body) = into_parts(authorize_stake(request(json!({
+            "stakeAccount": stake.to_string(),
+            "currentAuthority": current.to_string(),
+            "newAuthority": new.to_string(),
+            "authorityType": "withdrawer",
+            "custodian": custodian.to_string()
+        }))).await).await;
+        assert_eq!(body["data"]["accounts"][3]["pubkey"], custodian.to_string());
+        assert_eq!(body["data"]["accounts"][3]["is_signer"], true);
+
+        let (status, body) = into_parts(authorize_stake(request(json!({
+            "stakeAccount": stake.to_string(),
+            "currentAuthority": current.to_string(),
+            "newAuthority": new.to_string(),
+            "authorityType": "voter"
+        }))).await).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["error"], "Invalid authorityType: \"voter\", expected one of: staker, withdrawer");
+    }
+
+    #[tokio::test]
+    async fn lookup_table_create_and_chunked_extend() {
+        let authority = Pubkey::new_unique();
+        let payer = Pubkey::new_unique();
+
+        let (status, body) = into_parts(create_lookup_table(request(json!({
+            "authority": authority.to_string(),
+            "payer": payer.to_string(),
+            "recentSlot": "123456"
+        }))).await).await;
+        let (expected, table) = lookup_table_instruction::create_lookup_table(authority, payer, 123_456);
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["data"]["lookup_table_address"], table.to_string());
+        assert_eq!(body["data"]["instruction_data"], BASE64.encode(&expected.data));
+
+        let addresses: Vec<Pubkey> = (0..45).map(|_| Pubkey::new_unique()).collect();
+        let (status, body) = into_parts(extend_lookup_table(request(json!({
+            "lookupTable": table.to_string(),
+            "authority": authority.to_string(),
+            "payer": payer.to_string(),
+            "addresses": addresses.iter().map(|a| a.to_string()).collect::<Vec<_>>()
+        }))).await).await;
+        assert_eq!(status, StatusCode::OK);
+        let ixs = body["data"]["instructions"].as_array().unwrap();
+        assert_eq!(ixs.len(), 2);
+        let first = lookup_table_instruction::extend_lookup_table(table, authority, Some(payer), addresses[..30].to_vec());
+        let second = lookup_table_instruction::extend_lookup_table(table, authority, Some(payer), addresses[30..].to_vec());
+        assert_eq!(ixs[0], instruction_to_json(&first));
+        assert_eq!(ixs[1], instruction_to_json(&second));
+
+        let (status, body) = into_parts(extend_lookup_table(request(json!({
+            "lookupTable": table.to_string(),
+            "authority": authority.to_string(),
+            "payer": payer.to_string(),
+            "addresses": [authority.to_string(), "nope"]
+        }))).await).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert!(body["error"].as_str().unwrap().starts_with("Invalid addresses[1]:"));
+    }
+
+    #[tokio::test]
+    async fn lookup_table_deactivate_and_close_metas() {
+        let table = Pubkey::new_unique();
+        let authority = Pubkey::new_unique();
+        let recipient = Pubkey::new_unique();
+
+        let (status, body) = into_parts(deactivate_lookup_table(request(json!({
+            "lookupTable": table.to_string(),
+            "authority": authority.to_string()
+        }))).await).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["data"]["program_id"], solana_program::address_lookup_table::program::id().to_string());
+        assert_eq!(body["data"]["accounts"], json!([
+            { "pubkey": table.to_string(), "is_signer": false, "is_writable": true },
+            { "pubkey": authority.to_string(), "is_signer": true, "is_writable": false }
+        ]));
+
+        let (status, body) = into_parts(close_lookup_table(request(json!({
+            "lookupTable": table.to_string(),
+            "authority": authority.to_string(),
+            "recipient": recipient.to_string()
+        }))).await).await;
+        assert_eq!(status, StatusCode::OK);
+        let expected = lookup_table_instruction::close_lookup_table(table, authority, recipient);
+        assert_eq!(body["data"], instruction_to_json(&expected));
+
+        let (status, body) = into_parts(close_lookup_table(request(json!({
+            "lookupTable": table.to_string(),
+            "authority": authority.to_string(),
+            "recipient": table.to_string()
+        }))).await).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["error"], "recipient must differ from lookupTable");
+    }
+
+    #[tokio::test]
+    async fn set_upgrade_authority_requires_confirmation_for_immutable() {
+        let program = Pubkey::new_unique();
+        let current = Pubkey::new_unique();
+        let new = Pubkey::new_unique();
+        let (program_data, _) =
+            Pubkey::find_program_address(&[program.as_ref()], &bpf_loader_upgradeable::id());
+
+        let (status, body) = into_parts(set_upgrade_authority(request(json!({
+            "programId": program.to_string(),
+            "currentAuthority": current.to_string(),
+            "newAuthority": new.to_string()
+        }))).await).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["data"]["program_data_address"], program_data.to_string());
+        assert_eq!(body["data"]["accounts"][0]["pubkey"], program_data.to_string());
+        assert_eq!(body["data"]["accounts"][2]["pubkey"], new.to_string());
+
+        let immutable = json!({
+            "programId": program.to_string(),
+            "currentAuthority": current.to_string(),
+            "newAuthority": null
+        });
+        let (status, _) = into_parts(set_upgrade_authority(request(immutable.clone())).await).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+
+        let mut confirmed = immutable;
+        confirmed["confirmImmutable"] = json!(true);
+        let (status, body) = into_parts(set_upgrade_authority(request(confirmed)).await).await;
+        assert_eq!(status, StatusCode::OK);
+        let expected = bpf_loader_upgradeable::set_upgrade_authority(&program, &current, None);
+        assert_eq!(body["data"]["accounts"], instruction_to_json(&expected)["accounts"]);
+    }
+
+    #[tokio::test]
+    async fn ed25519_verify_instruction_checks_signature_from_message_sign() {
+        let keypair = Keypair::new();
+        let secret = bs58::encode(&keypair.to_bytes()[..32]).into_string();
+        let (_, signed) = into_parts(sign_message_with_ed25519(State(Keystore::default()), request(json!({
+            "message": "attest: 42",
+            "secret": secret
+        }))).await).await;
+
+        let (status, body) = into_parts(ed25519_verify_instruction(request(json!({
+            "message": "attest: 42",
+            "signature": signed["data"]["signature"],
+            "publicKey": signed["data"]["public_key"]
+        }))).await).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["data"]["program_id"], solana_sdk::ed25519_program::id().to_string());
+        assert_eq!(body["data"]["offsets"]["message_data_offset"], 112);
+
+        let data = BASE64.decode(body["data"]["instruction_data"].as_str().unwrap()).unwrap();
+        let feature_set = solana_sdk::feature_set::FeatureSet::default();
+        assert!(solana_sdk::ed25519_instruction::verify(&data, &[&data], &feature_set).is_ok());
+
+        let dalek = ed25519_dalek::Keypair::from_bytes(&keypair.to_bytes()).unwrap();
+        let expected = solana_sdk::ed25519_instruction::new_ed25519_instruction(&dalek, b"attest: 42");
+        assert_eq!(data, expected.data);
+
+        let (status, body) = into_parts(ed25519_verify_instruction(request(json!({
+            "message": "attest: 42",
+            "signature": BASE64.encode([0u8; 63]),
+            "publicKey": signed["data"]["public_key"]
+        }))).await).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["error"], "signature must be 64 bytes, got 63");
+    }
+
+    #[tokio::test]
+    async fn secp256k1_verify_instruction_matches_sdk_layout() {
+        use sha3::{Digest, Keccak256};
+
+        let secret = libsecp256k1::SecretKey::parse(&[7u8; 32]).unwrap();
+        let eth_address = solana_sdk::secp256k1_instruction::construct_eth_pubkey(
+            &libsecp256k1::PublicKey::from_secret_key(&secret),
+        );
+        let message = b"withdraw 10 to 0xabc";
+        let hash: [u8; 32] = Keccak256::digest(message).into();
+        let (signature, recovery_id) = libsecp256k1::sign(&libsecp256k1::Message::parse(&hash), &secret);
+
+        let (status, body) = into_parts(secp256k1_verify_instruction(request(json!({
+            "message": BASE64.encode(message),
+            "signature": BASE64.encode(signature.serialize()),
+            "recoveryId": recovery_id.serialize(),
+            "ethAddress": format!("0x{}", hex::encode(eth_address))
+        }))).await).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["data"]["program_id"], solana_sdk::secp256k1_program::id().to_string());
+
+        let data = BASE64.decode(body["data"]["instruction_data"].as_str().unwrap()).unwrap();
+        let expected = solana_sdk::secp256k1_instruction::new_secp256k1_instruction(&secret, message);
+        assert_eq!(data, expected.data);
+        let feature_set = solana_sdk::feature_set::FeatureSet::all_enabled();
+        assert!(solana_sdk::secp256k1_instruction::verify(&data, &[&data], &feature_set).is_ok());
+
+        let (status, body) = into_parts(secp256k1_verify_instruction(request(json!({
+            "message": BASE64.encode(message),
+            "signature": BASE64.encode(signature.serialize()),
+            "recoveryId": 0,
+            "ethAddress": hex::encode(&eth_address[..19])
+        }))).await).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["error"], "ethAddress must be 20 bytes, got 19");
+    }
+
+    #[tokio::test]
+    async fn keypair_from_seed_is_deterministic_across_encodings() {
+        let seed = [9u8; 32];
+        let expected = solana_sdk::signer::keypair::keypair_from_seed(&seed).unwrap();
+
+        let (status, body) = into_parts(keypair_from_seed(request(json!({
+            "seed": hex::encode(seed)
+        }))).await).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["data"], keypair_json(&expected, SecretFormatParam::Base58));
+
+        let (_, body) = into_parts(keypair_from_seed(request(json!({
+            "seed": bs58::encode(seed).into_string(),
+            "encoding": "base58"
+        }))).await).await;
+        assert_eq!(body["data"]["pubkey"], expected.pubkey().to_string());
+
+        let (status, body) = into_parts(keypair_from_seed(request(json!({
+            "seed": hex::encode([1u8; 31])
+        }))).await).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["error"], "seed must be 32 bytes, got 31");
+    }
+
+    const TEST_MNEMONIC: &str =
+        "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+
+    #[tokio::test]
+    async fn keypair_from_mnemonic_matches_solana_keygen() {
+        let (status, body) = into_parts(keypair_from_mnemonic(request(json!({
+            "mnemonic": TEST_MNEMONIC,
+            "passphrase": "hunter2"
+        }))).await).await;
+        let expected =
+            solana_sdk::signer::keypair::keypair_from_seed_phrase_and_passphrase(TEST_MNEMONIC, "hunter2").unwrap();
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["data"], keypair_json(&expected, SecretFormatParam::Base58));
+
+        let (_, body) = into_parts(keypair_from_mnemonic(request(json!({
+            "mnemonic": TEST_MNEMONIC,
+            "derivationPath": "m/44'/501'/0'/0'"
+        }))).await).await;
+        let seed = solana_sdk::signer::keypair::generate_seed_from_seed_phrase_and_passphrase(TEST_MNEMONIC, "");
+        let expected = solana_sdk::signer::keypair::keypair_from_seed_and_derivation_path(
+            &seed,
+            Some(solana_sdk::derivation_path::DerivationPath::new_bip44(Some(0), Some(0))),
+        ).unwrap();
+        assert_eq!(body["data"], keypair_json(&expected, SecretFormatParam::Base58));
+
+        let (status, body) = into_parts(keypair_from_mnemonic(request(json!({
+            "mnemonic": TEST_MNEMONIC.replace("about", "abandon")
+        }))).await).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["error"], "mnemonic checksum is invalid");
+
+        let (status, body) = into_parts(keypair_from_mnemonic(request(json!({
+            "mnemonic": TEST_MNEMONIC.replacen("abandon", "abandonn", 1)
+        }))).await).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["error"], "mnemonic word 1 (\"abandonn\") is not in the BIP39 English word list");
+    }
+
+    #[tokio::test]
+    async fn generate_mnemonic_round_trips_through_from_mnemonic() {
+        let (status, body) = into_parts(generate_mnemonic(Bytes::from(json!({ "wordCount": 24 }).to_string())).await).await;
+        assert_eq!(status, StatusCode::OK);
+        let phrase = body["data"]["mnemonic"].as_str().unwrap();
+        assert_eq!(phrase.split(' ').count(), 24);
+
+        let (_, recovered) = into_parts(keypair_from_mnemonic(request(json!({ "mnemonic": phrase }))).await).await;
+        assert_eq!(recovered["data"]["pubkey"], body["data"]["pubkey"]);
+        assert_eq!(recovered["data"]["secret"], body["data"]["secret"]);
+
+        let (_, body) = into_parts(generate_mnemonic(Bytes::new()).await).await;
+        assert_eq!(body["data"]["mnemonic"].as_str().unwrap().split(' ').count(), 12);
+
+        let (status, body) = into_parts(generate_mnemonic(Bytes::from(json!({ "wordCount": 18 }).to_string())).await).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["error"], "wordCount must be 12 or 24, got 18");
+    }
+
+    #[test]
+    fn derive_keypair_matches_slip10_ed25519_vectors() {
+        // SLIP-0010 test vector 1 for ed25519.
+        let seed = hex::decode("000102030405060708090a0b0c0d0e0f").unwrap();
+        for (path, secret, public) in [
+            (
+                "m/0'/1'",
+                "b1d0bad404bf35da785a64ca1ac54b2617211d2777696fbffaf208f746ae84f2",
+                "1932a5270f335bed617d5b935c80aedb1a35bd9fc1e31acafd5372c30f5c1187",
+            ),
+            (
+                "m/0'/1'/2'/2'/1000000000'",
+                "8f94d394a8e8fd6b1bc2f3f49f5c47e385281d5c17e65324b0f62483e37e8793",
+                "3c24da049451555d51a7014a37337aa4e12d41e485abccfa46b47dfb2af54b7a",
+            ),
+        ] {
+            let keypair = derive_keypair(&seed, Some(path)).ok().unwrap();
+            assert_eq!(hex::encode(&keypair.to_bytes()[..32]), secret, "{}", path);
+            assert_eq!(hex::encode(keypair.pubkey().to_bytes()), public, "{}", path);
+        }
+
+        let err = parse_derivation_path("m/44'/501'/0'/0").err().unwrap();
+        assert_eq!(err.status, StatusCode::BAD_REQUEST);
+        assert!(err.message.starts_with("derivationPath segment 4 (\"0\") is not hardened"), "{}", err.message);
+    }
+
+    #[tokio::test]
+    async fn keypair_from_mnemonic_account_index_shorthand() {
+        let (_, by_index) = into_parts(keypair_from_mnemonic(request(json!({
+            "mnemonic": TEST_MNEMONIC,
+            "accountIndex": 3
+        }))).await).await;
+        let (_, by_path) = into_parts(keypair_from_mnemonic(request(json!({
+            "mnemonic": TEST_MNEMONIC,
+            "derivationPath": "m/44'/501'/3'/0'"
+        }))).await).await;
+        assert_eq!(by_index["data"], by_path["data"]);
+
+        let seed = solana_sdk::signer::keypair::generate_seed_from_seed_phrase_and_passphrase(TEST_MNEMONIC, "");
+        let expected = solana_sdk::signer::keypair::keypair_from_seed_and_derivation_path(
+            &seed,
+            Some(solana_sdk::derivation_path::DerivationPath::new_bip44(Some(3), Some(0))),
+        ).unwrap();
+        assert_eq!(by_index["data"], keypair_json(&expected, SecretFormatParam::Base58));
+
+        let (status, body) = into_parts(keypair_from_mnemonic(request(json!({
+            "mnemonic": TEST_MNEMONIC,
+            "accountIndex": HARDENED_OFFSET
+        }))).await).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["error"], "accountIndex must be below 2147483648, got 2147483648");
+    }
+
+    #[tokio::test]
+    async fn generate_keypair_supports_batches() {
+        let (status, body) = into_parts(generate_new_keypair(State(None), Bytes::new()).await).await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(body["data"]["pubkey"].is_string());
+        assert!(body["data"]["keypairs"].is_null());
+
+        let (status, body) = into_parts(generate_new_keypair(State(None), Bytes::from(r#"{"count": 37}"#)).await).await;
+        assert_eq!(status, StatusCode::OK);
+        let keypairs = body["data"]["keypairs"].as_array().unwrap();
+        assert_eq!(keypairs.len(), 37);
+        let unique: std::collections::HashSet<_> = keypairs.iter().map(|k| k["pubkey"].to_string()).collect();
+        assert_eq!(unique.len(), 37);
+
+        let (status, body) = into_parts(generate_new_keypair(State(None), Bytes::from(r#"{"count": 1001}"#)).await).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["error"], "count must be between 1 and 1000, got 1001");
+    }
+
+    #[tokio::test]
+    async fn keypair_secret_formats_round_trip() {
+        let secret_bytes = |secret: &serde_json::Value, format: &str| -> Vec<u8> {
+            match format {
+                "base58" => bs58::decode(secret.as_str().unwrap()).into_vec().unwrap(),
+                "hex" => hex::decode(secret.as_str().unwrap()).unwrap(),
+                _ => serde_json::from_value(secret.clone()).unwrap(),
+            }
+        };
+
+        for format in ["base58", "jsonArray", "hex"] {
+            let body = Bytes::from(json!({ "format": format }).to_string());
+            let (status, body) = into_parts(generate_new_keypair(State(None), body).await).await;
+            assert_eq!(status, StatusCode::OK);
+            let keypair = Keypair::from_bytes(&secret_bytes(&body["data"]["secret"], format)).unwrap();
+            assert_eq!(body["data"]["pubkey"], keypair.pubkey().to_string());
+
+            let (_, body) = into_parts(keypair_from_seed(request(json!({
+                "seed": hex::encode([3u8; 32]),
+                "format": format
+            }))).await).await;
+            let keypair = Keypair::from_bytes(&secret_bytes(&body["data"]["secret"], format)).unwrap();
+            assert_eq!(body["data"]["pubkey"], keypair.pubkey().to_string());
+
+            let (_, body) = into_parts(keypair_from_mnemonic(request(json!({
+                "mnemonic": TEST_MNEMONIC,
+                "format": format
+            }))).await).await;
+            let keypair = Keypair::from_bytes(&secret_bytes(&body["data"]["secret"], format)).unwrap();
+            assert_eq!(body["data"]["pubkey"], keypair.pubkey().to_string());
+        }
+
+        let (_, body) = into_parts(generate_new_keypair(State(None), Bytes::from(r#"{"format": "jsonArray"}"#)).await).await;
+        assert_eq!(body["data"]["secret"].as_array().unwrap().len(), 64);
+
+        let (status, body) = into_parts(generate_new_keypair(State(None), Bytes::from(r#"{"format": "pem"}"#)).await).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["error"], "Invalid format: \"pem\", expected one of: base58, jsonArray, hex");
+    }
+
+    #[tokio::test]
+    async fn verify_keypair_detects_secret_length() {
+        let keypair = Keypair::new();
+        let bytes = keypair.to_bytes();
+
+        let (status, body) = into_parts(verify_keypair(request(json!({
+            "secret": bs58::encode(bytes).into_string()
+        }))).await).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["data"], json!({ "pubkey": keypair.pubkey().to_string(), "format": "solana_keypair" }));
+
+        let (_, body) = into_parts(verify_keypair(request(json!({
+            "secret": hex::encode(&bytes[..32]),
+            "secretEncoding": "hex"
+        }))).await).await;
+        assert_eq!(body["data"], json!({ "pubkey": keypair.pubkey().to_string(), "format": "ed25519_seed" }));
+
+        let mut mismatched = bytes;
+        mismatched[32..].copy_from_slice(Pubkey::new_unique().as_ref());
+        let (status, body) = into_parts(verify_keypair(request(json!({
+            "secret": bs58::encode(mismatched).into_string()
+        }))).await).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert!(body["error"].as_str().unwrap().contains("embeds public key"));
+
+        let (status, body) = into_parts(verify_keypair(request(json!({
+            "secret": hex::encode([1u8; 48]),
+            "secretEncoding": "hex"
+        }))).await).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["error"], "secret must be a 32-byte ed25519 seed or a 64-byte Solana keypair, got 48 bytes");
+    }
+
+    #[tokio::test]
+    async fn sign_message_accepts_keypairs_from_keypair_endpoint() {
+        let (_, generated) = into_parts(generate_new_keypair(State(None), Bytes::new()).await).await;
+
+        let (status, body) = into_parts(sign_message_with_ed25519(State(Keystore::default()), request(json!({
+            "message": "hello",
+            "secret": generated["data"]["secret"]
+        }))).await).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["data"]["public_key"], generated["data"]["pubkey"]);
+
+        let signature = solana_sdk::signature::Signature::try_from(
+            BASE64.decode(body["data"]["signature"].as_str().unwrap()).unwrap().as_slice(),
+        ).unwrap();
+        let pubkey = Pubkey::from_str(generated["data"]["pubkey"].as_str().unwrap()).unwrap();
+        assert!(signature.verify(pubkey.as_ref(), b"hello"));
+
+        let (status, body) = into_parts(sign_message_with_ed25519(State(Keystore::default()), request(json!({
+            "message": "hello",
+            "secret": bs58::encode([5u8; 40]).into_string()
+        }))).await).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["error"], "secret must be a 32-byte ed25519 seed or a 64-byte Solana keypair, got 40 bytes");
+    }
+
+    #[test]
+    fn decode_secret_handles_each_encoding() {
+        let bytes: Vec<u8> = (0..64).collect();
+        let decode = |value: &str, encoding: Option<&str>| decode_secret("secret", value, encoding);
+
+        assert_eq!(decode(&bs58::encode(&bytes).into_string(), None).map(|s| s.to_vec()).ok(), Some(bytes.clone()));
+        assert_eq!(decode(&bs58::encode(&bytes).into_string(), Some("base58")).map(|s| s.to_vec()).ok(), Some(bytes.clone()));
+        assert_eq!(decode(&hex::encode(&bytes), Some("hex")).map(|s| s.to_vec()).ok(), Some(bytes.clone()));
+        assert_eq!(decode(&BASE64.encode(&bytes), Some("base64")).map(|s| s.to_vec()).ok(), Some(bytes.clone()));
+
+        // "abcd" is valid in base58 and hex alike; the declared encoding decides.
+        assert_eq!(decode("abcd", Some("hex")).map(|s| s.to_vec()).ok(), Some(vec![0xab, 0xcd]));
+        assert_ne!(decode("abcd", None).map(|s| s.to_vec()).ok(), Some(vec![0xab, 0xcd]));
+
+        let err = decode("zz", Some("hex")).err().unwrap();
+        assert!(err.message.starts_with("Invalid secret (hex):"), "{}", err.message);
+        let err = decode("0OIl", None).err().unwrap();
+        assert!(err.message.starts_with("Invalid secret (base58):"), "{}", err.message);
+        let err = decode("a", Some("base32")).err().unwrap();
+        assert_eq!(err.message, "Invalid secretEncoding: \"base32\", expected one of: base58, hex, base64");
+
+        let short = decode(&BASE64.encode([1u8; 31]), Some("base64")).ok().unwrap();
+        let err = keypair_from_secret("secret", &short).err().unwrap();
+        assert_eq!(err.message, "secret must be a 32-byte ed25519 seed or a 64-byte Solana keypair, got 31 bytes");
+    }
+
+    #[tokio::test]
+    async fn sign_message_accepts_hex_and_base64_secrets() {
+        let keypair = Keypair::new();
+        for (secret, encoding) in [
+            (hex::encode(keypair.to_bytes()), "hex"),
+            (BASE64.encode(&keypair.to_bytes()[..32]), "base64"),
+        ] {
+            let (status, body) = into_parts(sign_message_with_ed25519(State(Keystore::default()), request(json!({
+                "message": "hello",
+                "secret": secret,
+                "secretEncoding": encoding
+            }))).await).await;
+            assert_eq!(status, StatusCode::OK);
+            assert_eq!(body["data"]["public_key"], keypair.pubkey().to_string());
+        }
+    }
+
+    #[tokio::test]
+    async fn verify_message_round_trips_with_sign() {
+        let keypair = Keypair::new();
+        let (_, signed) = into_parts(sign_message_with_ed25519(State(Keystore::default()), request(json!({
+            "message": "pay 5 SOL",
+            "secret": bs58::encode(keypair.to_bytes()).into_string()
+        }))).await).await;
+        let signature = signed["data"]["signature"].as_str().unwrap();
+        let verify = |message: &str, signature: String, pubkey: String| verify_message(request(json!({
+            "message": message,
+            "signature": signature,
+            "pubkey": pubkey
+        })));
+
+        let (status, body) = into_parts(verify("pay 5 SOL", signature.to_string(), keypair.pubkey().to_string()).await).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["data"]["valid"], true);
+
+        let base58 = bs58::encode(BASE64.decode(signature).unwrap()).into_string();
+        let (_, body) = into_parts(verify("pay 5 SOL", base58, keypair.pubkey().to_string()).await).await;
+        assert_eq!(body["data"]["valid"], true);
+
+        let (_, body) = into_parts(verify("pay 50 SOL", signature.to_string(), keypair.pubkey().to_string()).await).await;
+        assert_eq!(body["data"]["valid"], false);
+
+        let (_, body) = into_parts(verify("pay 5 SOL", signature.to_string(), Pubkey::new_unique().to_string()).await).await;
+        assert_eq!(body["data"]["valid"], false);
+
+        let (status, body) = into_parts(verify("pay 5 SOL", BASE64.encode([0u8; 32]), keypair.pubkey().to_string()).await).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["error"], "signature must be 64 bytes, got 32");
+
+        let (status, body) = into_parts(verify("pay 5 SOL", signature.to_string(), "xyz".to_string()).await).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert!(body["error"].as_str().unwrap().starts_with("Invalid pubkey:"));
+    }
+
+    #[tokio::test]
+    async fn sign_and_verify_binary_messages() {
+        let keypair = Keypair::new();
+        let secret = bs58::encode(keypair.to_bytes()).into_string();
+        let payload = [0u8, 159, 146, 150, 255];
+
+        for (message, encoding) in [(BASE64.encode(payload), "base64"), (hex::encode(payload), "hex")] {
+            let (status, signed) = into_parts(sign_message_with_ed25519(State(Keystore::default()), request(json!({
+                "message": message,
+                "encoding": encoding,
+                "secret": secret
+            }))).await).await;
+            assert_eq!(status, StatusCode::OK);
+            assert_eq!(signed["data"]["encoding"], encoding);
+            assert_eq!(signed["data"]["signature"], BASE64.encode(keypair.sign_message(&payload).as_ref()));
+
+            let (_, verified) = into_parts(verify_message(request(json!({
+                "message": message,
+                "encoding": encoding,
+                "signature": signed["data"]["signature"],
+                "pubkey": keypair.pubkey().to_string()
+            }))).await).await;
+            assert_eq!(verified["data"]["valid"], true);
+        }
+
+        let (status, body) = into_parts(sign_message_with_ed25519(State(Keystore::default()), request(json!({
+            "message": "not hex",
+            "encoding": "hex",
+            "secret": secret
+        }))).await).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert!(body["error"].as_str().unwrap().starts_with("Invalid message:"));
+    }
+
+    #[test]
+    fn encode_signature_formats() {
+        let keypair = Keypair::new();
+        let signature = keypair.sign_message(b"formats");
+
+        let base64 = encode_signature(&signature, SignatureEncodingParam::Base64);
+        assert_eq!(BASE64.decode(&base64).unwrap(), signature.as_ref());
+        let base58 = encode_signature(&signature, SignatureEncodingParam::Base58);
+        assert_eq!(solana_sdk::signature::Signature::from_str(&base58).unwrap(), signature);
+        let hex_sig = encode_signature(&signature, SignatureEncodingParam::Hex);
+        assert_eq!(hex::decode(&hex_sig).unwrap(), signature.as_ref());
+
+        let data = signature_json(&signature, SignatureEncodingParam::Hex);
+        assert_eq!(data["signature"], hex_sig);
+        assert_eq!(data["signature_base58"], base58);
+        assert_eq!(data["signature_base64"], base64);
+    }
+
+    #[tokio::test]
+    async fn sign_message_honours_signature_encoding() {
+        let keypair = Keypair::new();
+        let (_, body) = into_parts(sign_message_with_ed25519(State(Keystore::default()), request(json!({
+            "message": "hello",
+            "secret": bs58::encode(keypair.to_bytes()).into_string(),
+            "signatureEncoding": "base58"
+        }))).await).await;
+        let signature = keypair.sign_message(b"hello");
+        assert_eq!(body["data"]["signature"], signature.to_string());
+        assert_eq!(body["data"]["signature_base64"], BASE64.encode(signature.as_ref()));
+
+        let (_, body) = into_parts(sign_message_with_ed25519(State(Keystore::default()), request(json!({
+            "message": "hello",
+            "secret": bs58::encode(keypair.to_bytes()).into_string()
+        }))).await).await;
+        assert_eq!(body["data"]["signature"], BASE64.encode(signature.as_ref()));
+    }
+
+    #[tokio::test]
+    async fn sign_message_checks_expected_pubkey() {
+        let keypair = Keypair::new();
+        let secret = bs58::encode(keypair.to_bytes()).into_string();
+        let sign = |expected: &str| sign_message_with_ed25519(State(Keystore::default()), request(json!({
+            "message": "hello",
+            "secret": secret,
+            "expectedPubkey": expected
+        })));
+
+        let (status, _) = into_parts(sign(&keypair.pubkey().to_string()).await).await;
+        assert_eq!(status, StatusCode::OK);
+
+        let (status, body) = into_parts(sign(&Pubkey::new_unique().to_string()).await).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["error"], "secret does not correspond to expectedPubkey");
+        assert!(!body.to_string().contains(&secret));
+
+        let (status, body) = into_parts(sign("not-a-pubkey").await).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert!(body["error"].as_str().unwrap().starts_with("Invalid expectedPubkey:"));
+    }
+
+    async fn post_json(uri: &str, body: String) -> (StatusCode, serde_json::Value) {
+        call(&app(AppState::default()), "POST", uri, body).await
+    }
+
+    /// Sends one request through a router, which keeps its state across calls.
+    async fn call(app: &Router, method: &str, uri: &str, body: String) -> (StatusCode, serde_json::Value) {
+        use tower::ServiceExt;
+
+        let request = axum::http::Request::builder()
+            .method(method)
+            .uri(uri)
+            .header("content-type", "application/json")
+            .body(axum::body::Body::from(body))
+            .unwrap();
+        let response = app.clone().oneshot(request).await.unwrap();
+        let status = response.status();
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (status, serde_json::from_slice(&bytes).unwrap_or(serde_json::Value::Null))
+    }
+
+    #[tokio::test]
+    async fn sign_message_rejects_messages_over_the_limit() {
+        let secret = bs58::encode(Keypair::new().to_bytes()).into_string();
+        let body = |len: usize| json!({ "message": "a".repeat(len), "secret": secret }).to_string();
+
+        let (status, _) = post_json("/message/sign", body(DEFAULT_MAX_MESSAGE_BYTES)).await;
+        assert_eq!(status, StatusCode::OK);
+
+        let (status, body) = post_json("/message/sign", body(DEFAULT_MAX_MESSAGE_BYTES + 1)).await;
+        assert_eq!(status, StatusCode::PAYLOAD_TOO_LARGE);
+        assert_eq!(body["error"], "message is 65537 bytes, exceeding the limit of 65536 bytes");
+
+        // Bodies beyond the route's limit are refused before the handler runs.
+        let (status, _) = post_json("/message/sign", "x".repeat(signing_body_limit() + 1)).await;
+        assert_eq!(status, StatusCode::PAYLOAD_TOO_LARGE);
+    }
+
+    #[test]
+    fn secret_wrappers_redact_debug_output() {
+        let bytes = SecretBytes::new(vec![0xde, 0xad, 0xbe, 0xef]);
+        assert_eq!(format!("{:?}", bytes), "SecretBytes([REDACTED; 4 bytes])");
+
+        let request: SignMessageRequest = serde_json::from_value(json!({
+            "message": "hi",
+            "secret": "5ecretMaterial"
+        })).unwrap();
+        let secret = request.signer.secret.unwrap();
+        let debug = format!("{:?}", secret);
+        assert_eq!(debug, "SecretString([REDACTED])");
+        assert!(!debug.contains("5ecretMaterial"));
+        assert_eq!(&*secret, "5ecretMaterial");
+    }
+
+    #[tokio::test]
+    async fn keystore_import_sign_delete() {
+        let app = app(AppState::default());
+        let keypair = Keypair::new();
+        let secret = bs58::encode(keypair.to_bytes()).into_string();
+        let import = |label: &str, secret: &str| {
+            call(&app, "POST", "/keystore/import", json!({ "label": label, "secret": secret }).to_string())
+        };
+        let sign = || call(&app, "POST", "/message/sign", json!({ "message": "hello", "keyLabel": "Alice" }).to_string());
+
+        let (status, body) = import("Alice", &secret).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["data"], json!({ "label": "Alice", "pubkey": keypair.pubkey().to_string() }));
+
+        let (status, body) = import("Alice", &bs58::encode(Keypair::new().to_bytes()).into_string()).await;
+        assert_eq!(status, StatusCode::CONFLICT);
+        assert_eq!(body["error"], "A key with label \"Alice\" already exists");
+        let (status, _) = import("list", &secret).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+
+        let (status, body) = sign().await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["data"]["public_key"], keypair.pubkey().to_string());
+        let signature = decode_signature("signature", body["data"]["signature"].as_str().unwrap()).ok().unwrap();
+        assert!(signature.verify(keypair.pubkey().as_ref(), b"hello"));
+
+        // Lookups are case-sensitive.
+        let (status, body) = call(&app, "POST", "/message/sign", json!({ "message": "hello", "keyLabel": "alice" }).to_string()).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert_eq!(body["error"], "No key with label \"alice\"");
+
+        let (status, body) = call(&app, "POST", "/message/sign", json!({
+            "message": "hello",
+            "keyLabel": "Alice",
+            "secret": secret
+        }).to_string()).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["error"], "provide only one of secret, encryptedSecret or keyLabel");
+
+        let (status, body) = call(&app, "GET", "/keystore/list", String::new()).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["data"]["keys"], json!([{ "label": "Alice", "pubkey": keypair.pubkey().to_string() }]));
+        let listed = body.to_string();
+        let seed = keypair.secret().to_bytes();
+        for encoded in [secret.clone(), bs58::encode(seed).into_string(), hex::encode(seed), BASE64.encode(seed)] {
+            assert!(!listed.contains(&encoded));
+        }
+
+        let (status, body) = call(&app, "DELETE", "/keystore/Alice", String::new()).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["data"]["pubkey"], keypair.pubkey().to_string());
+
+        let (status, body) = sign().await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert_eq!(body["error"], "No key with label \"Alice\"");
+        let (status, _) = call(&app, "DELETE", "/keystore/Alice", String::new()).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        let (_, body) = call(&app, "GET", "/keystore/list", String::new()).await;
+        assert_eq!(body["data"]["keys"], json!([]));
+    }
+
+    #[tokio::test]
+    async fn transfers_sign_with_keystore_keys() {
+        let app = app(AppState::default());
+        let keypair = Keypair::new();
+        let (status, _) = call(&app, "POST", "/keystore/import", json!({
+            "label": "treasury",
+            "secret": bs58::encode(keypair.to_bytes()).into_string()
+        }).to_string()).await;
+        assert_eq!(status, StatusCode::OK);
+        let blockhash = Hash::new_unique();
+        let to = Pubkey::new_unique();
+        let decode_tx = |body: &serde_json::Value| -> Transaction {
+            bincode::deserialize(&BASE64.decode(body["data"]["transaction"].as_str().unwrap()).unwrap()).unwrap()
+        };
+
+        let (status, body) = call(&app, "POST", "/send/sol", json!({
+            "fromKeyLabel": "treasury",
+            "to": to.to_string(),
+            "lamports": 5000,
+            "recentBlockhash": blockhash.to_string()
+        }).to_string()).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["data"]["accounts"][0], keypair.pubkey().to_string());
+        let tx = decode_tx(&body);
+        assert!(tx.verify().is_ok());
+        assert_eq!(tx.message.recent_blockhash, blockhash);
+        assert_eq!(tx.message.account_keys[0], keypair.pubkey());
+        assert_eq!(tx.signatures[0].to_string(), body["data"]["signature"]);
+        assert_eq!(tx.message.instructions.len(), 1);
+
+        // With a memo both instructions land in the transaction.
+        let (status, body) = call(&app, "POST", "/send/sol", json!({
+            "from": keypair.pubkey().to_string(),
+            "fromKeyLabel": "treasury",
+            "to": to.to_string(),
+            "lamports": 5000,
+            "memo": "rent",
+            "recentBlockhash": blockhash.to_string()
+        }).to_string()).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["data"]["instructions"].as_array().unwrap().len(), 2);
+        let tx = decode_tx(&body);
+        assert!(tx.verify().is_ok());
+        assert_eq!(tx.message.instructions.len(), 2);
+
+        // A bad recipient is rejected rather than signed over to the default pubkey.
+        let (status, body) = call(&app, "POST", "/send/sol", json!({
+            "fromKeyLabel": "treasury",
+            "to": "not-a-pubkey",
+            "lamports": 5000,
+            "recentBlockhash": blockhash.to_string()
+        }).to_string()).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert!(body["error"].as_str().unwrap().starts_with("Invalid to"));
+
+        let mint = Pubkey::new_unique();
+        let destination = Pubkey::new_unique();
+        let (status, body) = call(&app, "POST", "/send/token", json!({
+            "destination": destination.to_string(),
+            "mint": mint.to_string(),
+            "ownerKeyLabel": "treasury",
+            "amount": 10,
+            "decimals": 6,
+            "recentBlockhash": blockhash.to_string()
+        }).to_string()).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["data"]["instruction"], "transfer_checked");
+        let tx = decode_tx(&body);
+        assert!(tx.verify().is_ok());
+        let expected = token_instruction::transfer_checked(
+            &spl_token::id(), &get_associated_token_address(&keypair.pubkey(), &mint), &mint, &destination,
+            &keypair.pubkey(), &[], 10, 6,
+        ).unwrap();
+        assert_eq!(tx.message, solana_sdk::message::Message::new_with_blockhash(&[expected], Some(&keypair.pubkey()), &blockhash));
+
+        let (status, body) = call(&app, "POST", "/send/token", json!({
+            "destination": "not-a-pubkey",
+            "mint": mint.to_string(),
+            "ownerKeyLabel": "treasury",
+            "amount": 10,
+            "recentBlockhash": blockhash.to_string()
+        }).to_string()).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert!(body["error"].as_str().unwrap().starts_with("Invalid destination"));
+
+        let (status, body) = call(&app, "POST", "/send/sol", json!({
+            "fromKeyLabel": "payroll",
+            "to": to.to_string(),
+            "lamports": 5000,
+            "recentBlockhash": blockhash.to_string()
+        }).to_string()).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert_eq!(body["error"], "No key with label \"payroll\"");
+
+        let (status, body) = call(&app, "POST", "/send/sol", json!({
+            "fromKeyLabel": "treasury",
+            "to": to.to_string(),
+            "lamports": 5000,
+            "recentBlockhash": "not-a-blockhash"
+        }).to_string()).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert!(body["error"].as_str().unwrap().starts_with("Invalid recentBlockhash:"));
+
+        let cases = [
+            (json!({ "fromKeyLabel": "treasury" }), "recentBlockhash is required with fromKeyLabel"),
+            (json!({ "from": keypair.pubkey().to_string(), "recentBlockhash": blockhash.to_string() }),
+             "recentBlockhash requires fromKeyLabel"),
+            (json!({ "from": to.to_string(), "fromKeyLabel": "treasury", "recentBlockhash": blockhash.to_string() }),
+             "from does not match the key stored under fromKeyLabel \"treasury\""),
+            (json!({}), "from or fromKeyLabel is required"),
+        ];
+        for (fields, expected) in cases {
+            let mut request = json!({ "to": to.to_string(), "lamports": 5000 });
+            request.as_object_mut().unwrap().extend(fields.as_object().unwrap().clone());
+            let (status, body) = call(&app, "POST", "/send/sol", request.to_string()).await;
+            assert_eq!(status, StatusCode::BAD_REQUEST);
+            assert_eq!(body["error"], expected);
+        }
+    }
+
+    #[tokio::test]
+    async fn deterministic_keypairs_repeat_across_instances() {
+        let instance = |seed: u64| app(AppState {
+            test_keys: Some(TestKeypairSource::new(seed)),
+            ..AppState::default()
+        });
+        let sequence = |app: Router| async move {
+            let mut keys = Vec::new();
+            for body in [String::new(), json!({ "count": 3 }).to_string(), String::new()] {
+                let (status, body) = call(&app, "POST", "/keypair", body).await;
+                assert_eq!(status, StatusCode::OK);
+                assert_eq!(body["data"]["deterministic"], true);
+                match body["data"]["keypairs"].as_array() {
+                    Some(batch) => keys.extend(batch.iter().map(|key| key["secret"].clone())),
+                    None => keys.push(body["data"]["secret"].clone()),
+                }
+            }
+            keys
+        };
+
+        let first = sequence(instance(42)).await;
+        assert_eq!(first.len(), 5);
+        assert_eq!(first, sequence(instance(42)).await);
+        assert_ne!(first, sequence(instance(43)).await);
+        // Each draw is a fresh key, not a repeat of the first.
+        assert_eq!(first.iter().collect::<std::collections::HashSet<_>>().len(), 5);
+
+        let (_, body) = post_json("/keypair", String::new()).await;
+        assert_eq!(body["data"].get("deterministic"), None);
+    }
+
+    #[test]
+    fn deterministic_mode_requires_acknowledgement() {
+        assert!(TestKeypairSource::from_env_values(None, None).unwrap().is_none());
+        assert!(TestKeypairSource::from_env_values(None, Some("1")).unwrap().is_none());
+        assert!(TestKeypairSource::from_env_values(Some("7"), Some("1")).unwrap().is_some());
+
+        let err = TestKeypairSource::from_env_values(Some("7"), None).err().unwrap();
+        assert_eq!(
+            err,
+            "KEYPAIR_TEST_SEED is set but KEYPAIR_TEST_SEED_INSECURE_ACK is not \"1\"; deterministic keypairs are predictable and for tests only"
+        );
+        assert!(TestKeypairSource::from_env_values(Some("7"), Some("yes")).is_err());
+        let err = TestKeypairSource::from_env_values(Some("seven"), Some("1")).err().unwrap();
+        assert_eq!(err, "KEYPAIR_TEST_SEED must be an unsigned 64-bit integer, got \"seven\"");
+    }
+
+    #[tokio::test]
+    async fn convert_signature_round_trips_every_encoding() {
+        let signature = Keypair::new().sign_message(b"hello");
+        let expected = json!({
+            "base58": signature.to_string(),
+            "base64": BASE64.encode(signature),
+            "hex": hex::encode(signature),
+            "s_canonical": true
+        });
+        let convert = |value: serde_json::Value| convert_signature(request(value));
+
+        for (encoding, value) in [("base58", &expected["base58"]), ("base64", &expected["base64"]), ("hex", &expected["hex"])] {
+            for explicit in [None, Some(encoding)] {
+                let (status, body) = into_parts(convert(json!({ "signature": value, "encoding": explicit })).await).await;
+                assert_eq!(status, StatusCode::OK);
+                let mut expected = expected.clone();
+                expected["encoding"] = json!(encoding);
+                assert_eq!(body["data"], expected);
+            }
+        }
+
+        // Adding L to a canonical s keeps the signature valid under lenient
+        // verifiers but makes it non-canonical.
+        let mut bytes: [u8; 64] = signature.into();
+        let mut carry = 0u16;
+        for (byte, order) in bytes[32..].iter_mut().zip(ED25519_GROUP_ORDER) {
+            let sum = *byte as u16 + order as u16 + carry;
+            *byte = sum as u8;
+            carry = sum >> 8;
+        }
+        let (status, body) = into_parts(convert(json!({ "signature": hex::encode(bytes) })).await).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["data"]["s_canonical"], false);
+    }
+
+    #[tokio::test]
+    async fn convert_signature_reports_decoded_length() {
+        let convert = |signature: String, encoding: Option<&str>| convert_signature(request(json!({
+            "signature": signature,
+            "encoding": encoding
+        })));
+
+        let (status, body) = into_parts(convert(bs58::encode([1u8; 63]).into_string(), None).await).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["error"], "signature must be 64 bytes, got 63");
+        let (_, body) = into_parts(convert(BASE64.encode([1u8; 65]), None).await).await;
+        assert_eq!(body["error"], "signature must be 64 bytes, got 65");
+        let (_, body) = into_parts(convert(hex::encode([1u8; 32]), Some("hex")).await).await;
+        assert_eq!(body["error"], "signature must be 64 bytes, got 32");
+
+        let (status, body) = into_parts(convert("0OIl".to_string(), Some("base58")).await).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert!(body["error"].as_str().unwrap().starts_with("Invalid signature (base58):"));
+        let (_, body) = into_parts(convert("abc".to_string(), Some("base32")).await).await;
+        assert_eq!(body["error"], "Invalid encoding: \"base32\", expected one of: base64, base58, hex");
+    }
+
+    #[tokio::test]
+    async fn sign_message_multi_signs_in_input_order() {
+        let app = app(AppState::default());
+        let stored = Keypair::new();
+        let (status, _) = call(&app, "POST", "/keystore/import", json!({
+            "label": "attestor",
+            "secret": bs58::encode(stored.to_bytes()).into_string()
+        }).to_string()).await;
+        assert_eq!(status, StatusCode::OK);
+        let plain = Keypair::new();
+        let encrypted = Keypair::new();
+
+        let (status, body) = call(&app, "POST", "/message/sign-multi", json!({
+            "message": "68656c6c6f",
+            "encoding": "hex",
+            "signatureEncoding": "base58",
+            "signers": [
+                { "secret": hex::encode(plain.to_bytes()), "secretEncoding": "hex" },
+                { "keyLabel": "attestor" },
+                { "encryptedSecret": encrypt_secret(&encrypted.to_bytes(), "pw").ok().unwrap(), "passphrase": "pw" }
+            ]
+        }).to_string()).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["data"]["encoding"], "hex");
+        let signatures = body["data"]["signatures"].as_array().unwrap();
+        assert_eq!(signatures.len(), 3);
+        for (entry, keypair) in signatures.iter().zip([&plain, &stored, &encrypted]) {
+            assert_eq!(entry["pubkey"], keypair.pubkey().to_string());
+            assert_eq!(entry["signature"], keypair.sign_message(b"hello").to_string());
+        }
+
+        let (status, body) = call(&app, "POST", "/message/sign-multi", json!({
+            "message": "hello",
+            "signers": [{ "keyLabel": "attestor" }, { "keyLabel": "missing" }]
+        }).to_string()).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert_eq!(body["error"], "signers[1]: No key with label \"missing\"");
+    }
+
+    #[tokio::test]
+    async fn sign_message_multi_rejects_bad_signers() {
+        let secret = bs58::encode(Keypair::new().to_bytes()).into_string();
+        let sign = |signers: serde_json::Value| sign_message_multi(State(Keystore::default()), request(json!({
+            "message": "hello",
+            "signers": signers
+        })));
+
+        let (status, body) = into_parts(sign(json!([{ "secret": secret }, { "secret": bs58::encode([1u8; 40]).into_string() }])).await).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(
+            body["error"],
+            "signers[1]: secret must be a 32-byte ed25519 seed or a 64-byte Solana keypair, got 40 bytes"
+        );
+
+        let (_, body) = into_parts(sign(json!([{ "secret": secret }, {}])).await).await;
+        assert_eq!(body["error"], "signers[1]: secret, encryptedSecret or keyLabel is required");
+
+        let (_, body) = into_parts(sign(json!([])).await).await;
+        assert_eq!(body["error"], "signers must contain between 1 and 16 entries, got 0");
+        let (status, _) = into_parts(sign(json!(vec![json!({ "secret": secret }); 16])).await).await;
+        assert_eq!(status, StatusCode::OK);
+        let (_, body) = into_parts(sign(json!(vec![json!({ "secret": secret }); 17])).await).await;
+        assert_eq!(body["error"], "signers must contain between 1 and 16 entries, got 17");
+    }
+
+    #[tokio::test]
+    async fn instruction_json_round_trips_through_parser() {
+        let from = Pubkey::new_unique();
+        let ix = system_instruction::transfer(&from, &Pubkey::new_unique(), 42);
+        let param: transaction::InstructionParam = serde_json::from_value(instruction_to_json(&ix)).unwrap();
+        assert_eq!(transaction::parse_instruction("ix", &param).ok().unwrap(), ix);
+
+        // camelCase spellings parse to the same instruction.
+        let camel = json!({
+            "programId": ix.program_id.to_string(),
+            "accounts": ix.accounts.iter().map(|meta| json!({
+                "pubkey": meta.pubkey.to_string(),
+                "isSigner": meta.is_signer,
+                "isWritable": meta.is_writable
+            })).collect::<Vec<_>>(),
+            "data": BASE64.encode(&ix.data)
+        });
+        let param: transaction::InstructionParam = serde_json::from_value(camel).unwrap();
+        assert_eq!(transaction::parse_instruction("ix", &param).ok().unwrap(), ix);
+
+        let bad: Vec<transaction::InstructionParam> = serde_json::from_value(json!([
+            instruction_to_json(&ix),
+            { "program_id": ix.program_id.to_string(), "accounts": [{ "pubkey": "nope", "is_signer": false, "is_writable": false }] }
+        ])).unwrap();
+        let err = transaction::parse_instructions("instructions", &bad).err().unwrap();
+        assert!(err.message.starts_with("Invalid instructions[1].accounts[0].pubkey"), "{}", err.message);
+        let err = transaction::parse_instructions("instructions", &[]).err().unwrap();
+        assert_eq!(err.message, "instructions must not be empty");
+    }
+
+    #[tokio::test]
+    async fn build_transaction_compiles_endpoint_output() {
+        let payer = Keypair::new();
+        let from = Keypair::new();
+        let to = Pubkey::new_unique();
+        let blockhash = Hash::new_unique();
+        let (_, transfer) = post_json("/send/sol", json!({
+            "from": from.pubkey().to_string(),
+            "to": to.to_string(),
+            "lamports": 1000,
+            "memo": "invoice 7"
+        }).to_string()).await;
+
+        let (status, body) = post_json("/transaction/build", json!({
+            "instructions": transfer["data"]["instructions"],
+            "feePayer": payer.pubkey().to_string(),
+            "recentBlockhash": blockhash.to_string()
+        }).to_string()).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["data"]["fee_payer"], payer.pubkey().to_string());
+        assert_eq!(body["data"]["required_signers"], json!([payer.pubkey().to_string(), from.pubkey().to_string()]));
+
+        let wire = BASE64.decode(body["data"]["transaction"].as_str().unwrap()).unwrap();
+        let mut tx: Transaction = bincode::deserialize(&wire).unwrap();
+        assert_eq!(tx.message.recent_blockhash, blockhash);
+        assert_eq!(tx.message.account_keys[0], payer.pubkey());
+        assert_eq!(tx.message.instructions.len(), 2);
+        assert!(tx.signatures.iter().all(|sig| *sig == solana_sdk::signature::Signature::default()));
+        tx.try_sign(&[&payer, &from], blockhash).unwrap();
+        assert!(tx.verify().is_ok());
+
+        let (status, body) = post_json("/transaction/build", json!({
+            "instructions": transfer["data"]["instructions"],
+            "feePayer": payer.pubkey().to_string(),
+            "recentBlockhash": "not-base58!"
+        }).to_string()).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert!(body["error"].as_str().unwrap().starts_with("Invalid recentBlockhash:"));
+
+        let (status, body) = post_json("/transaction/build", json!({
+            "instructions": transfer["data"]["instructions"],
+            "feePayer": "payer",
+            "recentBlockhash": blockhash.to_string()
+        }).to_string()).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert!(body["error"].as_str().unwrap().starts_with("Invalid feePayer:"));
+    }
+
+    /// Builds an unsigned transfer (fee payer, then `from`) through /transaction/build.
+    async fn build_transfer(payer: &Pubkey, from: &Pubkey, blockhash: &Hash) -> String {
+        let ix = system_instruction::transfer(from, &Pubkey::new_unique(), 1000);
+        let (status, body) = post_json("/transaction/build", json!({
+            "instructions": [instruction_to_json(&ix)],
+            "feePayer": payer.to_string(),
+            "recentBlockhash": blockhash.to_string()
+        }).to_string()).await;
+        assert_eq!(status, StatusCode::OK);
+        body["data"]["transaction"].as_str().unwrap().to_string()
+    }
+
+    #[tokio::test]
+    async fn sign_transaction_round_trip() {
+        let payer = Keypair::new();
+        let from = Keypair::new();
+        let unsigned = build_transfer(&payer.pubkey(), &from.pubkey(), &Hash::new_unique()).await;
+        let sign = |transaction: &str, secrets: Vec<String>| post_json("/transaction/sign", json!({
+            "transaction": transaction,
+            "secrets": secrets
+        }).to_string());
+
+        let (status, body) = sign(&unsigned, vec![bs58::encode(from.to_bytes()).into_string()]).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["data"]["complete"], false);
+        assert_eq!(body["data"]["missing_signers"], json!([payer.pubkey().to_string()]));
+        // No transaction id until the fee payer has signed.
+        assert_eq!(body["data"]["signature"], json!(null));
+        assert_eq!(body["data"]["signature_base58"], json!(null));
+        assert_eq!(body["data"]["signature_base64"], json!(null));
+
+        // A 32-byte seed works as well as a full keypair.
+        let partial = body["data"]["transaction"].as_str().unwrap().to_string();
+        let (status, body) = sign(&partial, vec![bs58::encode(payer.secret().to_bytes()).into_string()]).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["data"]["complete"], true);
+        assert_eq!(body["data"]["missing_signers"], json!([]));
+
+        let tx: Transaction = bincode::deserialize(&BASE64.decode(body["data"]["transaction"].as_str().unwrap()).unwrap()).unwrap();
+        assert!(tx.verify_with_results().iter().all(|ok| *ok));
+        assert_eq!(body["data"]["signature"], tx.signatures[0].to_string());
+        assert_eq!(body["data"]["signature_base58"], tx.signatures[0].to_string());
+        assert_eq!(body["data"]["signature_base64"], BASE64.encode(tx.signatures[0]));
+
+        // secretEncoding applies to every secret; signatureEncoding picks `signature`.
+        let (status, body) = post_json("/transaction/sign", json!({
+            "transaction": unsigned,
+            "secrets": [hex::encode(payer.to_bytes()), hex::encode(from.secret().to_bytes())],
+            "secretEncoding": "hex",
+            "signatureEncoding": "hex"
+        }).to_string()).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["data"]["complete"], true);
+        assert_eq!(body["data"]["signature"], hex::encode(tx.signatures[0]));
+        assert_eq!(body["data"]["signature_base58"], tx.signatures[0].to_string());
+        let (status, body) = post_json("/transaction/sign", json!({
+            "transaction": unsigned,
+            "secrets": [bs58::encode(payer.to_bytes()).into_string()],
+            "secretEncoding": "hex"
+        }).to_string()).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert!(body["error"].as_str().unwrap().starts_with("Invalid secrets[0] (hex)"), "{}", body["error"]);
+
+        let stranger = Keypair::new();
+        let (status, body) = sign(&unsigned, vec![bs58::encode(stranger.to_bytes()).into_string()]).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(
+            body["error"],
+            format!("secrets[0] ({}) is not a required signer of this transaction", stranger.pubkey())
+        );
+
+        let (status, body) = sign(&BASE64.encode([1u8, 2, 3]), vec![bs58::encode(from.to_bytes()).into_string()]).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert!(body["error"].as_str().unwrap().starts_with("Invalid transaction:"));
+    }
+
+    #[tokio::test]
+    async fn build_v0_transaction_uses_lookup_tables() {
+        use solana_sdk::transaction::VersionedTransaction;
+
+        let payer = Keypair::new();
+        let recipients: Vec<Pubkey> = (0..12).map(|_| Pubkey::new_unique()).collect();
+        let ixs: Vec<_> = recipients
+            .iter()
+            .map(|to| instruction_to_json(&system_instruction::transfer(&payer.pubkey(), to, 1)))
+            .collect();
+        let table = Pubkey::new_unique();
+        let build = |version: Option<&str>, tables: serde_json::Value| post_json("/transaction/build", json!({
+            "instructions": ixs,
+            "feePayer": payer.pubkey().to_string(),
+            "recentBlockhash": Hash::new_unique().to_string(),
+            "version": version,
+            "addressLookupTables": tables
+        }).to_string());
+        let size = |body: &serde_json::Value| BASE64.decode(body["data"]["transaction"].as_str().unwrap()).unwrap().len();
+        let tables = json!([{
+            "accountKey": table.to_string(),
+            "addresses": recipients.iter().map(Pubkey::to_string).collect::<Vec<_>>()
+        }]);
+
+        let (status, legacy) = build(None, json!([])).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(legacy["data"]["version"], "legacy");
+        let (status, v0) = build(Some("v0"), tables.clone()).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(v0["data"]["version"], 0);
+        // 12 recipients cost 32 bytes each as static keys but 1 byte each as
+        // lookups, less the table's own key and lookup header.
+        assert!(size(&v0) + 300 < size(&legacy), "{} vs {}", size(&v0), size(&legacy));
+
+        let (_, decoded) = post_json("/transaction/decode", json!({ "transaction": v0["data"]["transaction"] }).to_string()).await;
+        assert_eq!(decoded["data"]["address_table_lookups"][0]["writable_indexes"], json!((0..12).collect::<Vec<_>>()));
+
+        let (_, signed) = post_json("/transaction/sign", json!({
+            "transaction": v0["data"]["transaction"],
+            "secrets": [bs58::encode(payer.to_bytes()).into_string()]
+        }).to_string()).await;
+        let tx: VersionedTransaction = bincode::deserialize(
+            &BASE64.decode(signed["data"]["transaction"].as_str().unwrap()).unwrap(),
+        ).unwrap();
+        assert!(tx.verify_with_results().iter().all(|ok| *ok));
+
+        let (status, body) = build(None, tables).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["error"], "addressLookupTables requires version \"v0\"");
+        let (status, body) = build(Some("v1"), json!([])).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["error"], "Invalid version: \"v1\", expected one of: legacy, v0");
+        let (status, body) = build(Some("v0"), json!([{ "accountKey": table.to_string(), "addresses": ["x"] }])).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert!(body["error"].as_str().unwrap().starts_with("Invalid addressLookupTables[0].addresses"), "{}", body["error"]);
+    }
+
+    #[tokio::test]
+    async fn transaction_size_breaks_down_the_wire_format() {
+        let payer = Pubkey::new_unique();
+        let recipients: Vec<Pubkey> = (0..30).map(|_| Pubkey::new_unique()).collect();
+        let ixs: Vec<_> = recipients
+            .iter()
+            .map(|to| instruction_to_json(&system_instruction::transfer(&payer, to, 1)))
+            .collect();
+        let inputs = |version: &str| json!({
+            "instructions": ixs,
+            "feePayer": payer.to_string(),
+            "recentBlockhash": Hash::new_unique().to_string(),
+            "version": version,
+            "addressLookupTables": if version == "v0" {
+                json!([{ "accountKey": Pubkey::new_unique().to_string(), "addresses": recipients.iter().map(Pubkey::to_string).collect::<Vec<_>>() }])
+            } else {
+                json!([])
+            }
+        });
+        let sum = |breakdown: &serde_json::Value| breakdown.as_object().unwrap().values().map(|v| v.as_u64().unwrap()).sum::<u64>();
+
+        let (status, legacy) = post_json("/transaction/size", inputs("legacy").to_string()).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(legacy["data"]["limit"], 1232);
+        assert_eq!(legacy["data"]["fits"], false);
+        assert_eq!(legacy["data"]["size"].as_u64().unwrap(), sum(&legacy["data"]["breakdown"]));
+        assert_eq!(legacy["data"]["breakdown"]["account_keys"], 1 + 32 * 32);
+        assert_eq!(legacy["data"]["instruction_data"], 30 * 12);
+
+        // The same transfers fit once the recipients come from a lookup table.
+        let (_, built) = post_json("/transaction/build", inputs("v0").to_string()).await;
+        let (status, v0) = post_json("/transaction/size", json!({ "transaction": built["data"]["transaction"] }).to_string()).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(v0["data"]["version"], 0);
+        assert_eq!(v0["data"]["fits"], true);
+        let size = v0["data"]["size"].as_u64().unwrap();
+        assert_eq!(size as usize, BASE64.decode(built["data"]["transaction"].as_str().unwrap()).unwrap().len());
+        assert_eq!(size, sum(&v0["data"]["breakdown"]));
+
+        let (status, body) = post_json("/transaction/size", json!({ "feePayer": payer.to_string() }).to_string()).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert!(body["error"].as_str().unwrap().starts_with("Invalid request:"));
+    }
+
+    #[tokio::test]
+    async fn transaction_signers_distinguish_missing_from_invalid() {
+        let payer = Keypair::new();
+        let from = Keypair::new();
+        let unsigned = build_transfer(&payer.pubkey(), &from.pubkey(), &Hash::new_unique()).await;
+        let (_, signed) = post_json("/transaction/sign", json!({
+            "transaction": unsigned,
+            "secrets": [bs58::encode(from.to_bytes()).into_string()]
+        }).to_string()).await;
+        let signers = |transaction: String| post_json("/transaction/signers", json!({ "transaction": transaction }).to_string());
+
+        let (status, body) = signers(signed["data"]["transaction"].as_str().unwrap().to_string()).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["data"]["signers"], json!([
+            { "pubkey": payer.pubkey().to_string(), "slot": 0, "status": "missing", "signed": false },
+            { "pubkey": from.pubkey().to_string(), "slot": 1, "status": "valid", "signed": true }
+        ]));
+        assert_eq!(body["data"]["complete"], false);
+
+        // A signature over different bytes sits in the payer's slot.
+        let mut tx: Transaction = bincode::deserialize(
+            &BASE64.decode(signed["data"]["transaction"].as_str().unwrap()).unwrap(),
+        ).unwrap();
+        tx.signatures[0] = payer.sign_message(b"something else");
+        let (_, body) = signers(BASE64.encode(bincode::serialize(&tx).unwrap())).await;
+        assert_eq!(body["data"]["signers"][0]["status"], "invalid");
+        assert_eq!(body["data"]["signers"][1]["status"], "valid");
+        assert_eq!((body["data"]["valid"].clone(), body["data"]["missing"].clone(), body["data"]["invalid"].clone()), (json!(1), json!(0), json!(1)));
+
+        tx.try_sign(&[&payer], tx.message.recent_blockhash).unwrap();
+        let (_, body) = signers(BASE64.encode(bincode::serialize(&tx).unwrap())).await;
+        assert_eq!(body["data"]["complete"], true);
+    }
+
+    #[tokio::test]
+    async fn update_blockhash_drops_stale_signatures() {
+        let payer = Keypair::new();
+        let from = Keypair::new();
+        let old = Hash::new_unique();
+        let unsigned = build_transfer(&payer.pubkey(), &from.pubkey(), &old).await;
+        let (_, signed) = post_json("/transaction/sign", json!({
+            "transaction": unsigned,
+            "secrets": [bs58::encode(from.to_bytes()).into_string()]
+        }).to_string()).await;
+        let update = |transaction: &str, blockhash: String| post_json("/transaction/update-blockhash", json!({
+            "transaction": transaction,
+            "recentBlockhash": blockhash
+        }).to_string());
+
+        let fresh = Hash::new_unique();
+        let (status, body) = update(signed["data"]["transaction"].as_str().unwrap(), fresh.to_string()).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["data"]["previous_blockhash"], old.to_string());
+        assert_eq!(body["data"]["dropped_signatures"], json!([from.pubkey().to_string()]));
+        assert_eq!(body["data"]["missing_signers"], json!([payer.pubkey().to_string(), from.pubkey().to_string()]));
+        assert_eq!(body["data"]["signature"], json!(null));
+
+        let mut tx: Transaction = bincode::deserialize(&BASE64.decode(body["data"]["transaction"].as_str().unwrap()).unwrap()).unwrap();
+        assert_eq!(tx.message.recent_blockhash, fresh);
+        tx.try_sign(&[&payer, &from], fresh).unwrap();
+        assert!(tx.verify().is_ok());
+
+        // Keeping the same blockhash keeps the signature.
+        let (_, body) = update(signed["data"]["transaction"].as_str().unwrap(), old.to_string()).await;
+        assert_eq!(body["data"]["dropped_signatures"], json!([]));
+
+        // Once the fee payer has signed, the transaction id survives an unchanged blockhash.
+        let (_, complete) = post_json("/transaction/sign", json!({
+            "transaction": signed["data"]["transaction"],
+            "secrets": [bs58::encode(payer.to_bytes()).into_string()]
+        }).to_string()).await;
+        let (_, body) = post_json("/transaction/update-blockhash", json!({
+            "transaction": complete["data"]["transaction"],
+            "recentBlockhash": old.to_string(),
+            "signatureEncoding": "base64"
+        }).to_string()).await;
+        assert_eq!(body["data"]["signature"], complete["data"]["signature_base64"]);
+        assert_eq!(body["data"]["signature_base58"], complete["data"]["signature"]);
+
+        let (status, body) = update(&unsigned, "bogus".to_string()).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert!(body["error"].as_str().unwrap().starts_with("Invalid recentBlockhash:"));
+
+        let nonce = Pubkey::new_unique();
+        let ixs = [
+            system_instruction::advance_nonce_account(&nonce, &payer.pubkey()),
+            system_instruction::transfer(&payer.pubkey(), &Pubkey::new_unique(), 1),
+        ];
+        let (_, built) = post_json("/transaction/build", json!({
+            "instructions": ixs.iter().map(instruction_to_json).collect::<Vec<_>>(),
+            "feePayer": payer.pubkey().to_string(),
+            "recentBlockhash": Hash::new_unique().to_string()
+        }).to_string()).await;
+        let (status, body) = update(built["data"]["transaction"].as_str().unwrap(), fresh.to_string()).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert!(body["error"].as_str().unwrap().starts_with("transaction uses a durable nonce"));
+    }
+
+    /// Posts `body` to `uri` as-is and with output "transaction", checking the
+    /// default response is unchanged and the transaction carries `expected`.
+    async fn assert_output_modes(uri: &str, body: serde_json::Value, expected: &[Instruction]) {
+        let (status, plain) = post_json(uri, body.to_string()).await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(plain["data"].get("transaction").is_none());
+
+        let fee_payer = Pubkey::new_unique();
+        let blockhash = Hash::new_unique();
+        let mut with_output = body.clone();
+        with_output["output"] = json!("transaction");
+        with_output["feePayer"] = json!(fee_payer.to_string());
+        with_output["recentBlockhash"] = json!(blockhash.to_string());
+        let (status, wrapped) = post_json(uri, with_output.to_string()).await;
+        assert_eq!(status, StatusCode::OK);
+
+        let data = wrapped["data"].as_object().unwrap();
+        for (key, value) in plain["data"].as_object().unwrap() {
+            assert_eq!(&data[key], value, "{} changed in transaction mode", key);
+        }
+        assert_eq!(data["fee_payer"], fee_payer.to_string());
+        assert_eq!(data["recent_blockhash"], blockhash.to_string());
+        let tx: Transaction = bincode::deserialize(&BASE64.decode(data["transaction"].as_str().unwrap()).unwrap()).unwrap();
+        assert_eq!(tx.message, solana_sdk::message::Message::new_with_blockhash(expected, Some(&fee_payer), &blockhash));
+        assert!(tx.signatures.iter().all(|signature| *signature == solana_sdk::signature::Signature::default()));
+    }
+
+    #[tokio::test]
+    async fn instruction_endpoints_can_return_a_transaction() {
+        let from = Pubkey::new_unique();
+        let to = Pubkey::new_unique();
+        assert_output_modes(
+            "/send/sol",
+            json!({ "from": from.to_string(), "to": to.to_string(), "lamports": 500 }),
+            &[system_instruction::transfer(&from, &to, 500)],
+        ).await;
+        assert_output_modes(
+            "/send/sol",
+            json!({ "from": from.to_string(), "to": to.to_string(), "lamports": 500, "memo": "rent" }),
+            &[system_instruction::transfer(&from, &to, 500), spl_memo::build_memo(b"rent", &[&from])],
+        ).await;
+
+        let destination = Pubkey::new_unique();
+        let mint = Pubkey::new_unique();
+        let owner = Pubkey::new_unique();
+        assert_output_modes(
+            "/send/token",
+            json!({
+                "destination": destination.to_string(),
+                "mint": mint.to_string(),
+                "owner": owner.to_string(),
+                "amount": 42,
+                "decimals": 6
+            }),
+            &[spl_token::instruction::transfer_checked(
+                &spl_token::id(), &get_associated_token_address(&owner, &mint), &mint, &destination, &owner, &[], 42, 6,
+            ).unwrap()],
+        ).await;
+        let source = Pubkey::new_unique();
+        assert_output_modes(
+            "/send/token",
+            json!({
+                "destination": destination.to_string(),
+                "mint": mint.to_string(),
+                "owner": owner.to_string(),
+                "source": source.to_string(),
+                "amount": 42,
+                "decimals": 6
+            }),
+            &[spl_token::instruction::transfer_checked(
+                &spl_token::id(), &source, &mint, &destination, &owner, &[], 42, 6,
+            ).unwrap()],
+        ).await;
+
+        // Bad accounts are rejected before anything is wrapped in a transaction.
+        for (uri, body, field) in [
+            ("/send/sol", json!({ "from": from.to_string(), "to": "not-a-pubkey", "lamports": 500 }), "to"),
+            ("/send/token", json!({
+                "destination": "not-a-pubkey",
+                "mint": mint.to_string(),
+                "owner": owner.to_string(),
+                "amount": 42
+            }), "destination"),
+        ] {
+            let mut body = body;
+            body["output"] = json!("transaction");
+            body["feePayer"] = json!(Pubkey::new_unique().to_string());
+            body["recentBlockhash"] = json!(Hash::new_unique().to_string());
+            let (status, body) = post_json(uri, body.to_string()).await;
+            assert_eq!(status, StatusCode::BAD_REQUEST);
+            assert!(body["error"].as_str().unwrap().starts_with(&format!("Invalid {}", field)), "{}", body["error"]);
+        }
+
+        let authority = Pubkey::new_unique();
+        assert_output_modes(
+            "/token/create",
+            json!({ "mintAuthority": authority.to_string(), "mint": mint.to_string(), "decimals": 6 }),
+            &[spl_token::instruction::initialize_mint(&spl_token::id(), &mint, &authority, None, 6).unwrap()],
+        ).await;
+    }
+
+    #[tokio::test]
+    async fn transaction_output_requires_payer_and_blockhash() {
+        let create = json!({ "mintAuthority": Pubkey::new_unique().to_string(), "mint": Pubkey::new_unique().to_string(), "decimals": 0 });
+        let with = |extra: serde_json::Value| {
+            let mut body = create.clone();
+            body.as_object_mut().unwrap().extend(extra.as_object().unwrap().clone());
+            post_json("/token/create", body.to_string())
+        };
+
+        let (status, body) = with(json!({ "output": "transaction", "recentBlockhash": Hash::new_unique().to_string() })).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["error"], "feePayer is required with output \"transaction\"");
+        let (status, body) = with(json!({ "output": "transaction", "feePayer": Pubkey::new_unique().to_string() })).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["error"], "recentBlockhash is required with output \"transaction\"");
+        let (status, body) = with(json!({ "feePayer": Pubkey::new_unique().to_string() })).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["error"], "feePayer requires output \"transaction\"");
+        let (status, body) = with(json!({ "output": "tx" })).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["error"], "Invalid output: \"tx\", expected one of: instruction, transaction");
+
+        let (status, body) = post_json("/send/sol", json!({
+            "fromKeyLabel": "hot",
+            "to": Pubkey::new_unique().to_string(),
+            "lamports": 1,
+            "output": "transaction",
+            "feePayer": Pubkey::new_unique().to_string(),
+            "recentBlockhash": Hash::new_unique().to_string()
+        }).to_string()).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(
+            body["error"],
+            "fromKeyLabel already returns a signed transaction and cannot be combined with output \"transaction\""
+        );
+    }
+
+    /// The `parsed` field /instruction/decode gives for `ix`.
+    async fn parsed(ix: &Instruction) -> serde_json::Value {
+        let (status, body) = post_json("/instruction/decode", instruction_to_json(ix).to_string()).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["data"]["instruction_data"], BASE64.encode(&ix.data));
+        body["data"]["parsed"].clone()
+    }
+
+    #[tokio::test]
+    async fn decodes_system_instructions() {
+        let [from, to, base, owner, nonce, authority] = [(); 6].map(|_| Pubkey::new_unique());
+        let key = |pubkey: &Pubkey| json!(pubkey.to_string());
+        #[allow(deprecated)]
+        let recent_blockhashes = solana_program::sysvar::recent_blockhashes::id();
+
+        let cases = [
+            (
+                system_instruction::transfer(&from, &to, 42),
+                json!({ "program": "system", "type": "transfer", "info": { "from": key(&from), "to": key(&to), "lamports": 42 } }),
+            ),
+            (
+                system_instruction::create_account(&from, &to, 1_000, 165, &owner),
+                json!({ "program": "system", "type": "create_account", "info": {
+                    "from": key(&from), "new_account": key(&to), "lamports": 1_000, "space": 165, "owner": key(&owner)
+                } }),
+            ),
+            (
+                system_instruction::create_account_with_seed(&from, &to, &base, "vault", 7, 8, &owner),
+                json!({ "program": "system", "type": "create_account_with_seed", "info": {
+                    "from": key(&from), "new_account": key(&to), "base": key(&base), "seed": "vault",
+                    "lamports": 7, "space": 8, "owner": key(&owner)
+                } }),
+            ),
+            (
+                system_instruction::assign(&to, &owner),
+                json!({ "program": "system", "type": "assign", "info": { "account": key(&to), "owner": key(&owner) } }),
+            ),
+            (
+                system_instruction::allocate(&to, 99),
+                json!({ "program": "system", "type": "allocate", "info": { "account": key(&to), "space": 99 } }),
+            ),
+            (
+                system_instruction::transfer_with_seed(&from, &base, "seed".to_string(), &owner, &to, 5),
+                json!({ "program": "system", "type": "transfer_with_seed", "info": {
+                    "from": key(&from), "from_base": key(&base), "to": key(&to),
+                    "from_seed": "seed", "from_owner": key(&owner), "lamports": 5
+                } }),
+            ),
+            (
+                system_instruction::advance_nonce_account(&nonce, &authority),
+                json!({ "program": "system", "type": "advance_nonce_account", "info": {
+                    "nonce_account": key(&nonce),
+                    "recent_blockhashes_sysvar": key(&recent_blockhashes),
+                    "nonce_authority": key(&authority)
+                } }),
+            ),
+            (
+                system_instruction::withdraw_nonce_account(&nonce, &authority, &to, 11),
+                json!({ "program": "system", "type": "withdraw_nonce_account", "info": {
+                    "nonce_account": key(&nonce), "to": key(&to),
+                    "recent_blockhashes_sysvar": key(&recent_blockhashes),
+                    "rent_sysvar": key(&solana_program::sysvar::rent::id()),
+                    "nonce_authority": key(&authority), "lamports": 11
+                } }),
+            ),
+            (
+                system_instruction::authorize_nonce_account(&nonce, &authority, &owner),
+                json!({ "program": "system", "type": "authorize_nonce_account", "info": {
+                    "nonce_account": key(&nonce), "nonce_authority": key(&authority), "new_authority": key(&owner)
+                } }),
+            ),
+        ];
+        for (ix, expected) in cases {
+            assert_eq!(parsed(&ix).await, expected);
+        }
+
+        // create_nonce_account is create_account followed by the initialize.
+        let initialize = &system_instruction::create_nonce_account(&from, &nonce, &authority, 1)[1];
+        assert_eq!(parsed(initialize).await["type"], "initialize_nonce_account");
+        assert_eq!(parsed(initialize).await["info"]["nonce_authority"], key(&authority));
+    }
+
+    #[tokio::test]
+    async fn decodes_token_instructions_for_both_programs() {
+        let [mint, source, destination, owner, delegate, signer_a, signer_b] = [(); 7].map(|_| Pubkey::new_unique());
+        let key = |pubkey: &Pubkey| json!(pubkey.to_string());
+        // These builders accept either program id.
+        use spl_token_2022::instruction as token_instruction;
+
+        for program in [spl_token::id(), spl_token_2022::id()] {
+            let name = if program == spl_token::id() { "spl-token" } else { "spl-token-2022" };
+
+            let ix = token_instruction::transfer_checked(&program, &source, &mint, &destination, &owner, &[], 1_500, 6).unwrap();
+            assert_eq!(parsed(&ix).await, json!({ "program": name, "type": "transfer_checked", "info": {
+                "source": key(&source), "mint": key(&mint), "destination": key(&destination),
+                "authority": key(&owner), "amount": "1500", "decimals": 6
+            } }));
+
+            let ix = token_instruction::mint_to(&program, &mint, &destination, &owner, &[&signer_a, &signer_b], u64::MAX).unwrap();
+            assert_eq!(parsed(&ix).await, json!({ "program": name, "type": "mint_to", "info": {
+                "mint": key(&mint), "account": key(&destination), "mint_authority": key(&owner),
+                "amount": u64::MAX.to_string(), "signers": [key(&signer_a), key(&signer_b)]
+            } }));
+
+            let ix = token_instruction::initialize_mint2(&program, &mint, &owner, None, 9).unwrap();
+            assert_eq!(parsed(&ix).await, json!({ "program": name, "type": "initialize_mint2", "info": {
+                "mint": key(&mint), "decimals": 9, "mint_authority": key(&owner), "freeze_authority": null
+            } }));
+
+            let ix = token_instruction::initialize_account3(&program, &source, &mint, &owner).unwrap();
+            assert_eq!(parsed(&ix).await, json!({ "program": name, "type": "initialize_account3", "info": {
+                "account": key(&source), "mint": key(&mint), "owner": key(&owner)
+            } }));
+
+            let ix = token_instruction::approve(&program, &source, &delegate, &owner, &[], 3).unwrap();
+            assert_eq!(parsed(&ix).await["info"], json!({
+                "source": key(&source), "delegate": key(&delegate), "owner": key(&owner), "amount": "3"
+            }));
+
+            let ix = token_instruction::set_authority(
+                &program, &mint, Some(&delegate), token_instruction::AuthorityType::FreezeAccount, &owner, &[],
+            ).unwrap();
+            assert_eq!(parsed(&ix).await, json!({ "program": name, "type": "set_authority", "info": {
+                "account": key(&mint), "authority": key(&owner),
+                "authority_type": "freezeAccount", "new_authority": key(&delegate)
+            } }));
+
+            let ix = token_instruction::burn_checked(&program, &source, &mint, &owner, &[], 10, 2).unwrap();
+            assert_eq!(parsed(&ix).await["type"], "burn_checked");
+            let ix = token_instruction::close_account(&program, &source, &destination, &owner, &[]).unwrap();
+            assert_eq!(parsed(&ix).await["info"], json!({
+                "account": key(&source), "destination": key(&destination), "owner": key(&owner)
+            }));
+            let ix = token_instruction::freeze_account(&program, &source, &mint, &owner, &[]).unwrap();
+            assert_eq!(parsed(&ix).await["type"], "freeze_account");
+            let ix = token_instruction::revoke(&program, &source, &owner, &[]).unwrap();
+            assert_eq!(parsed(&ix).await["type"], "revoke");
+            let ix = token_instruction::sync_native(&program, &source).unwrap();
+            assert_eq!(parsed(&ix).await, json!({ "program": name, "type": "sync_native", "info": { "account": key(&source) } }));
+        }
+
+        // Token-2022 extensions are decoded for token-2022 only; the original
+        // program would reject the same bytes.
+        let ix = spl_token_2022::instruction::initialize_mint_close_authority(&spl_token_2022::id(), &mint, Some(&owner)).unwrap();
+        assert_eq!(parsed(&ix).await, json!({ "program": "spl-token-2022", "type": "initialize_mint_close_authority", "info": {
+            "mint": key(&mint), "close_authority": key(&owner)
+        } }));
+        let legacy = Instruction { program_id: spl_token::id(), ..ix };
+        assert_eq!(parsed(&legacy).await, json!(null));
+
+        // Permanent delegates are labelled however they are granted.
+        let ix = spl_token_2022::instruction::initialize_permanent_delegate(&spl_token_2022::id(), &mint, &delegate).unwrap();
+        assert_eq!(parsed(&ix).await, json!({ "program": "spl-token-2022", "type": "initialize_permanent_delegate", "info": {
+            "mint": key(&mint), "delegate": key(&delegate), "warning": PERMANENT_DELEGATE_WARNING
+        } }));
+        let ix = spl_token_2022::instruction::set_authority(
+            &spl_token_2022::id(), &mint, Some(&delegate), spl_token_2022::instruction::AuthorityType::PermanentDelegate, &owner, &[],
+        ).unwrap();
+        assert_eq!(parsed(&ix).await["info"], json!({
+            "account": key(&mint), "authority": key(&owner), "authority_type": "permanentDelegate",
+            "new_authority": key(&delegate), "warning": PERMANENT_DELEGATE_WARNING
+        }));
+
+        let ix = transfer_fee_instruction::transfer_checked_with_fee(
+            &spl_token_2022::id(), &source, &mint, &destination, &owner, &[], 1_000, 6, 5,
+        ).unwrap();
+        assert_eq!(parsed(&ix).await, json!({ "program": "spl-token-2022", "type": "transfer_checked_with_fee", "info": {
+            "source": key(&source), "mint": key(&mint), "destination": key(&destination),
+            "authority": key(&owner), "amount": "1000", "decimals": 6, "fee": "5"
+        } }));
+
+        let ix = transfer_fee_instruction::withdraw_withheld_tokens_from_accounts(
+            &spl_token_2022::id(), &mint, &destination, &owner, &[&signer_a], &[&source, &delegate],
+        ).unwrap();
+        assert_eq!(parsed(&ix).await["info"], json!({
+            "mint": key(&mint), "destination": key(&destination), "withdraw_withheld_authority": key(&owner),
+            "source_accounts": [key(&source), key(&delegate)], "signers": [key(&signer_a)]
+        }));
+
+        let ix = transfer_fee_instruction::initialize_transfer_fee_config(
+            &spl_token_2022::id(), &mint, Some(&owner), None, 50, 10_000,
+        ).unwrap();
+        assert_eq!(parsed(&ix).await["info"], json!({
+            "mint": key(&mint), "transfer_fee_config_authority": key(&owner), "withdraw_withheld_authority": null,
+            "transfer_fee_basis_points": 50, "maximum_fee": "10000"
+        }));
+    }
+
+    #[tokio::test]
+    async fn decodes_ata_memo_and_compute_budget_instructions() {
+        let [payer, wallet, mint] = [(); 3].map(|_| Pubkey::new_unique());
+        let key = |pubkey: &Pubkey| json!(pubkey.to_string());
+        let ata = get_associated_token_address(&wallet, &mint);
+
+        let create_info = json!({
+            "payer": key(&payer), "account": key(&ata), "wallet": key(&wallet), "mint": key(&mint),
+            "system_program": key(&solana_program::system_program::id()), "token_program": key(&spl_token::id())
+        });
+        let ix = ata_instruction::create_associated_token_account(&payer, &wallet, &mint, &spl_token::id());
+        assert_eq!(parsed(&ix).await, json!({ "program": "spl-associated-token-account", "type": "create", "info": create_info }));
+        let ix = ata_instruction::create_associated_token_account_idempotent(&payer, &wallet, &mint, &spl_token::id());
+        assert_eq!(parsed(&ix).await["type"], "create_idempotent");
+        // The original Create carried no data at all.
+        let ix = Instruction { data: vec![], ..ix };
+        assert_eq!(parsed(&ix).await["info"], create_info);
+
+        let nested_mint = Pubkey::new_unique();
+        let ix = ata_instruction::recover_nested(&wallet, &mint, &nested_mint, &spl_token::id());
+        let parsed_ix = parsed(&ix).await;
+        assert_eq!(parsed_ix["type"], "recover_nested");
+        assert_eq!(parsed_ix["info"]["owner_account"], key(&ata));
+        assert_eq!(parsed_ix["info"]["nested_mint"], key(&nested_mint));
+
+        let ix = spl_memo::build_memo("gm ☀".as_bytes(), &[&wallet]);
+        assert_eq!(parsed(&ix).await, json!({ "program": "spl-memo", "type": "memo", "info": {
+            "memo": "gm ☀", "signers": [key(&wallet)]
+        } }));
+        let ix = Instruction { program_id: spl_memo::v1::id(), accounts: vec![], data: b"v1".to_vec() };
+        assert_eq!(parsed(&ix).await["info"], json!({ "memo": "v1", "signers": [] }));
+
+        let cases = [
+            (ComputeBudgetInstruction::set_compute_unit_limit(200_000), json!({ "type": "set_compute_unit_limit", "info": { "units": 200_000 } })),
+            (ComputeBudgetInstruction::set_compute_unit_price(u64::MAX), json!({ "type": "set_compute_unit_price", "info": { "micro_lamports": u64::MAX } })),
+            (ComputeBudgetInstruction::request_heap_frame(64 * 1024), json!({ "type": "request_heap_frame", "info": { "bytes": 65_536 } })),
+            (ComputeBudgetInstruction::set_loaded_accounts_data_size_limit(1024), json!({ "type": "set_loaded_accounts_data_size_limit", "info": { "bytes": 1024 } })),
+        ];
+        for (ix, mut expected) in cases {
+            expected["program"] = json!("compute-budget");
+            assert_eq!(parsed(&ix).await, expected);
+        }
+    }
+
+    #[tokio::test]
+    async fn undecodable_instructions_fall_back_to_raw_data() {
+        let from = Pubkey::new_unique();
+        let unknown = Instruction::new_with_bytes(Pubkey::new_unique(), &[1, 2, 3], vec![]);
+        assert_eq!(parsed(&unknown).await, json!(null));
+
+        // Known program, but too few accounts or data that isn't one of its
+        // instructions.
+        let mut short = system_instruction::transfer(&from, &Pubkey::new_unique(), 1);
+        short.accounts.pop();
+        assert_eq!(parsed(&short).await, json!(null));
+        let garbage = Instruction::new_with_bytes(spl_token::id(), &[200], vec![]);
+        assert_eq!(parsed(&garbage).await, json!(null));
+        let truncated = Instruction { data: vec![3, 1], ..ComputeBudgetInstruction::set_compute_unit_price(1) };
+        assert_eq!(parsed(&truncated).await, json!(null));
+        let invalid_utf8 = Instruction::new_with_bytes(spl_memo::id(), &[0xff, 0xfe], vec![]);
+        assert_eq!(parsed(&invalid_utf8).await, json!(null));
+
+        let (status, body) = post_json("/instruction/decode", json!({
+            "programId": "nope",
+            "accounts": [],
+            "data": ""
+        }).to_string()).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert!(body["error"].as_str().unwrap().starts_with("Invalid program_id"));
+    }
+
+    #[tokio::test]
+    async fn encoding_round_trips_through_the_decoder() {
+        use spl_token_2022::instruction as token_2022_instruction;
+        let [a, b, c, d, e] = [(); 5].map(|_| Pubkey::new_unique());
+        let t22 = spl_token_2022::id();
+
+        let ixs = vec![
+            system_instruction::transfer(&a, &b, 1),
+            system_instruction::create_account(&a, &b, 2, 3, &c),
+            system_instruction::create_account_with_seed(&a, &b, &c, "s", 4, 5, &d),
+            system_instruction::assign_with_seed(&a, &b, "s", &c),
+            system_instruction::allocate_with_seed(&a, &b, "s", 6, &c),
+            system_instruction::transfer_with_seed(&a, &b, "s".to_string(), &c, &d, 7),
+            system_instruction::advance_nonce_account(&a, &b),
+            system_instruction::withdraw_nonce_account(&a, &b, &c, 8),
+            system_instruction::create_nonce_account(&a, &b, &c, 9)[1].clone(),
+            system_instruction::upgrade_nonce_account(a),
+            token_instruction::initialize_mint(&spl_token::id(), &a, &b, Some(&c), 6).unwrap(),
+            token_instruction::initialize_account(&spl_token::id(), &a, &b, &c).unwrap(),
+            token_instruction::initialize_multisig2(&spl_token::id(), &a, &[&b, &c], 2).unwrap(),
+            token_instruction::transfer_checked(&spl_token::id(), &a, &b, &c, &d, &[&e], u64::MAX, 9).unwrap(),
+            token_instruction::set_authority(&spl_token::id(), &a, None, token_instruction::AuthorityType::CloseAccount, &b, &[]).unwrap(),
+            token_2022_instruction::mint_to_checked(&t22, &a, &b, &c, &[], 10, 2).unwrap(),
+            token_2022_instruction::get_account_data_size(&t22, &a, &[ExtensionType::TransferFeeAmount, ExtensionType::ImmutableOwner]).unwrap(),
+            token_2022_instruction::ui_amount_to_amount(&t22, &a, "1.5").unwrap(),
+            token_2022_instruction::initialize_permanent_delegate(&t22, &a, &b).unwrap(),
+            token_2022_instruction::set_authority(&t22, &a, Some(&b), token_2022_instruction::AuthorityType::PermanentDelegate, &c, &[]).unwrap(),
+            token_2022_instruction::reallocate(&t22, &a, &b, &c, &[], &[ExtensionType::MemoTransfer]).unwrap(),
+            transfer_fee_instruction::set_transfer_fee(&t22, &a, &b, &[&c], 25, 1_000).unwrap(),
+            transfer_fee_instruction::harvest_withheld_tokens_to_mint(&t22, &a, &[&b, &c]).unwrap(),
+            ata_instruction::create_associated_token_account_idempotent(&a, &b, &c, &t22),
+            ata_instruction::recover_nested(&a, &b, &c, &spl_token::id()),
+            spl_memo::build_memo(b"hello", &[&a, &b]),
+            ComputeBudgetInstruction::set_compute_unit_limit(300_000),
+            ComputeBudgetInstruction::set_compute_unit_price(12),
+        ];
+        for ix in ixs {
+            let decoded = parsed(&ix).await;
+            assert!(!decoded.is_null(), "{:?} did not decode", ix);
+            let (status, body) = post_json("/instruction/encode", decoded.to_string()).await;
+            assert_eq!(status, StatusCode::OK, "{} {}: {}", decoded["program"], decoded["type"], body["error"]);
+            assert_eq!(body["data"], instruction_to_json(&ix));
+            let encoded = transaction::parse_instruction("", &serde_json::from_value(body["data"].clone()).unwrap()).ok().unwrap();
+            assert_eq!(parsed(&encoded).await, decoded);
+        }
+    }
+
+    #[tokio::test]
+    async fn encoding_rejects_unsupported_and_inconsistent_input() {
+        let encode = |body: serde_json::Value| post_json("/instruction/encode", body.to_string());
+        let [wallet, mint, payer] = [(); 3].map(|_| Pubkey::new_unique());
+
+        let (status, body) = encode(json!({ "program": "stake", "type": "delegate", "info": {} })).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(
+            body["error"],
+            "Unsupported program \"stake\", expected one of: system, spl-token, spl-token-2022, \
+             spl-associated-token-account, spl-memo, compute-budget"
+        );
+        let (status, body) = encode(json!({ "program": "compute-budget", "type": "transfer", "info": {} })).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(
+            body["error"],
+            "Unsupported type \"transfer\" for compute-budget, expected one of: request_heap_frame, \
+             set_compute_unit_limit, set_compute_unit_price, set_loaded_accounts_data_size_limit"
+        );
+        let (status, body) = encode(json!({ "program": "spl-associated-token-account", "type": "bogus", "info": {} })).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(
+            body["error"],
+            "Unsupported type \"bogus\" for spl-associated-token-account, expected one of: create, \
+             create_idempotent, recover_nested"
+        );
+        // Extension instructions exist only in token-2022.
+        let (status, body) = encode(json!({
+            "program": "spl-token",
+            "type": "initialize_permanent_delegate",
+            "info": { "mint": mint.to_string(), "delegate": wallet.to_string() }
+        })).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert!(body["error"].as_str().unwrap().starts_with("Unsupported type \"initialize_permanent_delegate\" for spl-token"));
+
+        // Numbers may be given as numbers or strings; the derived ATA
+        // address may be left out but must match when given.
+        let (status, body) = encode(json!({
+            "program": "system",
+            "type": "transfer",
+            "info": { "from": payer.to_string(), "to": wallet.to_string(), "lamports": "5" }
+        })).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["data"], instruction_to_json(&system_instruction::transfer(&payer, &wallet, 5)));
+
+        let ata_info = json!({
+            "payer": payer.to_string(),
+            "wallet": wallet.to_string(),
+            "mint": mint.to_string(),
+            "token_program": spl_token::id().to_string(),
+            "account": Pubkey::new_unique().to_string()
+        });
+        let (status, body) = encode(json!({ "program": "spl-associated-token-account", "type": "create", "info": ata_info })).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert!(body["error"].as_str().unwrap().starts_with("info.account ("));
+
+        let (status, body) = encode(json!({
+            "program": "system",
+            "type": "allocate",
+            "info": { "account": wallet.to_string(), "space": 8, "owner": mint.to_string() }
+        })).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["error"], "info.owner is not a field of system allocate");
+
+        let (status, body) = encode(json!({ "program": "system", "type": "allocate", "info": { "account": wallet.to_string() } })).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["error"], "Missing info.space");
+
+        let (status, body) = encode(json!({
+            "program": "spl-token",
+            "type": "set_authority",
+            "info": { "account": mint.to_string(), "authority": wallet.to_string(), "authority_type": "owner" }
+        })).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert!(body["error"].as_str().unwrap().starts_with("Invalid info.authority_type: \"owner\", expected one of: mintTokens, "));
+    }
+
+    #[tokio::test]
+    async fn transaction_decode_includes_parsed_instructions() {
+        let payer = Keypair::new();
+        let to = Pubkey::new_unique();
+        let ixs = [
+            ComputeBudgetInstruction::set_compute_unit_price(5),
+            system_instruction::transfer(&payer.pubkey(), &to, 77),
+            Instruction::new_with_bytes(Pubkey::new_unique(), &[9], vec![]),
+        ];
+        let (_, built) = post_json("/transaction/build", json!({
+            "instructions": ixs.iter().map(instruction_to_json).collect::<Vec<_>>(),
+            "feePayer": payer.pubkey().to_string(),
+            "recentBlockhash": Hash::new_unique().to_string()
+        }).to_string()).await;
+        let (status, body) = post_json("/transaction/decode", json!({ "transaction": built["data"]["transaction"] }).to_string()).await;
+        assert_eq!(status, StatusCode::OK);
+
+        let instructions = &body["data"]["instructions"];
+        assert_eq!(instructions[0]["parsed"]["type"], "set_compute_unit_price");
+        assert_eq!(instructions[1]["parsed"], json!({ "program": "system", "type": "transfer", "info": {
+            "from": payer.pubkey().to_string(), "to": to.to_string(), "lamports": 77
+        } }));
+        assert_eq!(instructions[2]["parsed"], json!(null));
+        assert_eq!(instructions[2]["instruction_data"], BASE64.encode([9]));
+    }
+
+    #[tokio::test]
+    async fn verifies_present_signatures_offline() {
+        let payer = Keypair::new();
+        let from = Keypair::new();
+        let unsigned = build_transfer(&payer.pubkey(), &from.pubkey(), &Hash::new_unique()).await;
+        let verify = |transaction: String| {
+            post_json("/transaction/verify-signatures", json!({ "transaction": transaction }).to_string())
+        };
+
+        let (_, signed) = post_json("/transaction/partial-sign", json!({
+            "transaction": unsigned,
+            "secret": bs58::encode(payer.to_bytes()).into_string()
+        }).to_string()).await;
+        let partly_signed = signed["data"]["transaction"].as_str().unwrap().to_string();
+        let (status, body) = verify(partly_signed.clone()).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["data"]["all_valid"], true);
+        assert_eq!(body["data"]["signatures"][0]["valid"], true);
+        assert_eq!(body["data"]["signatures"][1], json!({
+            "pubkey": from.pubkey().to_string(),
+            "slot": 1,
+            "signature": null,
+            "valid": null
+        }));
+        assert_eq!(body["data"]["missing_signers"], json!([from.pubkey().to_string()]));
+
+        // Flip one bit of the payer's signature (bytes 1..65 of the wire
+        // format follow the one-byte signature count).
+        let mut bytes = BASE64.decode(&partly_signed).unwrap();
+        bytes[1] ^= 1;
+        let (status, body) = verify(BASE64.encode(&bytes)).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["data"]["all_valid"], false);
+        assert_eq!(body["data"]["signatures"][0]["valid"], false);
+        assert_eq!(body["data"]["signatures"][0]["pubkey"], payer.pubkey().to_string());
+
+        let (status, body) = verify(BASE64.encode(&bytes[..40])).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert!(body["error"].as_str().unwrap().starts_with("Invalid transaction"));
+    }
+
+    #[tokio::test]
+    async fn build_prepends_compute_budget_once() {
+        let payer = Keypair::new();
+        let nonce_account = Pubkey::new_unique();
+        let transfer = system_instruction::transfer(&payer.pubkey(), &Pubkey::new_unique(), 5);
+        let build = |instructions: Vec<serde_json::Value>, extra: serde_json::Value| {
+            let mut body = json!({
+                "instructions": instructions,
+                "feePayer": payer.pubkey().to_string(),
+                "computeUnitLimit": 200_000,
+                "computeUnitPriceMicroLamports": "1000"
+            });
+            body.as_object_mut().unwrap().extend(extra.as_object().unwrap().clone());
+            post_json("/transaction/build", body.to_string())
+        };
+        let decode_tx = |body: &serde_json::Value| -> Transaction {
+            bincode::deserialize(&BASE64.decode(body["data"]["transaction"].as_str().unwrap()).unwrap()).unwrap()
+        };
+        let blockhash = Hash::new_unique();
+
+        let (status, body) = build(vec![instruction_to_json(&transfer)], json!({ "recentBlockhash": blockhash.to_string() })).await;
+        assert_eq!(status, StatusCode::OK);
+        let expected = solana_sdk::message::Message::new_with_blockhash(
+            &[
+                ComputeBudgetInstruction::set_compute_unit_limit(200_000),
+                ComputeBudgetInstruction::set_compute_unit_price(1000),
+                transfer.clone(),
+            ],
+            Some(&payer.pubkey()),
+            &blockhash,
+        );
+        assert_eq!(decode_tx(&body).message, expected);
+
+        // Only the price, and after the nonce advance, which must stay first.
+        let nonce = Hash::new_unique();
+        let (status, body) = build(vec![instruction_to_json(&transfer)], json!({
+            "computeUnitLimit": null,
+            "nonceAccount": nonce_account.to_string(),
+            "nonceAuthority": payer.pubkey().to_string(),
+            "nonce": nonce.to_string()
+        })).await;
+        assert_eq!(status, StatusCode::OK);
+        let expected = solana_sdk::message::Message::new_with_blockhash(
+            &[
+                system_instruction::advance_nonce_account(&nonce_account, &payer.pubkey()),
+                ComputeBudgetInstruction::set_compute_unit_price(1000),
+                transfer.clone(),
+            ],
+            Some(&payer.pubkey()),
+            &nonce,
+        );
+        assert_eq!(decode_tx(&body).message, expected);
+
+        let (status, body) = build(
+            vec![instruction_to_json(&transfer), instruction_to_json(&ComputeBudgetInstruction::set_compute_unit_limit(1))],
+            json!({ "recentBlockhash": blockhash.to_string() }),
+        ).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(
+            body["error"],
+            "instructions[1] is already a compute budget instruction; drop it or omit \
+             computeUnitLimit and computeUnitPriceMicroLamports"
+        );
+
+        let (status, body) = build(vec![instruction_to_json(&transfer)], json!({
+            "recentBlockhash": blockhash.to_string(),
+            "computeUnitLimit": MAX_COMPUTE_UNIT_LIMIT + 1
+        })).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["error"], format!("computeUnitLimit must be at most 1400000, got {}", MAX_COMPUTE_UNIT_LIMIT + 1));
+
+        // Without the fields, compute budget instructions pass through as given.
+        let (status, _) = post_json("/transaction/build", json!({
+            "instructions": [instruction_to_json(&ComputeBudgetInstruction::set_compute_unit_limit(1))],
+            "feePayer": payer.pubkey().to_string(),
+            "recentBlockhash": blockhash.to_string()
+        }).to_string()).await;
+        assert_eq!(status, StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn builds_durable_nonce_transactions() {
+        let payer = Keypair::new();
+        let nonce_account = Pubkey::new_unique();
+        let nonce = Hash::new_unique();
+        let ix = system_instruction::transfer(&payer.pubkey(), &Pubkey::new_unique(), 5);
+        let build = |extra: serde_json::Value| {
+            let mut body = json!({
+                "instructions": [instruction_to_json(&ix)],
+                "feePayer": payer.pubkey().to_string()
+            });
+            body.as_object_mut().unwrap().extend(extra.as_object().unwrap().clone());
+            post_json("/transaction/build", body.to_string())
+        };
+
+        let (status, body) = build(json!({
+            "nonceAccount": nonce_account.to_string(),
+            "nonceAuthority": payer.pubkey().to_string(),
+            "nonce": nonce.to_string()
+        })).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["data"]["recent_blockhash"], nonce.to_string());
+        assert_eq!(body["data"]["durable_nonce"], true);
+        let tx: Transaction = bincode::deserialize(&BASE64.decode(body["data"]["transaction"].as_str().unwrap()).unwrap()).unwrap();
+        let expected = solana_sdk::message::Message::new_with_blockhash(
+            &[system_instruction::advance_nonce_account(&nonce_account, &payer.pubkey()), ix.clone()],
+            Some(&payer.pubkey()),
+            &nonce,
+        );
+        assert_eq!(tx.message, expected);
+
+        // A nonce transaction keeps its blockhash: update-blockhash refuses it.
+        let (status, _) = post_json("/transaction/update-blockhash", json!({
+            "transaction": body["data"]["transaction"],
+            "recentBlockhash": Hash::new_unique().to_string()
+        }).to_string()).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+
+        let (status, body) = build(json!({
+            "recentBlockhash": Hash::new_unique().to_string(),
+            "nonce": nonce.to_string()
+        })).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(
+            body["error"],
+            "recentBlockhash cannot be combined with nonce; in durable nonce mode the nonce value is the blockhash"
+        );
+
+        let (status, body) = build(json!({ "nonceAccount": nonce_account.to_string() })).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(
+            body["error"],
+            "Durable nonce mode needs nonceAccount, nonceAuthority and nonce; missing nonceAuthority, nonce"
+        );
+
+        let (status, body) = build(json!({ "recentBlockhash": Hash::new_unique().to_string() })).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["data"]["durable_nonce"], false);
+    }
+
+    #[tokio::test]
+    async fn attaches_only_signatures_that_verify_in_their_slot() {
+        let payer = Keypair::new();
+        let from = Keypair::new();
+        let unsigned = build_transfer(&payer.pubkey(), &from.pubkey(), &Hash::new_unique()).await;
+        let message_bytes = {
+            let tx: Transaction = bincode::deserialize(&BASE64.decode(&unsigned).unwrap()).unwrap();
+            tx.message_data()
+        };
+        let attach = |transaction: String, pubkey: Pubkey, signature: String| {
+            post_json("/transaction/attach-signature", json!({
+                "transaction": transaction,
+                "pubkey": pubkey.to_string(),
+                "signature": signature
+            }).to_string())
+        };
+
+        let from_signature = from.sign_message(&message_bytes);
+        let (status, body) = attach(unsigned.clone(), from.pubkey(), BASE64.encode(from_signature)).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["data"]["complete"], false);
+        assert_eq!(body["data"]["missing_signers"], json!([payer.pubkey().to_string()]));
+
+        let partly_signed = body["data"]["transaction"].as_str().unwrap().to_string();
+        let (status, body) = attach(partly_signed, payer.pubkey(), payer.sign_message(&message_bytes).to_string()).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["data"]["complete"], true);
+        let tx: Transaction = bincode::deserialize(&BASE64.decode(body["data"]["transaction"].as_str().unwrap()).unwrap()).unwrap();
+        assert!(tx.verify().is_ok());
+        assert_eq!(tx.signatures[1], from_signature);
+
+        // The right signer's signature, claimed for the other slot.
+        let (status, body) = attach(unsigned.clone(), payer.pubkey(), from_signature.to_string()).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(
+            body["error"],
+            format!("signature does not verify for {} over this transaction's message", payer.pubkey())
+        );
+
+        // A signature over different bytes.
+        let stale = from.sign_message(b"some other message").to_string();
+        let (status, _) = attach(unsigned.clone(), from.pubkey(), stale).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+
+        let outsider = Keypair::new();
+        let (status, body) = attach(unsigned, outsider.pubkey(), outsider.sign_message(&message_bytes).to_string()).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(
+            body["error"],
+            format!("pubkey ({}) is not a required signer of this transaction", outsider.pubkey())
+        );
+    }
+
+    #[tokio::test]
+    async fn compiled_messages_assemble_with_external_signatures() {
+        let payer = Keypair::new();
+        let from = Keypair::new();
+        let ix = system_instruction::transfer(&from.pubkey(), &Pubkey::new_unique(), 9);
+        let (status, compiled) = post_json("/message/compile", json!({
+            "instructions": [instruction_to_json(&ix)],
+            "feePayer": payer.pubkey().to_string(),
+            "recentBlockhash": Hash::new_unique().to_string()
+        }).to_string()).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(compiled["data"]["signers"], json!([payer.pubkey().to_string(), from.pubkey().to_string()]));
+
+        // Sign the raw message bytes elsewhere, as a hardware wallet would.
+        let message = compiled["data"]["message"].as_str().unwrap();
+        let message_bytes = BASE64.decode(message).unwrap();
+        let payer_signature = payer.sign_message(&message_bytes);
+        let from_signature = from.sign_message(&message_bytes);
+        let assemble = |signatures: serde_json::Value| post_json("/transaction/assemble", json!({
+            "message": message,
+            "signatures": signatures
+        }).to_string());
+
+        let (status, body) = assemble(json!([
+            { "pubkey": from.pubkey().to_string(), "signature": from_signature.to_string() },
+            { "pubkey": payer.pubkey().to_string(), "signature": BASE64.encode(payer_signature) }
+        ])).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["data"]["complete"], true);
+        assert_eq!(body["data"]["signature"], payer_signature.to_string());
+        assert_eq!(body["data"]["signature_base64"], BASE64.encode(payer_signature));
+        let tx: Transaction = bincode::deserialize(&BASE64.decode(body["data"]["transaction"].as_str().unwrap()).unwrap()).unwrap();
+        assert!(tx.verify().is_ok());
+        assert_eq!(tx.message_data(), message_bytes);
+
+        let (status, body) = assemble(json!([
+            { "pubkey": from.pubkey().to_string(), "signature": from_signature.to_string() }
+        ])).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["data"]["missing_signers"], json!([payer.pubkey().to_string()]));
+        assert_eq!(body["data"]["signature"], json!(null));
+
+        let (status, body) = assemble(json!([
+            { "pubkey": payer.pubkey().to_string(), "signature": from_signature.to_string() }
+        ])).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(
+            body["error"],
+            format!("signatures[0].signature does not verify for {} over this transaction's message", payer.pubkey())
+        );
+
+        let (status, body) = post_json("/transaction/assemble", json!({ "message": BASE64.encode([1u8, 2]) }).to_string()).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert!(body["error"].as_str().unwrap().starts_with("Invalid message:"));
+    }
+
+    #[tokio::test]
+    async fn partial_signs_chain_without_clobbering() {
+        let signers = [Keypair::new(), Keypair::new(), Keypair::new()];
+        let ixs: Vec<_> = signers[1..]
+            .iter()
+            .map(|from| instruction_to_json(&system_instruction::transfer(&from.pubkey(), &Pubkey::new_unique(), 7)))
+            .collect();
+        let (_, built) = post_json("/transaction/build", json!({
+            "instructions": ixs,
+            "feePayer": signers[0].pubkey().to_string(),
+            "recentBlockhash": Hash::new_unique().to_string()
+        }).to_string()).await;
+        let partial_sign = |transaction: String, signer: &Keypair| post_json("/transaction/partial-sign", json!({
+            "transaction": transaction,
+            "secret": hex::encode(signer.to_bytes()),
+            "secretEncoding": "hex"
+        }).to_string());
+
+        let mut transaction = built["data"]["transaction"].as_str().unwrap().to_string();
+        for (i, signer) in [&signers[2], &signers[0], &signers[1]].into_iter().enumerate() {
+            let (status, body) = partial_sign(transaction.clone(), signer).await;
+            assert_eq!(status, StatusCode::OK);
+            assert_eq!(body["data"]["signer"], signer.pubkey().to_string());
+            assert_eq!(body["data"]["missing_signers"].as_array().unwrap().len(), 2 - i);
+            transaction = body["data"]["transaction"].as_str().unwrap().to_string();
+        }
+
+        let tx: Transaction = bincode::deserialize(&BASE64.decode(&transaction).unwrap()).unwrap();
+        assert!(tx.verify().is_ok());
+
+        // Signing again with a key that already signed changes nothing.
+        let (status, body) = partial_sign(transaction.clone(), &signers[1]).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["data"]["transaction"], transaction);
+        assert_eq!(body["data"]["complete"], true);
+
+        let (status, _) = partial_sign(transaction, &Keypair::new()).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn decode_transaction_reports_legacy_fields() {
+        let payer = Keypair::new();
+        let from = Keypair::new();
+        let blockhash = Hash::new_unique();
+        let unsigned = build_transfer(&payer.pubkey(), &from.pubkey(), &blockhash).await;
+        let (_, signed) = post_json("/transaction/sign", json!({
+            "transaction": unsigned,
+            "secrets": [bs58::encode(from.to_bytes()).into_string()]
+        }).to_string()).await;
+        let wire = BASE64.decode(signed["data"]["transaction"].as_str().unwrap()).unwrap();
+        let tx: Transaction = bincode::deserialize(&wire).unwrap();
+
+        for encoded in [BASE64.encode(&wire), bs58::encode(&wire).into_string()] {
+            let (status, body) = post_json("/transaction/decode", json!({ "transaction": encoded }).to_string()).await;
+            assert_eq!(status, StatusCode::OK);
+            let data = &body["data"];
+            assert_eq!(data["version"], "legacy");
+            assert_eq!(data["fee_payer"], payer.pubkey().to_string());
+            assert_eq!(data["recent_blockhash"], blockhash.to_string());
+            assert_eq!(data["missing_signers"], json!([payer.pubkey().to_string()]));
+            assert_eq!(data["signatures"], json!([
+                { "pubkey": payer.pubkey().to_string(), "signature": null },
+                { "pubkey": from.pubkey().to_string(), "signature": tx.signatures[1].to_string() }
+            ]));
+            assert_eq!(data["address_table_lookups"], json!([]));
+
+            let ix = &data["instructions"][0];
+            assert_eq!(ix["program_id"], solana_program::system_program::id().to_string());
+            assert_eq!(ix["accounts"][0], json!({
+                "pubkey": from.pubkey().to_string(),
+                "is_signer": true,
+                "is_writable": true
+            }));
+            assert_eq!(ix["accounts"][1]["is_signer"], false);
+            assert_eq!(ix["accounts"][1]["is_writable"], true);
+            assert_eq!(ix["instruction_data"], BASE64.encode(&tx.message.instructions[0].data));
+        }
+    }
+
+    #[tokio::test]
+    async fn decode_transaction_reports_v0_lookups() {
+        use solana_sdk::address_lookup_table::AddressLookupTableAccount;
+        use solana_sdk::message::{v0, VersionedMessage};
+        use solana_sdk::transaction::VersionedTransaction;
+
+        let payer = Pubkey::new_unique();
+        let table_key = Pubkey::new_unique();
+        let (to, readonly) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let mut ix = system_instruction::transfer(&payer, &to, 5);
+        ix.accounts.push(solana_sdk::instruction::AccountMeta::new_readonly(readonly, false));
+        let table = AddressLookupTableAccount { key: table_key, addresses: vec![readonly, Pubkey::new_unique(), to] };
+        let message = v0::Message::try_compile(&payer, &[ix], &[table], Hash::new_unique()).unwrap();
+        let tx = VersionedTransaction {
+            signatures: vec![solana_sdk::signature::Signature::default()],
+            message: VersionedMessage::V0(message),
+        };
+
+        let (status, body) = post_json("/transaction/decode", json!({
+            "transaction": BASE64.encode(bincode::serialize(&tx).unwrap())
+        }).to_string()).await;
+        assert_eq!(status, StatusCode::OK);
+        let data = &body["data"];
+        assert_eq!(data["version"], 0);
+        assert_eq!(data["account_keys"], json!([payer.to_string(), solana_program::system_program::id().to_string()]));
+        assert_eq!(data["address_table_lookups"], json!([{
+            "account_key": table_key.to_string(),
+            "writable_indexes": [2],
+            "readonly_indexes": [0]
+        }]));
+        assert_eq!(data["instructions"][0]["accounts"], json!([
+            { "pubkey": payer.to_string(), "is_signer": true, "is_writable": true },
+            { "pubkey": null, "lookup_table": table_key.to_string(), "lookup_index": 2, "is_signer": false, "is_writable": true },
+            { "pubkey": null, "lookup_table": table_key.to_string(), "lookup_index": 0, "is_signer": false, "is_writable": false }
+        ]));
+    }
+
+    #[tokio::test]
+    async fn decode_transaction_rejects_malformed_bytes() {
+        let (status, body) = post_json("/transaction/decode", json!({
+            "transaction": BASE64.encode([0u8; 3])
+        }).to_string()).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert!(body["error"].as_str().unwrap().starts_with("Invalid transaction:"), "{}", body["error"]);
+
+        let unsigned = build_transfer(&Pubkey::new_unique(), &Pubkey::new_unique(), &Hash::new_unique()).await;
+        let mut wire = BASE64.decode(&unsigned).unwrap();
+        wire.push(0);
+        let (status, _) = post_json("/transaction/decode", json!({ "transaction": BASE64.encode(&wire) }).to_string()).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+
+        // Point an instruction's program id past the end of the key list.
+        let mut tx: Transaction = bincode::deserialize(&BASE64.decode(&unsigned).unwrap()).unwrap();
+        tx.message.instructions[0].program_id_index = 200;
+        let (status, body) = post_json("/transaction/decode", json!({
+            "transaction": BASE64.encode(bincode::serialize(&tx).unwrap())
+        }).to_string()).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert!(body["error"].as_str().unwrap().starts_with("Invalid transaction:"));
+
+        let (status, body) = post_json("/transaction/decode", json!({ "transaction": "!!" }).to_string()).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["error"], "Invalid transaction: expected base64 or base58");
+    }
+
+    #[tokio::test]
+    async fn encrypted_secrets_round_trip_through_sign_message() {
+        let (status, generated) = into_parts(generate_new_keypair(State(None), Bytes::from(
+            json!({ "passphrase": "correct horse" }).to_string(),
+        )).await).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(generated["data"]["encrypted"], true);
+        let encrypted = generated["data"]["secret"].as_str().unwrap();
+        assert!(encrypted.starts_with("argon2id-aes256gcm$m=19456,t=2,p=1$"), "{}", encrypted);
+
+        let sign = |passphrase: &str| sign_message_with_ed25519(State(Keystore::default()), request(json!({
+            "message": "hello",
+            "encryptedSecret": encrypted,
+            "passphrase": passphrase
+        })));
+        let (status, body) = into_parts(sign("correct horse").await).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["data"]["public_key"], generated["data"]["pubkey"]);
+        let signature = decode_signature("signature", body["data"]["signature"].as_str().unwrap()).ok().unwrap();
+        let pubkey = Pubkey::from_str(generated["data"]["pubkey"].as_str().unwrap()).unwrap();
+        assert!(signature.verify(pubkey.as_ref(), b"hello"));
+
+        let (status, body) = into_parts(sign("wrong horse").await).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["error"], "Wrong passphrase for encryptedSecret");
+
+        // The same key re-derived from its seed encrypts to a different string
+        // (fresh salt and nonce) that still decrypts to the same keypair.
+        let seed = [9u8; 32];
+        let (_, from_seed) = into_parts(keypair_from_seed(request(json!({
+            "seed": hex::encode(seed),
+            "passphrase": "pw"
+        }))).await).await;
+        let (_, again) = into_parts(keypair_from_seed(request(json!({
+            "seed": hex::encode(seed),
+            "passphrase": "pw"
+        }))).await).await;
+        assert_ne!(from_seed["data"]["secret"], again["data"]["secret"]);
+        let decrypted = decrypt_secret(again["data"]["secret"].as_str().unwrap(), "pw").ok().unwrap();
+        assert_eq!(&decrypted[..32], &seed);
+        assert_eq!(bs58::encode(&decrypted[32..]).into_string(), from_seed["data"]["pubkey"].as_str().unwrap());
+
+        let (_, from_mnemonic) = into_parts(keypair_from_mnemonic(request(json!({
+            "mnemonic": TEST_MNEMONIC,
+            "encryptionPassphrase": "pw"
+        }))).await).await;
+        let (_, plain) = into_parts(keypair_from_mnemonic(request(json!({ "mnemonic": TEST_MNEMONIC }))).await).await;
+        let decrypted = decrypt_secret(from_mnemonic["data"]["secret"].as_str().unwrap(), "pw").ok().unwrap();
+        assert_eq!(bs58::encode(&*decrypted).into_string(), plain["data"]["secret"].as_str().unwrap());
+    }
+
+    #[tokio::test]
+    async fn encrypted_secret_errors_are_distinct() {
+        let encrypted = encrypt_secret(&Keypair::new().to_bytes(), "pw").ok().unwrap();
+        let sign = |fields: serde_json::Value| {
+            let mut body = json!({ "message": "hello" });
+            body.as_object_mut().unwrap().extend(fields.as_object().unwrap().clone());
+            sign_message_with_ed25519(State(Keystore::default()), request(body))
+        };
+
+        let parts: Vec<&str> = encrypted.split('$').collect();
+        let mut tampered_nonce = parts.clone();
+        tampered_nonce[3] = "AAAA";
+        let cases = [
+            (json!({ "encryptedSecret": "garbage", "passphrase": "pw" }),
+             "Invalid encryptedSecret: expected 5 '$'-separated parts"),
+            (json!({ "encryptedSecret": encrypted.replacen("argon2id", "scrypt", 1), "passphrase": "pw" }),
+             "Invalid encryptedSecret: unsupported scheme \"scrypt-aes256gcm\""),
+            (json!({ "encryptedSecret": encrypted.replacen("m=19456", "m=1048576", 1), "passphrase": "pw" }),
+             "Invalid encryptedSecret: parameters exceed limits (m<=65536, t<=10, p<=4)"),
+            (json!({ "encryptedSecret": tampered_nonce.join("$"), "passphrase": "pw" }),
+             "Invalid encryptedSecret: nonce must be 12 bytes, got 3"),
+            (json!({ "encryptedSecret": encrypted }),
+             "passphrase is required with encryptedSecret"),
+            (json!({ "encryptedSecret": encrypted, "secret": "abc", "passphrase": "pw" }),
+             "provide either secret or encryptedSecret, not both"),
+            (json!({ "encryptedSecret": encrypted, "passphrase": "nope" }),
+             "Wrong passphrase for encryptedSecret"),
+        ];
+        for (fields, expected) in cases {
+            let (status, body) = into_parts(sign(fields).await).await;
+            assert_eq!(status, StatusCode::BAD_REQUEST);
+            assert_eq!(body["error"], expected);
+        }
+
+        let (status, body) = into_parts(generate_new_keypair(State(None), Bytes::from(
+            json!({ "passphrase": "pw", "format": "hex" }).to_string(),
+        )).await).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["error"], "format cannot be combined with passphrase; encrypted secrets have a fixed format");
+    }
+}
@@ -0,0 +1,1175 @@
+// src/parsed_instruction.rs
+
+use axum::Json;
+use serde::Deserialize;
+use serde_json::json;
+use solana_program::program_error::ProgramError;
+use solana_program::program_utils::limited_deserialize;
+use solana_program::system_instruction::{self, SystemInstruction};
+use solana_program::system_program;
+use solana_sdk::compute_budget::{self, ComputeBudgetInstruction};
+use solana_sdk::instruction::{AccountMeta, Instruction};
+use solana_sdk::packet::PACKET_DATA_SIZE;
+use solana_sdk::pubkey::Pubkey;
+use spl_associated_token_account::instruction as ata_instruction;
+use spl_token_2022::extension::transfer_fee::instruction::{self as transfer_fee_instruction, TransferFeeInstruction};
+use spl_token_2022::extension::ExtensionType;
+use spl_token_2022::instruction::{self as token_instruction, AuthorityType, TokenInstruction};
+
+use crate::transaction::{parse_instruction, InstructionParam};
+use crate::{
+    instruction_response, instruction_to_json, parse_pubkey, success, validate_memo, ApiError, ApiResult,
+    PERMANENT_DELEGATE_WARNING,
+};
+
+/// The last instruction tag the original token program understands
+/// (UiAmountToAmount); everything after it is token-2022 only.
+const LAST_TOKEN_INSTRUCTION: u8 = 24;
+const TRANSFER_FEE_EXTENSION: u8 = 26;
+
+/// An instruction's account keys, in order. Keys loaded from an address
+/// lookup table are unknown offline and render as null.
+struct Accounts<'a>(&'a [Option<Pubkey>]);
+
+impl Accounts<'_> {
+    /// The account at `index`, or None if the instruction lists too few
+    /// accounts to be the one its data claims.
+    fn key(&self, index: usize) -> Option<serde_json::Value> {
+        self.0.get(index).map(|key| key.map_or(json!(null), |key| json!(key.to_string())))
+    }
+
+    fn rest(&self, index: usize) -> Vec<serde_json::Value> {
+        (index..self.0.len()).filter_map(|i| self.key(i)).collect()
+    }
+}
+
+type Parsed = (&'static str, serde_json::Value);
+
+/// Renders an instruction for a well-known program as
+/// `{ program, type, info }`, or None when the program is not one we know
+/// or the data doesn't decode as any of its instructions.
+pub(crate) fn parse_known_instruction(
+    program_id: &Pubkey,
+    accounts: &[Option<Pubkey>],
+    data: &[u8],
+) -> Option<serde_json::Value> {
+    let accounts = Accounts(accounts);
+    let (program, (kind, info)) = if *program_id == system_program::id() {
+        ("system", parse_system(&accounts, data)?)
+    } else if *program_id == spl_token::id() {
+        if !matches!(data.first(), Some(tag) if *tag <= LAST_TOKEN_INSTRUCTION) {
+            return None;
+        }
+        ("spl-token", parse_token(&accounts, data)?)
+    } else if *program_id == spl_token_2022::id() {
+        ("spl-token-2022", parse_token(&accounts, data)?)
+    } else if *program_id == spl_associated_token_account::id() {
+        ("spl-associated-token-account", parse_associated_token_account(&accounts, data)?)
+    } else if *program_id == spl_memo::id() || *program_id == spl_memo::v1::id() {
+        ("spl-memo", parse_memo(&accounts, data)?)
+    } else if *program_id == compute_budget::id() {
+        ("compute-budget", parse_compute_budget(data)?)
+    } else {
+        return None;
+    };
+
+    Some(json!({ "program": program, "type": kind, "info": info }))
+}
+
+fn parse_system(a: &Accounts, data: &[u8]) -> Option<Parsed> {
+    // The runtime's own decoding: bincode, trailing bytes ignored.
+    let ix: SystemInstruction = limited_deserialize(data, PACKET_DATA_SIZE as u64).ok()?;
+    Some(match ix {
+        SystemInstruction::CreateAccount { lamports, space, owner } => ("create_account", json!({
+            "from": a.key(0)?,
+            "new_account": a.key(1)?,
+            "lamports": lamports,
+            "space": space,
+            "owner": owner.to_string()
+        })),
+        SystemInstruction::Assign { owner } => ("assign", json!({
+            "account": a.key(0)?,
+            "owner": owner.to_string()
+        })),
+        SystemInstruction::Transfer { lamports } => ("transfer", json!({
+            "from": a.key(0)?,
+            "to": a.key(1)?,
+            "lamports": lamports
+        })),
+        SystemInstruction::CreateAccountWithSeed { base, seed, lamports, space, owner } => {
+            ("create_account_with_seed", json!({
+                "from": a.key(0)?,
+                "new_account": a.key(1)?,
+                "base": base.to_string(),
+                "seed": seed,
+                "lamports": lamports,
+                "space": space,
+                "owner": owner.to_string()
+            }))
+        }
+        SystemInstruction::AdvanceNonceAccount => ("advance_nonce_account", json!({
+            "nonce_account": a.key(0)?,
+            "recent_blockhashes_sysvar": a.key(1)?,
+            "nonce_authority": a.key(2)?
+        })),
+        SystemInstruction::WithdrawNonceAccount(lamports) => ("withdraw_nonce_account", json!({
+            "nonce_account": a.key(0)?,
+            "to": a.key(1)?,
+            "recent_blockhashes_sysvar": a.key(2)?,
+            "rent_sysvar": a.key(3)?,
+            "nonce_authority": a.key(4)?,
+            "lamports": lamports
+        })),
+        SystemInstruction::InitializeNonceAccount(authority) => ("initialize_nonce_account", json!({
+            "nonce_account": a.key(0)?,
+            "recent_blockhashes_sysvar": a.key(1)?,
+            "rent_sysvar": a.key(2)?,
+            "nonce_authority": authority.to_string()
+        })),
+        SystemInstruction::AuthorizeNonceAccount(new_authority) => ("authorize_nonce_account", json!({
+            "nonce_account": a.key(0)?,
+            "nonce_authority": a.key(1)?,
+            "new_authority": new_authority.to_string()
+        })),
+        SystemInstruction::Allocate { space } => ("allocate", json!({
+            "account": a.key(0)?,
+            "space": space
+        })),
+        SystemInstruction::AllocateWithSeed { base, seed, space, owner } => ("allocate_with_seed", json!({
+            "account": a.key(0)?,
+            "base": base.to_string(),
+            "seed": seed,
+            "space": space,
+            "owner": owner.to_string()
+        })),
+        SystemInstruction::AssignWithSeed { base, seed, owner } => ("assign_with_seed", json!({
+            "account": a.key(0)?,
+            "base": base.to_string(),
+            "seed": seed,
+            "owner": owner.to_string()
+        })),
+        SystemInstruction::TransferWithSeed { lamports, from_seed, from_owner } => ("transfer_with_seed", json!({
+            "from": a.key(0)?,
+            "from_base": a.key(1)?,
+            "to": a.key(2)?,
+            "from_seed": from_seed,
+            "from_owner": from_owner.to_string(),
+            "lamports": lamports
+        })),
+        SystemInstruction::UpgradeNonceAccount => ("upgrade_nonce_account", json!({
+            "nonce_account": a.key(0)?
+        })),
+    })
+}
+
+/// The authority types by the names /token/set-authority accepts.
+fn authority_type_name(authority_type: AuthorityType) -> &'static str {
+    match authority_type {
+        AuthorityType::MintTokens => "mintTokens",
+        AuthorityType::FreezeAccount => "freezeAccount",
+        AuthorityType::AccountOwner => "accountOwner",
+        AuthorityType::CloseAccount => "closeAccount",
+        AuthorityType::TransferFeeConfig => "transferFeeConfig",
+        AuthorityType::WithheldWithdraw => "withheldWithdraw",
+        AuthorityType::CloseMint => "closeMint",
+        AuthorityType::InterestRate => "interestRate",
+        AuthorityType::PermanentDelegate => "permanentDelegate",
+        AuthorityType::ConfidentialTransferMint => "confidentialTransferMint",
+        AuthorityType::TransferHookProgramId => "transferHookProgramId",
+        AuthorityType::ConfidentialTransferFeeConfig => "confidentialTransferFeeConfig",
+        AuthorityType::MetadataPointer => "metadataPointer",
+        AuthorityType::GroupPointer => "groupPointer",
+        AuthorityType::GroupMemberPointer => "groupMemberPointer",
+    }
+}
+
+/// ExtensionType's variant name in camelCase, as spl-token-2022 spells it
+/// in JSON.
+fn extension_type_name(extension: &ExtensionType) -> String {
+    let name = format!("{:?}", extension);
+    let mut chars = name.chars();
+    chars
+        .next()
+        .map(|first| first.to_ascii_lowercase().to_string() + chars.as_str())
+        .unwrap_or_default()
+}
+
+fn optional_pubkey_json(key: impl Into<Option<Pubkey>>) -> serde_json::Value {
+    key.into().map_or(json!(null), |key| json!(key.to_string()))
+}
+
+/// Adds the multisig signers that follow an authority, when there are any.
+fn with_signers(mut info: serde_json::Value, a: &Accounts, first_signer: usize) -> serde_json::Value {
+    let signers = a.rest(first_signer);
+    if !signers.is_empty() {
+        info["signers"] = json!(signers);
+    }
+    info
+}
+
+/// Decodes the original token program and token-2022 alike; the shared
+/// instructions have identical layouts. Token amounts are strings, since
+/// they can exceed what a JSON number holds exactly.
+#[allow(deprecated)]
+fn parse_token(a: &Accounts, data: &[u8]) -> Option<Parsed> {
+    if data.first() == Some(&TRANSFER_FEE_EXTENSION) {
+        return parse_transfer_fee(a, &data[1..]);
+    }
+    Some(match TokenInstruction::unpack(data).ok()? {
+        TokenInstruction::InitializeMint { decimals, mint_authority, freeze_authority } => ("initialize_mint", json!({
+            "mint": a.key(0)?,
+            "rent_sysvar": a.key(1)?,
+            "decimals": decimals,
+            "mint_authority": mint_authority.to_string(),
+            "freeze_authority": optional_pubkey_json(freeze_authority)
+        })),
+        TokenInstruction::InitializeMint2 { decimals, mint_authority, freeze_authority } => ("initialize_mint2", json!({
+            "mint": a.key(0)?,
+            "decimals": decimals,
+            "mint_authority": mint_authority.to_string(),
+            "freeze_authority": optional_pubkey_json(freeze_authority)
+        })),
+        TokenInstruction::InitializeAccount => ("initialize_account", json!({
+            "account": a.key(0)?,
+            "mint": a.key(1)?,
+            "owner": a.key(2)?,
+            "rent_sysvar": a.key(3)?
+        })),
+        TokenInstruction::InitializeAccount2 { owner } => ("initialize_account2", json!({
+            "account": a.key(0)?,
+            "mint": a.key(1)?,
+            "rent_sysvar": a.key(2)?,
+            "owner": owner.to_string()
+        })),
+        TokenInstruction::InitializeAccount3 { owner } => ("initialize_account3", json!({
+            "account": a.key(0)?,
+            "mint": a.key(1)?,
+            "owner": owner.to_string()
+        })),
+        TokenInstruction::InitializeMultisig { m } => ("initialize_multisig", json!({
+            "multisig": a.key(0)?,
+            "rent_sysvar": a.key(1)?,
+            "signers": a.rest(2),
+            "m": m
+        })),
+        TokenInstruction::InitializeMultisig2 { m } => ("initialize_multisig2", json!({
+            "multisig": a.key(0)?,
+            "signers": a.rest(1),
+            "m": m
+        })),
+        TokenInstruction::Transfer { amount } => ("transfer", with_signers(json!({
+            "source": a.key(0)?,
+            "destination": a.key(1)?,
+            "authority": a.key(2)?,
+            "amount": amount.to_string()
+        }), a, 3)),
+        TokenInstruction::Approve { amount } => ("approve", with_signers(json!({
+            "source": a.key(0)?,
+            "delegate": a.key(1)?,
+            "owner": a.key(2)?,
+            "amount": amount.to_string()
+        }), a, 3)),
+        TokenInstruction::Revoke => ("revoke", with_signers(json!({
+            "source": a.key(0)?,
+            "owner": a.key(1)?
+        }), a, 2)),
+        TokenInstruction::SetAuthority { authority_type, new_authority } => {
+            let permanent_delegate = authority_type == AuthorityType::PermanentDelegate;
+            let mut info = with_signers(json!({
+                "account": a.key(0)?,
+                "authority": a.key(1)?,
+                "authority_type": authority_type_name(authority_type),
+                "new_authority": optional_pubkey_json(new_authority)
+            }), a, 2);
+            if permanent_delegate {
+                info["warning"] = json!(PERMANENT_DELEGATE_WARNING);
+            }
+            ("set_authority", info)
+        }
+        TokenInstruction::MintTo { amount } => ("mint_to", with_signers(json!({
+            "mint": a.key(0)?,
+            "account": a.key(1)?,
+            "mint_authority": a.key(2)?,
+            "amount": amount.to_string()
+        }), a, 3)),
+        TokenInstruction::Burn { amount } => ("burn", with_signers(json!({
+            "account": a.key(0)?,
+            "mint": a.key(1)?,
+            "authority": a.key(2)?,
+            "amount": amount.to_string()
+        }), a, 3)),
+        TokenInstruction::CloseAccount => ("close_account", with_signers(json!({
+            "account": a.key(0)?,
+            "destination": a.key(1)?,
+            "owner": a.key(2)?
+        }), a, 3)),
+        TokenInstruction::FreezeAccount => ("freeze_account", with_signers(json!({
+            "account": a.key(0)?,
+            "mint": a.key(1)?,
+            "freeze_authority": a.key(2)?
+        }), a, 3)),
+        TokenInstruction::ThawAccount => ("thaw_account", with_signers(json!({
+            "account": a.key(0)?,
+            "mint": a.key(1)?,
+            "freeze_authority": a.key(2)?
+        }), a, 3)),
+        TokenInstruction::TransferChecked { amount, decimals } => ("transfer_checked", with_signers(json!({
+            "source": a.key(0)?,
+            "mint": a.key(1)?,
+            "destination": a.key(2)?,
+            "authority": a.key(3)?,
+            "amount": amount.to_string(),
+            "decimals": decimals
+        }), a, 4)),
+        TokenInstruction::ApproveChecked { amount, decimals } => ("approve_checked", with_signers(json!({
+            "source": a.key(0)?,
+            "mint": a.key(1)?,
+            "delegate": a.key(2)?,
+            "owner": a.key(3)?,
+            "amount": amount.to_string(),
+            "decimals": decimals
+        }), a, 4)),
+        TokenInstruction::MintToChecked { amount, decimals } => ("mint_to_checked", with_signers(json!({
+            "mint": a.key(0)?,
+            "account": a.key(1)?,
+            "mint_authority": a.key(2)?,
+            "amount": amount.to_string(),
+            "decimals": decimals
+        }), a, 3)),
+        TokenInstruction::BurnChecked { amount, decimals } => ("burn_checked", with_signers(json!({
+            "account": a.key(0)?,
+            "mint": a.key(1)?,
+            "authority": a.key(2)?,
+            "amount": amount.to_string(),
+            "decimals": decimals
+        }), a, 3)),
+        TokenInstruction::SyncNative => ("sync_native", json!({ "account": a.key(0)? })),
+        TokenInstruction::GetAccountDataSize { extension_types } => ("get_account_data_size", json!({
+            "mint": a.key(0)?,
+            "extension_types": extension_types.iter().map(extension_type_name).collect::<Vec<_>>()
+        })),
+        TokenInstruction::InitializeImmutableOwner => ("initialize_immutable_owner", json!({ "account": a.key(0)? })),
+        TokenInstruction::AmountToUiAmount { amount } => ("amount_to_ui_amount", json!({
+            "mint": a.key(0)?,
+            "amount": amount.to_string()
+        })),
+        TokenInstruction::UiAmountToAmount { ui_amount } => ("ui_amount_to_amount", json!({
+            "mint": a.key(0)?,
+            "ui_amount": ui_amount
+        })),
+        TokenInstruction::InitializeMintCloseAuthority { close_authority } => ("initialize_mint_close_authority", json!({
+            "mint": a.key(0)?,
+            "close_authority": optional_pubkey_json(close_authority)
+        })),
+        TokenInstruction::Reallocate { extension_types } => ("reallocate", with_signers(json!({
+            "account": a.key(0)?,
+            "payer": a.key(1)?,
+            "system_program": a.key(2)?,
+            "owner": a.key(3)?,
+            "extension_types": extension_types.iter().map(extension_type_name).collect::<Vec<_>>()
+        }), a, 4)),
+        TokenInstruction::CreateNativeMint => ("create_native_mint", json!({
+            "payer": a.key(0)?,
+            "native_mint": a.key(1)?,
+            "system_program": a.key(2)?
+        })),
+        TokenInstruction::InitializeNonTransferableMint => ("initialize_non_transferable_mint", json!({ "mint": a.key(0)? })),
+        TokenInstruction::InitializePermanentDelegate { delegate } => ("initialize_permanent_delegate", json!({
+            "mint": a.key(0)?,
+            "delegate": delegate.to_string(),
+            "warning": PERMANENT_DELEGATE_WARNING
+        })),
+        TokenInstruction::WithdrawExcessLamports => ("withdraw_excess_lamports", with_signers(json!({
+            "source": a.key(0)?,
+            "destination": a.key(1)?,
+            "authority": a.key(2)?
+        }), a, 3)),
+        // The remaining extensions carry their own sub-instruction sets; they
+        // fall back to raw data.
+        TokenInstruction::TransferFeeExtension
+        | TokenInstruction::ConfidentialTransferExtension
+        | TokenInstruction::DefaultAccountStateExtension
+        | TokenInstruction::MemoTransferExtension
+        | TokenInstruction::InterestBearingMintExtension
+        | TokenInstruction::CpiGuardExtension
+        | TokenInstruction::TransferHookExtension
+        | TokenInstruction::ConfidentialTransferFeeExtension
+        | TokenInstruction::MetadataPointerExtension
+        | TokenInstruction::GroupPointerExtension
+        | TokenInstruction::GroupMemberPointerExtension => return None,
+    })
+}
+
+fn parse_transfer_fee(a: &Accounts, data: &[u8]) -> Option<Parsed> {
+    Some(match TransferFeeInstruction::unpack(data).ok()? {
+        TransferFeeInstruction::InitializeTransferFeeConfig {
+            transfer_fee_config_authority,
+            withdraw_withheld_authority,
+            transfer_fee_basis_points,
+            maximum_fee,
+        } => ("initialize_transfer_fee_config", json!({
+            "mint": a.key(0)?,
+            "transfer_fee_config_authority": optional_pubkey_json(transfer_fee_config_authority),
+            "withdraw_withheld_authority": optional_pubkey_json(withdraw_withheld_authority),
+            "transfer_fee_basis_points": transfer_fee_basis_points,
+            "maximum_fee": maximum_fee.to_string()
+        })),
+        TransferFeeInstruction::TransferCheckedWithFee { amount, decimals, fee } => {
+            ("transfer_checked_with_fee", with_signers(json!({
+                "source": a.key(0)?,
+                "mint": a.key(1)?,
+                "destination": a.key(2)?,
+                "authority": a.key(3)?,
+                "amount": amount.to_string(),
+                "decimals": decimals,
+                "fee": fee.to_string()
+            }), a, 4))
+        }
+        TransferFeeInstruction::WithdrawWithheldTokensFromMint => {
+            ("withdraw_withheld_tokens_from_mint", with_signers(json!({
+                "mint": a.key(0)?,
+                "destination": a.key(1)?,
+                "withdraw_withheld_authority": a.key(2)?
+            }), a, 3))
+        }
+        TransferFeeInstruction::WithdrawWithheldTokensFromAccounts { num_token_accounts } => {
+            // Multisig signers sit between the authority and the trailing
+            // source accounts.
+            let sources_start = a.0.len().checked_sub(usize::from(num_token_accounts))?;
+            if sources_start < 3 {
+                return None;
+            }
+            let mut info = json!({
+                "mint": a.key(0)?,
+                "destination": a.key(1)?,
+                "withdraw_withheld_authority": a.key(2)?,
+                "source_accounts": a.rest(sources_start)
+            });
+            let signers: Vec<_> = (3..sources_start).filter_map(|i| a.key(i)).collect();
+            if !signers.is_empty() {
+                info["signers"] = json!(signers);
+            }
+            ("withdraw_withheld_tokens_from_accounts", info)
+        }
+        TransferFeeInstruction::HarvestWithheldTokensToMint => ("harvest_withheld_tokens_to_mint", json!({
+            "mint": a.key(0)?,
+            "source_accounts": a.rest(1)
+        })),
+        TransferFeeInstruction::SetTransferFee { transfer_fee_basis_points, maximum_fee } => {
+            ("set_transfer_fee", with_signers(json!({
+                "mint": a.key(0)?,
+                "transfer_fee_config_authority": a.key(1)?,
+                "transfer_fee_basis_points": transfer_fee_basis_points,
+                "maximum_fee": maximum_fee.to_string()
+            }), a, 2))
+        }
+    })
+}
+
+fn parse_associated_token_account(a: &Accounts, data: &[u8]) -> Option<Parsed> {
+    // Empty data is the original Create, from before the instruction enum.
+    match data {
+        [] | [0] | [1] => {
+            let kind = if data == [1] { "create_idempotent" } else { "create" };
+            Some((kind, json!({
+                "payer": a.key(0)?,
+                "account": a.key(1)?,
+                "wallet": a.key(2)?,
+                "mint": a.key(3)?,
+                "system_program": a.key(4)?,
+                "token_program": a.key(5)?
+            })))
+        }
+        [2] => Some(("recover_nested", json!({
+            "nested_account": a.key(0)?,
+            "nested_mint": a.key(1)?,
+            "destination": a.key(2)?,
+            "owner_account": a.key(3)?,
+            "owner_mint": a.key(4)?,
+            "wallet": a.key(5)?,
+            "token_program": a.key(6)?
+        }))),
+        _ => None,
+    }
+}
+
+fn parse_memo(a: &Accounts, data: &[u8]) -> Option<Parsed> {
+    let memo = std::str::from_utf8(data).ok()?;
+    Some(("memo", json!({
+        "memo": memo,
+        "signers": a.rest(0)
+    })))
+}
+
+/// Compute budget instructions are borsh: a one-byte tag, then the
+/// little-endian argument.
+fn parse_compute_budget(data: &[u8]) -> Option<Parsed> {
+    let (&tag, arg) = data.split_first()?;
+    let u32_arg = || arg.try_into().ok().map(u32::from_le_bytes);
+    Some(match tag {
+        1 => ("request_heap_frame", json!({ "bytes": u32_arg()? })),
+        2 => ("set_compute_unit_limit", json!({ "units": u32_arg()? })),
+        3 => ("set_compute_unit_price", json!({
+            "micro_lamports": arg.try_into().ok().map(u64::from_le_bytes)?
+        })),
+        4 => ("set_loaded_accounts_data_size_limit", json!({ "bytes": u32_arg()? })),
+        _ => return None,
+    })
+}
+
+/// The instruction as given, with `parsed` added: the decoded form for a
+/// well-known program, or null when only the raw data is available.
+pub(crate) async fn decode_instruction(Json(req): Json<InstructionParam>) -> ApiResult {
+    let ix = parse_instruction("", &req)?;
+    let accounts: Vec<_> = ix.accounts.iter().map(|meta| Some(meta.pubkey)).collect();
+
+    let mut data = instruction_to_json(&ix);
+    data["parsed"] = parse_known_instruction(&ix.program_id, &accounts, &ix.data).unwrap_or(json!(null));
+    success(data)
+}
+
+const SYSTEM_TYPES: &[&str] = &[
+    "transfer",
+    "create_account",
+    "create_account_with_seed",
+    "assign",
+    "assign_with_seed",
+    "allocate",
+    "allocate_with_seed",
+    "transfer_with_seed",
+    "advance_nonce_account",
+    "withdraw_nonce_account",
+    "initialize_nonce_account",
+    "authorize_nonce_account",
+    "upgrade_nonce_account",
+];
+const TOKEN_TYPES: &[&str] = &[
+    "initialize_mint",
+    "initialize_mint2",
+    "initialize_account",
+    "initialize_account2",
+    "initialize_account3",
+    "initialize_multisig",
+    "initialize_multisig2",
+    "transfer",
+    "approve",
+    "revoke",
+    "set_authority",
+    "mint_to",
+    "burn",
+    "close_account",
+    "freeze_account",
+    "thaw_account",
+    "transfer_checked",
+    "approve_checked",
+    "mint_to_checked",
+    "burn_checked",
+    "sync_native",
+    "get_account_data_size",
+    "initialize_immutable_owner",
+    "amount_to_ui_amount",
+    "ui_amount_to_amount",
+];
+/// Accepted for spl-token-2022 on top of TOKEN_TYPES.
+const TOKEN_2022_TYPES: &[&str] = &[
+    "initialize_mint_close_authority",
+    "reallocate",
+    "create_native_mint",
+    "initialize_non_transferable_mint",
+    "initialize_permanent_delegate",
+    "withdraw_excess_lamports",
+    "initialize_transfer_fee_config",
+    "transfer_checked_with_fee",
+    "withdraw_withheld_tokens_from_mint",
+    "withdraw_withheld_tokens_from_accounts",
+    "harvest_withheld_tokens_to_mint",
+    "set_transfer_fee",
+];
+const ATA_TYPES: &[&str] = &["create", "create_idempotent", "recover_nested"];
+const MEMO_TYPES: &[&str] = &["memo"];
+const COMPUTE_BUDGET_TYPES: &[&str] = &[
+    "request_heap_frame",
+    "set_compute_unit_limit",
+    "set_compute_unit_price",
+    "set_loaded_accounts_data_size_limit",
+];
+const PROGRAMS: &[&str] = &[
+    "system",
+    "spl-token",
+    "spl-token-2022",
+    "spl-associated-token-account",
+    "spl-memo",
+    "compute-budget",
+];
+
+const AUTHORITY_TYPES: [AuthorityType; 15] = [
+    AuthorityType::MintTokens,
+    AuthorityType::FreezeAccount,
+    AuthorityType::AccountOwner,
+    AuthorityType::CloseAccount,
+    AuthorityType::TransferFeeConfig,
+    AuthorityType::WithheldWithdraw,
+    AuthorityType::CloseMint,
+    AuthorityType::InterestRate,
+    AuthorityType::PermanentDelegate,
+    AuthorityType::ConfidentialTransferMint,
+    AuthorityType::TransferHookProgramId,
+    AuthorityType::ConfidentialTransferFeeConfig,
+    AuthorityType::MetadataPointer,
+    AuthorityType::GroupPointer,
+    AuthorityType::GroupMemberPointer,
+];
+
+#[derive(Deserialize)]
+pub(crate) struct EncodeInstructionRequest {
+    program: String,
+    #[serde(rename = "type")]
+    kind: String,
+    #[serde(default)]
+    info: serde_json::Map<String, serde_json::Value>,
+}
+
+/// The `info` object of an instruction to encode. Field names and formats
+/// are the ones parse_known_instruction emits; numbers may also be given as
+/// decimal strings, as token amounts are.
+struct Info<'a>(&'a serde_json::Map<String, serde_json::Value>);
+
+impl Info<'_> {
+    fn get(&self, name: &str) -> Option<&serde_json::Value> {
+        self.0.get(name).filter(|value| !value.is_null())
+    }
+
+    fn str(&self, name: &str) -> Result<&str, ApiError> {
+        self.get(name)
+            .ok_or_else(|| ApiError::bad_request(format!("Missing info.{}", name)))?
+            .as_str()
+            .ok_or_else(|| ApiError::bad_request(format!("Invalid info.{}: expected a string", name)))
+    }
+
+    fn pubkey(&self, name: &str) -> Result<Pubkey, ApiError> {
+        parse_pubkey(&format!("info.{}", name), self.str(name)?)
+    }
+
+    fn optional_pubkey(&self, name: &str) -> Result<Option<Pubkey>, ApiError> {
+        self.get(name).map(|_| self.pubkey(name)).transpose()
+    }
+
+    /// A list of pubkeys; absent means empty, as with multisig signers.
+    fn pubkeys(&self, name: &str) -> Result<Vec<Pubkey>, ApiError> {
+        let Some(value) = self.get(name) else {
+            return Ok(Vec::new());
+        };
+        let items = value
+            .as_array()
+            .ok_or_else(|| ApiError::bad_request(format!("Invalid info.{}: expected an array", name)))?;
+        items
+            .iter()
+            .enumerate()
+            .map(|(i, item)| {
+                let field = format!("info.{}[{}]", name, i);
+                let item = item
+                    .as_str()
+                    .ok_or_else(|| ApiError::bad_request(format!("Invalid {}: expected a string", field)))?;
+                parse_pubkey(&field, item)
+            })
+            .collect()
+    }
+
+    fn int<T: TryFrom<u64>>(&self, name: &str) -> Result<T, ApiError> {
+        let value = match self.get(name) {
+            Some(serde_json::Value::Number(n)) => n.as_u64(),
+            Some(serde_json::Value::String(s)) => s.parse().ok(),
+            Some(_) => None,
+            None => return Err(ApiError::bad_request(format!("Missing info.{}", name))),
+        };
+        value
+            .and_then(|value| T::try_from(value).ok())
+            .ok_or_else(|| ApiError::bad_request(format!("Invalid info.{}: expected an unsigned integer in range", name)))
+    }
+
+    fn authority_type(&self, name: &str) -> Result<AuthorityType, ApiError> {
+        let value = self.str(name)?;
+        AUTHORITY_TYPES
+            .iter()
+            .find(|authority_type| authority_type_name((*authority_type).clone()) == value)
+            .cloned()
+            .ok_or_else(|| {
+                let accepted: Vec<_> = AUTHORITY_TYPES.iter().cloned().map(authority_type_name).collect();
+                ApiError::bad_request(format!(
+                    "Invalid info.{}: {:?}, expected one of: {}",
+                    name,
+                    value,
+                    accepted.join(", ")
+                ))
+            })
+    }
+
+    fn extension_types(&self, name: &str) -> Result<Vec<ExtensionType>, ApiError> {
+        let Some(value) = self.get(name) else {
+            return Ok(Vec::new());
+        };
+        let known: Vec<ExtensionType> = (0..=u16::MAX).filter_map(|i| ExtensionType::try_from(i).ok()).collect();
+        value
+            .as_array()
+            .ok_or_else(|| ApiError::bad_request(format!("Invalid info.{}: expected an array", name)))?
+            .iter()
+            .enumerate()
+            .map(|(i, item)| {
+                known
+                    .iter()
+                    .find(|extension| item.as_str() == Some(extension_type_name(extension).as_str()))
+                    .copied()
+                    .ok_or_else(|| ApiError::bad_request(format!("Invalid info.{}[{}]: unknown extension type", name, i)))
+            })
+            .collect()
+    }
+}
+
+fn unsupported_type(program: &str, kind: &str, supported: &[&str]) -> ApiError {
+    ApiError::bad_request(format!(
+        "Unsupported type {:?} for {}, expected one of: {}",
+        kind,
+        program,
+        supported.join(", ")
+    ))
+}
+
+fn token_build_error(e: ProgramError) -> ApiError {
+    ApiError::bad_request(format!("Failed to build instruction: {}", e))
+}
+
+fn encode_system(kind: &str, info: &Info) -> Result<Option<Instruction>, ApiError> {
+    Ok(Some(match kind {
+        "transfer" => system_instruction::transfer(&info.pubkey("from")?, &info.pubkey("to")?, info.int("lamports")?),
+        "create_account" => system_instruction::create_account(
+            &info.pubkey("from")?,
+            &info.pubkey("new_account")?,
+            info.int("lamports")?,
+            info.int("space")?,
+            &info.pubkey("owner")?,
+        ),
+        "create_account_with_seed" => system_instruction::create_account_with_seed(
+            &info.pubkey("from")?,
+            &info.pubkey("new_account")?,
+            &info.pubkey("base")?,
+            info.str("seed")?,
+            info.int("lamports")?,
+            info.int("space")?,
+            &info.pubkey("owner")?,
+        ),
+        "assign" => system_instruction::assign(&info.pubkey("account")?, &info.pubkey("owner")?),
+        "assign_with_seed" => system_instruction::assign_with_seed(
+            &info.pubkey("account")?,
+            &info.pubkey("base")?,
+            info.str("seed")?,
+            &info.pubkey("owner")?,
+        ),
+        "allocate" => system_instruction::allocate(&info.pubkey("account")?, info.int("space")?),
+        "allocate_with_seed" => system_instruction::allocate_with_seed(
+            &info.pubkey("account")?,
+            &info.pubkey("base")?,
+            info.str("seed")?,
+            info.int("space")?,
+            &info.pubkey("owner")?,
+        ),
+        "transfer_with_seed" => system_instruction::transfer_with_seed(
+            &info.pubkey("from")?,
+            &info.pubkey("from_base")?,
+            info.str("from_seed")?.to_string(),
+            &info.pubkey("from_owner")?,
+            &info.pubkey("to")?,
+            info.int("lamports")?,
+        ),
+        "advance_nonce_account" => {
+            system_instruction::advance_nonce_account(&info.pubkey("nonce_account")?, &info.pubkey("nonce_authority")?)
+        }
+        "withdraw_nonce_account" => system_instruction::withdraw_nonce_account(
+            &info.pubkey("nonce_account")?,
+            &info.pubkey("nonce_authority")?,
+            &info.pubkey("to")?,
+            info.int("lamports")?,
+        ),
+        // Only available from the SDK bundled with create_account.
+        "initialize_nonce_account" => {
+            #[allow(deprecated)]
+            let recent_blockhashes = solana_program::sysvar::recent_blockhashes::id();
+            Instruction::new_with_bincode(
+                system_program::id(),
+                &SystemInstruction::InitializeNonceAccount(info.pubkey("nonce_authority")?),
+                vec![
+                    AccountMeta::new(info.pubkey("nonce_account")?, false),
+                    AccountMeta::new_readonly(recent_blockhashes, false),
+                    AccountMeta::new_readonly(solana_program::sysvar::rent::id(), false),
+                ],
+            )
+        }
+        "authorize_nonce_account" => system_instruction::authorize_nonce_account(
+            &info.pubkey("nonce_account")?,
+            &info.pubkey("nonce_authority")?,
+            &info.pubkey("new_authority")?,
+        ),
+        "upgrade_nonce_account" => system_instruction::upgrade_nonce_account(info.pubkey("nonce_account")?),
+        _ => return Ok(None),
+    }))
+}
+
+/// Builds with the token-2022 builders, which accept either program id.
+#[allow(deprecated)]
+fn encode_token(program_id: &Pubkey, kind: &str, info: &Info) -> Result<Option<Instruction>, ApiError> {
+    let signers = info.pubkeys("signers")?;
+    let signers: Vec<&Pubkey> = signers.iter().collect();
+    let p = program_id;
+
+    let ix = match kind {
+        "initialize_mint" => token_instruction::initialize_mint(
+            p,
+            &info.pubkey("mint")?,
+            &info.pubkey("mint_authority")?,
+            info.optional_pubkey("freeze_authority")?.as_ref(),
+            info.int("decimals")?,
+        ),
+        "initialize_mint2" => token_instruction::initialize_mint2(
+            p,
+            &info.pubkey("mint")?,
+            &info.pubkey("mint_authority")?,
+            info.optional_pubkey("freeze_authority")?.as_ref(),
+            info.int("decimals")?,
+        ),
+        "initialize_account" => token_instruction::initialize_account(
+            p,
+            &info.pubkey("account")?,
+            &info.pubkey("mint")?,
+            &info.pubkey("owner")?,
+        ),
+        "initialize_account2" => token_instruction::initialize_account2(
+            p,
+            &info.pubkey("account")?,
+            &info.pubkey("mint")?,
+            &info.pubkey("owner")?,
+        ),
+        "initialize_account3" => token_instruction::initialize_account3(
+            p,
+            &info.pubkey("account")?,
+            &info.pubkey("mint")?,
+            &info.pubkey("owner")?,
+        ),
+        "initialize_multisig" => {
+            token_instruction::initialize_multisig(p, &info.pubkey("multisig")?, &signers, info.int("m")?)
+        }
+        "initialize_multisig2" => {
+            token_instruction::initialize_multisig2(p, &info.pubkey("multisig")?, &signers, info.int("m")?)
+        }
+        "transfer" => token_instruction::transfer(
+            p,
+            &info.pubkey("source")?,
+            &info.pubkey("destination")?,
+            &info.pubkey("authority")?,
+            &signers,
+            info.int("amount")?,
+        ),
+        "approve" => token_instruction::approve(
+            p,
+            &info.pubkey("source")?,
+            &info.pubkey("delegate")?,
+            &info.pubkey("owner")?,
+            &signers,
+            info.int("amount")?,
+        ),
+        "revoke" => token_instruction::revoke(p, &info.pubkey("source")?, &info.pubkey("owner")?, &signers),
+        "set_authority" => token_instruction::set_authority(
+            p,
+            &info.pubkey("account")?,
+            info.optional_pubkey("new_authority")?.as_ref(),
+            info.authority_type("authority_type")?,
+            &info.pubkey("authority")?,
+            &signers,
+        ),
+        "mint_to" => token_instruction::mint_to(
+            p,
+            &info.pubkey("mint")?,
+            &info.pubkey("account")?,
+            &info.pubkey("mint_authority")?,
+            &signers,
+            info.int("amount")?,
+        ),
+        "burn" => token_instruction::burn(
+            p,
+            &info.pubkey("account")?,
+            &info.pubkey("mint")?,
+            &info.pubkey("authority")?,
+            &signers,
+            info.int("amount")?,
+        ),
+        "close_account" => token_instruction::close_account(
+            p,
+            &info.pubkey("account")?,
+            &info.pubkey("destination")?,
+            &info.pubkey("owner")?,
+            &signers,
+        ),
+        "freeze_account" => token_instruction::freeze_account(
+            p,
+            &info.pubkey("account")?,
+            &info.pubkey("mint")?,
+            &info.pubkey("freeze_authority")?,
+            &signers,
+        ),
+        "thaw_account" => token_instruction::thaw_account(
+            p,
+            &info.pubkey("account")?,
+            &info.pubkey("mint")?,
+            &info.pubkey("freeze_authority")?,
+            &signers,
+        ),
+        "transfer_checked" => token_instruction::transfer_checked(
+            p,
+            &info.pubkey("source")?,
+            &info.pubkey("mint")?,
+            &info.pubkey("destination")?,
+            &info.pubkey("authority")?,
+            &signers,
+            info.int("amount")?,
+            info.int("decimals")?,
+        ),
+        "approve_checked" => token_instruction::approve_checked(
+            p,
+            &info.pubkey("source")?,
+            &info.pubkey("mint")?,
+            &info.pubkey("delegate")?,
+            &info.pubkey("owner")?,
+            &signers,
+            info.int("amount")?,
+            info.int("decimals")?,
+        ),
+        "mint_to_checked" => token_instruction::mint_to_checked(
+            p,
+            &info.pubkey("mint")?,
+            &info.pubkey("account")?,
+            &info.pubkey("mint_authority")?,
+            &signers,
+            info.int("amount")?,
+            info.int("decimals")?,
+        ),
+        "burn_checked" => token_instruction::burn_checked(
+            p,
+            &info.pubkey("account")?,
+            &info.pubkey("mint")?,
+            &info.pubkey("authority")?,
+            &signers,
+            info.int("amount")?,
+            info.int("decimals")?,
+        ),
+        "sync_native" => token_instruction::sync_native(p, &info.pubkey("account")?),
+        "get_account_data_size" => {
+            token_instruction::get_account_data_size(p, &info.pubkey("mint")?, &info.extension_types("extension_types")?)
+        }
+        "initialize_immutable_owner" => token_instruction::initialize_immutable_owner(p, &info.pubkey("account")?),
+        "amount_to_ui_amount" => token_instruction::amount_to_ui_amount(p, &info.pubkey("mint")?, info.int("amount")?),
+        "ui_amount_to_amount" => {
+            token_instruction::ui_amount_to_amount(p, &info.pubkey("mint")?, info.str("ui_amount")?)
+        }
+        "initialize_mint_close_authority" => token_instruction::initialize_mint_close_authority(
+            p,
+            &info.pubkey("mint")?,
+            info.optional_pubkey("close_authority")?.as_ref(),
+        ),
+        "reallocate" => token_instruction::reallocate(
+            p,
+            &info.pubkey("account")?,
+            &info.pubkey("payer")?,
+            &info.pubkey("owner")?,
+            &signers,
+            &info.extension_types("extension_types")?,
+        ),
+        "create_native_mint" => token_instruction::create_native_mint(p, &info.pubkey("payer")?),
+        "initialize_non_transferable_mint" => {
+            token_instruction::initialize_non_transferable_mint(p, &info.pubkey("mint")?)
+        }
+        "initialize_permanent_delegate" => {
+            token_instruction::initialize_permanent_delegate(p, &info.pubkey("mint")?, &info.pubkey("delegate")?)
+        }
+        "withdraw_excess_lamports" => token_instruction::withdraw_excess_lamports(
+            p,
+            &info.pubkey("source")?,
+            &info.pubkey("destination")?,
+            &info.pubkey("authority")?,
+            &signers,
+        ),
+        "initialize_transfer_fee_config" => transfer_fee_instruction::initialize_transfer_fee_config(
+            p,
+            &info.pubkey("mint")?,
+            info.optional_pubkey("transfer_fee_config_authority")?.as_ref(),
+            info.optional_pubkey("withdraw_withheld_authority")?.as_ref(),
+            info.int("transfer_fee_basis_points")?,
+            info.int("maximum_fee")?,
+        ),
+        "transfer_checked_with_fee" => transfer_fee_instruction::transfer_checked_with_fee(
+            p,
+            &info.pubkey("source")?,
+            &info.pubkey("mint")?,
+            &info.pubkey("destination")?,
+            &info.pubkey("authority")?,
+            &signers,
+            info.int("amount")?,
+            info.int("decimals")?,
+            info.int("fee")?,
+        ),
+        "withdraw_withheld_tokens_from_mint" => transfer_fee_instruction::withdraw_withheld_tokens_from_mint(
+            p,
+            &info.pubkey("mint")?,
+            &info.pubkey("destination")?,
+            &info.pubkey("withdraw_withheld_authority")?,
+            &signers,
+        ),
+        "withdraw_withheld_tokens_from_accounts" => {
+            let sources = info.pubkeys("source_accounts")?;
+            transfer_fee_instruction::withdraw_withheld_tokens_from_accounts(
+                p,
+                &info.pubkey("mint")?,
+                &info.pubkey("destination")?,
+                &info.pubkey("withdraw_withheld_authority")?,
+                &signers,
+                &sources.iter().collect::<Vec<_>>(),
+            )
+        }
+        "harvest_withheld_tokens_to_mint" => {
+            let sources = info.pubkeys("source_accounts")?;
+            transfer_fee_instruction::harvest_withheld_tokens_to_mint(
+                p,
+                &info.pubkey("mint")?,
+                &sources.iter().collect::<Vec<_>>(),
+            )
+        }
+        "set_transfer_fee" => transfer_fee_instruction::set_transfer_fee(
+            p,
+            &info.pubkey("mint")?,
+            &info.pubkey("transfer_fee_config_authority")?,
+            &signers,
+            info.int("transfer_fee_basis_points")?,
+            info.int("maximum_fee")?,
+        ),
+        _ => return Ok(None),
+    };
+    ix.map(Some).map_err(token_build_error)
+}
+
+fn encode_associated_token_account(kind: &str, info: &Info) -> Result<Option<Instruction>, ApiError> {
+    if !ATA_TYPES.contains(&kind) {
+        return Ok(None);
+    }
+    let token_program = info.pubkey("token_program")?;
+    Ok(Some(match kind {
+        "create" => ata_instruction::create_associated_token_account(
+            &info.pubkey("payer")?,
+            &info.pubkey("wallet")?,
+            &info.pubkey("mint")?,
+            &token_program,
+        ),
+        "create_idempotent" => ata_instruction::create_associated_token_account_idempotent(
+            &info.pubkey("payer")?,
+            &info.pubkey("wallet")?,
+            &info.pubkey("mint")?,
+            &token_program,
+        ),
+        "recover_nested" => ata_instruction::recover_nested(
+            &info.pubkey("wallet")?,
+            &info.pubkey("owner_mint")?,
+            &info.pubkey("nested_mint")?,
+            &token_program,
+        ),
+        _ => return Ok(None),
+    }))
+}
+
+fn encode_memo(kind: &str, info: &Info) -> Result<Option<Instruction>, ApiError> {
+    if kind != "memo" {
+        return Ok(None);
+    }
+    let signers = info.pubkeys("signers")?;
+    let memo = info.str("memo")?;
+    validate_memo("info.memo", memo.as_bytes())?;
+    Ok(Some(spl_memo::build_memo(memo.as_bytes(), &signers.iter().collect::<Vec<_>>())))
+}
+
+fn encode_compute_budget(kind: &str, info: &Info) -> Result<Option<Instruction>, ApiError> {
+    Ok(Some(match kind {
+        "request_heap_frame" => ComputeBudgetInstruction::request_heap_frame(info.int("bytes")?),
+        "set_compute_unit_limit" => ComputeBudgetInstruction::set_compute_unit_limit(info.int("units")?),
+        "set_compute_unit_price" => ComputeBudgetInstruction::set_compute_unit_price(info.int("micro_lamports")?),
+        "set_loaded_accounts_data_size_limit" => {
+            ComputeBudgetInstruction::set_loaded_accounts_data_size_limit(info.int("bytes")?)
+        }
+        _ => return Ok(None),
+    }))
+}
+
+/// Equal as JSON, or the same integer written once as a number and once as
+/// a string.
+fn same_value(given: &serde_json::Value, decoded: &serde_json::Value) -> bool {
+    use serde_json::Value;
+    given == decoded
+        || matches!(
+            (given, decoded),
+            (Value::Number(n), Value::String(s)) | (Value::String(s), Value::Number(n)) if n.to_string() == *s
+        )
+}
+
+/// Builds an instruction from the `{ program, type, info }` shape that
+/// /instruction/decode emits. Accounts the instruction fixes or derives
+/// (sysvars, the associated token address) may be left out; any that are
+/// given must agree with what gets built.
+pub(crate) async fn encode_instruction(Json(req): Json<EncodeInstructionRequest>) -> ApiResult {
+    let info = Info(&req.info);
+    let kind = req.kind.as_str();
+    let (ix, supported) = match req.program.as_str() {
+        "system" => (encode_system(kind, &info)?, SYSTEM_TYPES.to_vec()),
+        "spl-token" if !TOKEN_TYPES.contains(&kind) => (None, TOKEN_TYPES.to_vec()),
+        "spl-token" => (encode_token(&spl_token::id(), kind, &info)?, TOKEN_TYPES.to_vec()),
+        "spl-token-2022" => (
+            encode_token(&spl_token_2022::id(), kind, &info)?,
+            [TOKEN_TYPES, TOKEN_2022_TYPES].concat(),
+        ),
+        "spl-associated-token-account" => (encode_associated_token_account(kind, &info)?, ATA_TYPES.to_vec()),
+        "spl-memo" => (encode_memo(kind, &info)?, MEMO_TYPES.to_vec()),
+        "compute-budget" => (encode_compute_budget(kind, &info)?, COMPUTE_BUDGET_TYPES.to_vec()),
+        program => {
+            return Err(ApiError::bad_request(format!(
+                "Unsupported program {:?}, expected one of: {}",
+                program,
+                PROGRAMS.join(", ")
+            )))
+        }
+    };
+    let ix = ix.ok_or_else(|| unsupported_type(&req.program, kind, &supported))?;
+
+    // Decoding what was built is the check that every given field was used
+    // as the caller meant it.
+    let accounts: Vec<_> = ix.accounts.iter().map(|meta| Some(meta.pubkey)).collect();
+    let decoded = parse_known_instruction(&ix.program_id, &accounts, &ix.data)
+        .ok_or_else(|| ApiError::internal("Encoded instruction does not decode"))?;
+    for (name, given) in &req.info {
+        // The decoder's warnings are for people reading the output, so a
+        // decoded instruction fed back in may carry one in any wording.
+        if name == "warning" {
+            continue;
+        }
+        match decoded["info"].get(name) {
+            Some(built) if same_value(given, built) => {}
+            None if given.is_null() || given.as_array().is_some_and(|items| items.is_empty()) => {}
+            None => {
+                return Err(ApiError::bad_request(format!(
+                    "info.{} is not a field of {} {}",
+                    name, req.program, kind
+                )))
+            }
+            Some(built) => {
+                return Err(ApiError::bad_request(format!(
+                    "info.{} ({}) does not match the built instruction ({})",
+                    name, given, built
+                )))
+            }
+        }
+    }
+
+    instruction_response(&ix)
+}
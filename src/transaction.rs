@@ -0,0 +1,879 @@
+// src/transaction.rs
+
+use axum::Json;
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+use bincode::Options;
+use serde::Deserialize;
+use serde_json::json;
+use solana_program::address_lookup_table::state::LOOKUP_TABLE_MAX_ADDRESSES;
+use solana_program::system_instruction::SystemInstruction;
+use solana_program::{system_instruction, system_program};
+use solana_sdk::address_lookup_table::AddressLookupTableAccount;
+use solana_sdk::compute_budget;
+use solana_sdk::hash::Hash;
+use solana_sdk::instruction::{AccountMeta, Instruction};
+use solana_sdk::message::{v0, CompileError, Message, VersionedMessage};
+use solana_sdk::packet::PACKET_DATA_SIZE;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{Keypair, Signature, Signer};
+use solana_sdk::transaction::VersionedTransaction;
+
+use crate::parsed_instruction::parse_known_instruction;
+use crate::{
+    compute_budget_instructions, decode_secret, decode_signature, keypair_from_secret, optional_string_or_u64,
+    parse_blockhash, parse_enum, parse_pubkey, parse_pubkey_list, signature_json, success, ApiError, ApiResult,
+    SecretString, SignatureEncodingParam,
+};
+
+/// An account as emitted by the instruction endpoints. The camelCase
+/// spellings are accepted too, for clients that normalise keys.
+#[derive(Deserialize)]
+pub(crate) struct AccountMetaParam {
+    pubkey: String,
+    #[serde(alias = "isSigner")]
+    is_signer: bool,
+    #[serde(alias = "isWritable")]
+    is_writable: bool,
+}
+
+/// An instruction in the shape instruction_to_json emits, so any endpoint's
+/// output can be fed straight back in.
+#[derive(Deserialize)]
+pub(crate) struct InstructionParam {
+    #[serde(alias = "programId")]
+    program_id: String,
+    #[serde(default)]
+    accounts: Vec<AccountMetaParam>,
+    #[serde(default, alias = "data")]
+    instruction_data: String,
+}
+
+#[derive(Deserialize)]
+pub(crate) struct SignTransactionRequest {
+    transaction: String,
+    secrets: Vec<SecretString>,
+    /// Applies to every entry of `secrets`.
+    #[serde(rename = "secretEncoding")]
+    secret_encoding: Option<String>,
+    #[serde(rename = "signatureEncoding")]
+    signature_encoding: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub(crate) struct PartialSignTransactionRequest {
+    transaction: String,
+    secret: SecretString,
+    #[serde(rename = "secretEncoding")]
+    secret_encoding: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub(crate) struct SignatureEntryParam {
+    pubkey: String,
+    signature: String,
+}
+
+#[derive(Deserialize)]
+pub(crate) struct AssembleTransactionRequest {
+    message: String,
+    #[serde(default)]
+    signatures: Vec<SignatureEntryParam>,
+    #[serde(rename = "signatureEncoding")]
+    signature_encoding: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub(crate) struct AttachSignatureRequest {
+    transaction: String,
+    pubkey: String,
+    signature: String,
+}
+
+#[derive(Deserialize)]
+pub(crate) struct UpdateBlockhashRequest {
+    transaction: String,
+    #[serde(rename = "recentBlockhash")]
+    recent_blockhash: String,
+    #[serde(rename = "signatureEncoding")]
+    signature_encoding: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub(crate) struct SerializedTransactionRequest {
+    transaction: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+enum TransactionVersionParam {
+    Legacy,
+    V0,
+}
+
+impl TransactionVersionParam {
+    const ACCEPTED: &'static [&'static str] = &["legacy", "v0"];
+}
+
+/// An address lookup table's contents as the client last fetched them.
+#[derive(Deserialize)]
+pub(crate) struct LookupTableParam {
+    #[serde(rename = "accountKey")]
+    account_key: String,
+    addresses: Vec<String>,
+}
+
+#[derive(Deserialize)]
+pub(crate) struct BuildTransactionRequest {
+    instructions: Vec<InstructionParam>,
+    #[serde(rename = "feePayer")]
+    fee_payer: String,
+    #[serde(rename = "recentBlockhash")]
+    recent_blockhash: Option<String>,
+    /// Durable nonce mode: the nonce account, its authority, and its current
+    /// value, which stands in for recentBlockhash.
+    #[serde(rename = "nonceAccount")]
+    nonce_account: Option<String>,
+    #[serde(rename = "nonceAuthority")]
+    nonce_authority: Option<String>,
+    nonce: Option<String>,
+    #[serde(default, rename = "computeUnitLimit", deserialize_with = "optional_string_or_u64")]
+    compute_unit_limit: Option<u64>,
+    #[serde(default, rename = "computeUnitPriceMicroLamports", deserialize_with = "optional_string_or_u64")]
+    compute_unit_price_micro_lamports: Option<u64>,
+    version: Option<String>,
+    #[serde(default, rename = "addressLookupTables")]
+    address_lookup_tables: Vec<LookupTableParam>,
+}
+
+/// Parses instruction JSON back into an Instruction, naming the offending
+/// field by its position, e.g. "instructions[1].accounts[0].pubkey". An
+/// empty `field` is a top-level instruction.
+pub(crate) fn parse_instruction(field: &str, ix: &InstructionParam) -> Result<Instruction, ApiError> {
+    let path = |name: String| if field.is_empty() { name } else { format!("{}.{}", field, name) };
+    let program_id = parse_pubkey(&path("program_id".to_string()), &ix.program_id)?;
+    let accounts = ix
+        .accounts
+        .iter()
+        .enumerate()
+        .map(|(i, meta)| {
+            let pubkey = parse_pubkey(&path(format!("accounts[{}].pubkey", i)), &meta.pubkey)?;
+            Ok(AccountMeta { pubkey, is_signer: meta.is_signer, is_writable: meta.is_writable })
+        })
+        .collect::<Result<_, ApiError>>()?;
+    let data = BASE64.decode(&ix.instruction_data).map_err(|e| {
+        ApiError::bad_request(format!("Invalid {}: {}", path("instruction_data".to_string()), e))
+    })?;
+
+    Ok(Instruction { program_id, accounts, data })
+}
+
+pub(crate) fn parse_instructions(field: &str, ixs: &[InstructionParam]) -> Result<Vec<Instruction>, ApiError> {
+    if ixs.is_empty() {
+        return Err(ApiError::bad_request(format!("{} must not be empty", field)));
+    }
+    ixs.iter()
+        .enumerate()
+        .map(|(i, ix)| parse_instruction(&format!("{}[{}]", field, i), ix))
+        .collect()
+}
+
+fn parse_lookup_tables(tables: &[LookupTableParam]) -> Result<Vec<AddressLookupTableAccount>, ApiError> {
+    tables
+        .iter()
+        .enumerate()
+        .map(|(i, table)| {
+            let field = format!("addressLookupTables[{}]", i);
+            if table.addresses.len() > LOOKUP_TABLE_MAX_ADDRESSES {
+                return Err(ApiError::bad_request(format!(
+                    "{}.addresses must contain at most {} entries, got {}",
+                    field,
+                    LOOKUP_TABLE_MAX_ADDRESSES,
+                    table.addresses.len()
+                )));
+            }
+            Ok(AddressLookupTableAccount {
+                key: parse_pubkey(&format!("{}.accountKey", field), &table.account_key)?,
+                addresses: parse_pubkey_list(&format!("{}.addresses", field), &table.addresses)?,
+            })
+        })
+        .collect()
+}
+
+/// The blockhash to compile with, plus the advance_nonce_account
+/// instruction that must come first when it is a durable nonce value.
+fn resolve_blockhash(req: &BuildTransactionRequest) -> Result<(Hash, Option<Instruction>), ApiError> {
+    let nonce_fields = [
+        ("nonceAccount", &req.nonce_account),
+        ("nonceAuthority", &req.nonce_authority),
+        ("nonce", &req.nonce),
+    ];
+    let given: Vec<&str> = nonce_fields.iter().filter(|(_, v)| v.is_some()).map(|(name, _)| *name).collect();
+
+    match (&req.recent_blockhash, given.is_empty()) {
+        (Some(_), false) => Err(ApiError::bad_request(format!(
+            "recentBlockhash cannot be combined with {}; in durable nonce mode the nonce value is the blockhash",
+            given.join(", ")
+        ))),
+        (Some(blockhash), true) => Ok((parse_blockhash("recentBlockhash", blockhash)?, None)),
+        (None, true) => Err(ApiError::bad_request(
+            "Missing recentBlockhash (or nonceAccount, nonceAuthority and nonce for a durable nonce)",
+        )),
+        (None, false) => {
+            let (Some(account), Some(authority), Some(nonce)) = (&req.nonce_account, &req.nonce_authority, &req.nonce)
+            else {
+                let missing: Vec<&str> =
+                    nonce_fields.iter().filter(|(_, v)| v.is_none()).map(|(name, _)| *name).collect();
+                return Err(ApiError::bad_request(format!(
+                    "Durable nonce mode needs nonceAccount, nonceAuthority and nonce; missing {}",
+                    missing.join(", ")
+                )));
+            };
+            let account = parse_pubkey("nonceAccount", account)?;
+            let authority = parse_pubkey("nonceAuthority", authority)?;
+            let nonce = parse_blockhash("nonce", nonce)?;
+            Ok((nonce, Some(system_instruction::advance_nonce_account(&account, &authority))))
+        }
+    }
+}
+
+/// Compiles the request into a message. Compilation always places the fee
+/// payer first, as a writable signer, whatever the instructions say about it.
+/// For v0, non-signer accounts that aren't invoked as programs are loaded
+/// from the given lookup tables where possible.
+fn compile_message(req: &BuildTransactionRequest) -> Result<VersionedMessage, ApiError> {
+    let mut ixs = parse_instructions("instructions", &req.instructions)?;
+    let fee_payer = parse_pubkey("feePayer", &req.fee_payer)?;
+    let (blockhash, advance_nonce) = resolve_blockhash(req)?;
+    if req.compute_unit_limit.is_some() || req.compute_unit_price_micro_lamports.is_some() {
+        // The runtime fails a transaction with two compute budget
+        // instructions of the same kind, so budgets come from one place only.
+        if let Some(i) = ixs.iter().position(|ix| ix.program_id == compute_budget::id()) {
+            return Err(ApiError::bad_request(format!(
+                "instructions[{}] is already a compute budget instruction; drop it or omit \
+                 computeUnitLimit and computeUnitPriceMicroLamports",
+                i
+            )));
+        }
+        let budget = compute_budget_instructions(
+            "computeUnitLimit",
+            req.compute_unit_limit,
+            req.compute_unit_price_micro_lamports,
+        )?;
+        ixs.splice(0..0, budget);
+    }
+    if let Some(advance_nonce) = advance_nonce {
+        // The runtime only recognises a durable nonce transaction by this
+        // being its first instruction.
+        ixs.insert(0, advance_nonce);
+    }
+    let version = match req.version.as_deref() {
+        Some(version) => parse_enum("version", version, TransactionVersionParam::ACCEPTED)?,
+        None => TransactionVersionParam::Legacy,
+    };
+
+    match version {
+        TransactionVersionParam::Legacy => {
+            if !req.address_lookup_tables.is_empty() {
+                return Err(ApiError::bad_request("addressLookupTables requires version \"v0\""));
+            }
+            Ok(VersionedMessage::Legacy(Message::new_with_blockhash(&ixs, Some(&fee_payer), &blockhash)))
+        }
+        TransactionVersionParam::V0 => {
+            let tables = parse_lookup_tables(&req.address_lookup_tables)?;
+            let message = v0::Message::try_compile(&fee_payer, &ixs, &tables, blockhash).map_err(|e| match e {
+                CompileError::AccountIndexOverflow => {
+                    ApiError::bad_request("instructions reference more than 256 accounts")
+                }
+                CompileError::AddressTableLookupIndexOverflow => ApiError::bad_request(
+                    "an address lookup table entry beyond index 255 was needed; lookup tables hold at most 256 addresses",
+                ),
+                CompileError::UnknownInstructionKey(key) => ApiError::bad_request(format!(
+                    "instruction account {} is neither a static key nor in addressLookupTables",
+                    key
+                )),
+            })?;
+            Ok(VersionedMessage::V0(message))
+        }
+    }
+}
+
+/// The signer pubkeys a message requires, in signature-slot order.
+pub(crate) fn required_signers(message: &VersionedMessage) -> &[Pubkey] {
+    let count = usize::from(message.header().num_required_signatures);
+    &message.static_account_keys()[..count]
+}
+
+/// "legacy" or the numeric version, as RPC getTransaction reports it.
+pub(crate) fn version_json(message: &VersionedMessage) -> serde_json::Value {
+    match message {
+        VersionedMessage::Legacy(_) => json!("legacy"),
+        VersionedMessage::V0(_) => json!(0),
+    }
+}
+
+/// Wire-format bytes, as accepted by RPC sendTransaction with base64 encoding.
+pub(crate) fn serialize_transaction(tx: &VersionedTransaction) -> Result<Vec<u8>, ApiError> {
+    bincode::serialize(tx).map_err(|e| ApiError::internal(format!("Failed to serialize transaction: {}", e)))
+}
+
+/// The compiled message with an empty slot for every required signature.
+fn unsigned_transaction(req: &BuildTransactionRequest) -> Result<VersionedTransaction, ApiError> {
+    let message = compile_message(req)?;
+    let signer_count = required_signers(&message).len();
+    Ok(VersionedTransaction {
+        signatures: vec![Signature::default(); signer_count],
+        message,
+    })
+}
+
+/// The /transaction/build response for an unsigned transaction.
+fn built_transaction_json(tx: &VersionedTransaction) -> Result<serde_json::Value, ApiError> {
+    let signers: Vec<String> = required_signers(&tx.message).iter().map(Pubkey::to_string).collect();
+
+    Ok(json!({
+        "transaction": BASE64.encode(serialize_transaction(tx)?),
+        "version": version_json(&tx.message),
+        "fee_payer": signers[0],
+        "recent_blockhash": tx.message.recent_blockhash().to_string(),
+        "durable_nonce": uses_durable_nonce(&tx.message),
+        "required_signers": signers
+    }))
+}
+
+pub(crate) async fn build_transaction(Json(req): Json<BuildTransactionRequest>) -> ApiResult {
+    success(built_transaction_json(&unsigned_transaction(&req)?)?)
+}
+
+/// Wraps instructions another endpoint has already built, giving the same
+/// legacy transaction and fields /transaction/build would for them.
+pub(crate) fn unsigned_transaction_json(
+    ixs: &[Instruction],
+    fee_payer: &Pubkey,
+    blockhash: Hash,
+) -> Result<serde_json::Value, ApiError> {
+    let message = VersionedMessage::Legacy(Message::new_with_blockhash(ixs, Some(fee_payer), &blockhash));
+    let tx = VersionedTransaction {
+        signatures: vec![Signature::default(); required_signers(&message).len()],
+        message,
+    };
+    built_transaction_json(&tx)
+}
+
+/// Decodes a wire-format transaction (legacy or versioned) given as base64 or
+/// base58. Decoding is strict, as the runtime's is: trailing bytes and
+/// internally inconsistent messages are rejected here rather than panicking
+/// later on an out-of-range account index.
+pub(crate) fn parse_transaction(field: &str, value: &str) -> Result<VersionedTransaction, ApiError> {
+    let deserialize = |bytes: Vec<u8>| {
+        bincode::options()
+            .with_fixint_encoding()
+            .reject_trailing_bytes()
+            .deserialize::<VersionedTransaction>(&bytes)
+            .map_err(|e| e.to_string())
+    };
+    let tx = match (BASE64.decode(value), bs58::decode(value).into_vec()) {
+        (Ok(bytes), Ok(alternative)) => deserialize(bytes).or_else(|e| deserialize(alternative).map_err(|_| e)),
+        (Ok(bytes), Err(_)) | (Err(_), Ok(bytes)) => deserialize(bytes),
+        (Err(_), Err(_)) => Err("expected base64 or base58".to_string()),
+    }
+    .map_err(|e| ApiError::bad_request(format!("Invalid {}: {}", field, e)))?;
+
+    tx.sanitize()
+        .map_err(|e| ApiError::bad_request(format!("Invalid {}: {}", field, e)))?;
+    if tx.signatures.len() != usize::from(tx.message.header().num_required_signatures) {
+        return Err(ApiError::bad_request(format!(
+            "Invalid {}: has {} signatures but its message requires {}",
+            field,
+            tx.signatures.len(),
+            tx.message.header().num_required_signatures
+        )));
+    }
+    Ok(tx)
+}
+
+/// Adds `keypair`'s signature in its slot, leaving every other slot alone.
+/// This is Transaction::try_partial_sign without the blockhash argument, and
+/// it works for v0 messages too.
+pub(crate) fn partial_sign(tx: &mut VersionedTransaction, keypair: &Keypair, field: &str) -> Result<(), ApiError> {
+    let pubkey = keypair.pubkey();
+    let slot = required_signers(&tx.message)
+        .iter()
+        .position(|signer| *signer == pubkey)
+        .ok_or_else(|| {
+            ApiError::bad_request(format!("{} ({}) is not a required signer of this transaction", field, pubkey))
+        })?;
+    tx.signatures[slot] = keypair.sign_message(&tx.message.serialize());
+    Ok(())
+}
+
+/// Places a signature produced elsewhere into `pubkey`'s slot, but only once
+/// it verifies over the message; a bad signature would just get the
+/// transaction dropped by the cluster.
+fn attach_signature(
+    tx: &mut VersionedTransaction,
+    pubkey: &Pubkey,
+    signature: Signature,
+    pubkey_field: &str,
+    signature_field: &str,
+) -> Result<(), ApiError> {
+    let slot = required_signers(&tx.message)
+        .iter()
+        .position(|signer| signer == pubkey)
+        .ok_or_else(|| {
+            ApiError::bad_request(format!("{} ({}) is not a required signer of this transaction", pubkey_field, pubkey))
+        })?;
+    if !signature.verify(pubkey.as_ref(), &tx.message.serialize()) {
+        return Err(ApiError::bad_request(format!(
+            "{} does not verify for {} over this transaction's message",
+            signature_field, pubkey
+        )));
+    }
+    tx.signatures[slot] = signature;
+    Ok(())
+}
+
+/// The state of one signature slot.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub(crate) enum SignatureStatus {
+    /// Still the all-zero placeholder.
+    Missing,
+    /// Verifies against the message bytes and the slot's signer.
+    Valid,
+    /// Bytes are set but don't verify: stale after a message change, or
+    /// placed in the wrong slot.
+    Invalid,
+}
+
+impl SignatureStatus {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Missing => "missing",
+            Self::Valid => "valid",
+            Self::Invalid => "invalid",
+        }
+    }
+}
+
+/// Checks every signature slot against its signer, purely offline.
+pub(crate) fn signature_statuses(tx: &VersionedTransaction) -> Vec<(Pubkey, SignatureStatus)> {
+    let message_bytes = tx.message.serialize();
+    required_signers(&tx.message)
+        .iter()
+        .zip(&tx.signatures)
+        .map(|(signer, signature)| {
+            let status = if *signature == Signature::default() {
+                SignatureStatus::Missing
+            } else if signature.verify(signer.as_ref(), &message_bytes) {
+                SignatureStatus::Valid
+            } else {
+                SignatureStatus::Invalid
+            };
+            (*signer, status)
+        })
+        .collect()
+}
+
+/// Required signers whose slot still holds the default (all-zero) signature.
+pub(crate) fn missing_signers(tx: &VersionedTransaction) -> Vec<String> {
+    required_signers(&tx.message)
+        .iter()
+        .zip(&tx.signatures)
+        .filter(|(_, signature)| **signature == Signature::default())
+        .map(|(signer, _)| signer.to_string())
+        .collect()
+}
+
+/// Transaction ids are conventionally base58, so unlike /message/sign that
+/// is the default `signature` encoding here.
+fn parse_transaction_signature_encoding(encoding: Option<&str>) -> Result<SignatureEncodingParam, ApiError> {
+    match encoding {
+        Some(encoding) => parse_enum("signatureEncoding", encoding, SignatureEncodingParam::ACCEPTED),
+        None => Ok(SignatureEncodingParam::Base58),
+    }
+}
+
+/// The fee payer's signature, which is the transaction id, in the shape
+/// /message/sign uses. Every field is null until the fee payer has signed.
+fn transaction_id_json(tx: &VersionedTransaction, encoding: SignatureEncodingParam) -> serde_json::Value {
+    match tx.signatures[0] {
+        signature if signature == Signature::default() => {
+            json!({ "signature": null, "signature_base58": null, "signature_base64": null })
+        }
+        signature => signature_json(&signature, encoding),
+    }
+}
+
+/// Signs with each secret in turn. Signers may be a subset of those the
+/// transaction needs; the response lists who still has to sign.
+pub(crate) async fn sign_transaction(Json(req): Json<SignTransactionRequest>) -> ApiResult {
+    let mut tx = parse_transaction("transaction", &req.transaction)?;
+    if req.secrets.is_empty() {
+        return Err(ApiError::bad_request("secrets must not be empty"));
+    }
+    let signature_encoding = parse_transaction_signature_encoding(req.signature_encoding.as_deref())?;
+    for (i, secret) in req.secrets.iter().enumerate() {
+        let field = format!("secrets[{}]", i);
+        let bytes = decode_secret(&field, secret, req.secret_encoding.as_deref())?;
+        let (keypair, _) = keypair_from_secret(&field, &bytes)?;
+        partial_sign(&mut tx, &keypair, &field)?;
+    }
+
+    let missing = missing_signers(&tx);
+    let mut data = transaction_id_json(&tx, signature_encoding);
+    data["transaction"] = json!(BASE64.encode(serialize_transaction(&tx)?));
+    data["complete"] = json!(missing.is_empty());
+    data["missing_signers"] = json!(missing);
+    success(data)
+}
+
+/// Adds one signature to a transaction that may already carry others, as
+/// each party in a multisig flow does in turn. Signing is deterministic, so
+/// repeating a signer rewrites the same bytes.
+pub(crate) async fn partial_sign_transaction(Json(req): Json<PartialSignTransactionRequest>) -> ApiResult {
+    let mut tx = parse_transaction("transaction", &req.transaction)?;
+    let bytes = decode_secret("secret", &req.secret, req.secret_encoding.as_deref())?;
+    let (keypair, _) = keypair_from_secret("secret", &bytes)?;
+    partial_sign(&mut tx, &keypair, "secret")?;
+
+    let missing = missing_signers(&tx);
+    success(json!({
+        "transaction": BASE64.encode(serialize_transaction(&tx)?),
+        "signer": keypair.pubkey().to_string(),
+        "complete": missing.is_empty(),
+        "missing_signers": missing
+    }))
+}
+
+/// Attaches a raw 64-byte signature from an HSM or other external signer.
+/// Any signature already in the slot is replaced.
+pub(crate) async fn attach_signature_endpoint(Json(req): Json<AttachSignatureRequest>) -> ApiResult {
+    let mut tx = parse_transaction("transaction", &req.transaction)?;
+    let pubkey = parse_pubkey("pubkey", &req.pubkey)?;
+    let signature = decode_signature("signature", &req.signature)?;
+    attach_signature(&mut tx, &pubkey, signature, "pubkey", "signature")?;
+
+    let missing = missing_signers(&tx);
+    success(json!({
+        "transaction": BASE64.encode(serialize_transaction(&tx)?),
+        "signer": pubkey.to_string(),
+        "complete": missing.is_empty(),
+        "missing_signers": missing
+    }))
+}
+
+/// Describes the account at `index` in the message's combined key list.
+/// Indexes past the static keys come from address lookup tables, which are
+/// reported by table and position since resolving them needs chain state.
+fn account_json(message: &VersionedMessage, index: usize) -> serde_json::Value {
+    let static_keys = message.static_account_keys();
+    let mut data = match static_keys.get(index) {
+        Some(pubkey) => json!({ "pubkey": pubkey.to_string() }),
+        None => {
+            // Loaded addresses are all writable lookups, table by table,
+            // followed by all read-only lookups.
+            let lookups = message.address_table_lookups().unwrap_or_default();
+            let writable = lookups
+                .iter()
+                .flat_map(|lookup| lookup.writable_indexes.iter().map(move |i| (lookup.account_key, *i)));
+            let readonly = lookups
+                .iter()
+                .flat_map(|lookup| lookup.readonly_indexes.iter().map(move |i| (lookup.account_key, *i)));
+            match writable.chain(readonly).nth(index - static_keys.len()) {
+                Some((table, table_index)) => json!({
+                    "pubkey": null,
+                    "lookup_table": table.to_string(),
+                    "lookup_index": table_index
+                }),
+                None => json!({ "pubkey": null }),
+            }
+        }
+    };
+    data["is_signer"] = json!(message.is_signer(index));
+    data["is_writable"] = json!(message.is_maybe_writable(index));
+    data
+}
+
+pub(crate) async fn decode_transaction(Json(req): Json<SerializedTransactionRequest>) -> ApiResult {
+    let tx = parse_transaction("transaction", &req.transaction)?;
+    let message = &tx.message;
+    let static_keys = message.static_account_keys();
+
+    let instructions: Vec<_> = message
+        .instructions()
+        .iter()
+        .map(|ix| {
+            let program_id = static_keys[usize::from(ix.program_id_index)];
+            let keys: Vec<_> = ix.accounts.iter().map(|i| static_keys.get(usize::from(*i)).copied()).collect();
+            json!({
+                "program_id": program_id.to_string(),
+                "accounts": ix.accounts.iter().map(|i| account_json(message, usize::from(*i))).collect::<Vec<_>>(),
+                "instruction_data": BASE64.encode(&ix.data),
+                "parsed": parse_known_instruction(&program_id, &keys, &ix.data)
+            })
+        })
+        .collect();
+    let signatures: Vec<_> = required_signers(message)
+        .iter()
+        .zip(&tx.signatures)
+        .map(|(signer, signature)| {
+            json!({
+                "pubkey": signer.to_string(),
+                "signature": (*signature != Signature::default()).then(|| signature.to_string())
+            })
+        })
+        .collect();
+    let lookups: Vec<_> = message
+        .address_table_lookups()
+        .unwrap_or_default()
+        .iter()
+        .map(|lookup| {
+            json!({
+                "account_key": lookup.account_key.to_string(),
+                "writable_indexes": lookup.writable_indexes,
+                "readonly_indexes": lookup.readonly_indexes
+            })
+        })
+        .collect();
+
+    success(json!({
+        "version": version_json(message),
+        "fee_payer": static_keys[0].to_string(),
+        "recent_blockhash": message.recent_blockhash().to_string(),
+        "account_keys": static_keys.iter().map(Pubkey::to_string).collect::<Vec<_>>(),
+        "instructions": instructions,
+        "signatures": signatures,
+        "missing_signers": missing_signers(&tx),
+        "address_table_lookups": lookups
+    }))
+}
+
+/// Bytes taken by a compact-u16 length prefix.
+fn short_vec_len(len: usize) -> usize {
+    match len {
+        0..=0x7f => 1,
+        0x80..=0x3fff => 2,
+        _ => 3,
+    }
+}
+
+/// Accepts either `{ "transaction": ... }` or the /transaction/build inputs,
+/// and reports the wire size against the packet limit with a per-section
+/// breakdown that sums to the total.
+pub(crate) async fn transaction_size(Json(body): Json<serde_json::Value>) -> ApiResult {
+    let tx = match body.get("transaction") {
+        Some(serde_json::Value::String(transaction)) => parse_transaction("transaction", transaction)?,
+        Some(_) => return Err(ApiError::bad_request("transaction must be a string")),
+        None => {
+            let req: BuildTransactionRequest = serde_json::from_value(body)
+                .map_err(|e| ApiError::bad_request(format!("Invalid request: {}", e)))?;
+            unsigned_transaction(&req)?
+        }
+    };
+    let size = serialize_transaction(&tx)?.len();
+
+    let message = &tx.message;
+    let keys = message.static_account_keys();
+    let instructions = message.instructions();
+    let lookups = message.address_table_lookups().unwrap_or_default();
+    let version_prefix = usize::from(matches!(message, VersionedMessage::V0(_)));
+    let instruction_data: usize = instructions.iter().map(|ix| ix.data.len()).sum();
+    let instruction_bytes = short_vec_len(instructions.len())
+        + instructions
+            .iter()
+            .map(|ix| 1 + short_vec_len(ix.accounts.len()) + ix.accounts.len() + short_vec_len(ix.data.len()) + ix.data.len())
+            .sum::<usize>();
+    let lookup_bytes = match message {
+        VersionedMessage::Legacy(_) => 0,
+        VersionedMessage::V0(_) => {
+            short_vec_len(lookups.len())
+                + lookups
+                    .iter()
+                    .map(|lookup| {
+                        32 + short_vec_len(lookup.writable_indexes.len())
+                            + lookup.writable_indexes.len()
+                            + short_vec_len(lookup.readonly_indexes.len())
+                            + lookup.readonly_indexes.len()
+                    })
+                    .sum::<usize>()
+        }
+    };
+
+    success(json!({
+        "size": size,
+        "limit": PACKET_DATA_SIZE,
+        "fits": size <= PACKET_DATA_SIZE,
+        "version": version_json(message),
+        "breakdown": {
+            "signatures": short_vec_len(tx.signatures.len()) + 64 * tx.signatures.len(),
+            "message_header": version_prefix + 3,
+            "account_keys": short_vec_len(keys.len()) + 32 * keys.len(),
+            "recent_blockhash": 32,
+            "instructions": instruction_bytes,
+            "address_table_lookups": lookup_bytes
+        },
+        "instruction_data": instruction_data
+    }))
+}
+
+/// Lists required signers in slot order with the state of each signature,
+/// so a coordinator can route the transaction to whoever signs next.
+pub(crate) async fn transaction_signers(Json(req): Json<SerializedTransactionRequest>) -> ApiResult {
+    let tx = parse_transaction("transaction", &req.transaction)?;
+    let statuses = signature_statuses(&tx);
+
+    let signers: Vec<_> = statuses
+        .iter()
+        .enumerate()
+        .map(|(slot, (pubkey, status))| {
+            json!({
+                "pubkey": pubkey.to_string(),
+                "slot": slot,
+                "status": status.as_str(),
+                "signed": *status == SignatureStatus::Valid
+            })
+        })
+        .collect();
+    let count = |wanted: SignatureStatus| statuses.iter().filter(|(_, status)| *status == wanted).count();
+
+    success(json!({
+        "signers": signers,
+        "valid": count(SignatureStatus::Valid),
+        "missing": count(SignatureStatus::Missing),
+        "invalid": count(SignatureStatus::Invalid),
+        "complete": count(SignatureStatus::Valid) == statuses.len()
+    }))
+}
+
+/// Verifies every present signature against the serialized message and its
+/// signer's key, purely offline. all_valid covers the signatures present;
+/// empty slots are listed in missing_signers rather than counted as failures.
+pub(crate) async fn verify_signatures(Json(req): Json<SerializedTransactionRequest>) -> ApiResult {
+    let tx = parse_transaction("transaction", &req.transaction)?;
+    let statuses = signature_statuses(&tx);
+
+    let results: Vec<_> = statuses
+        .iter()
+        .zip(&tx.signatures)
+        .enumerate()
+        .map(|(slot, ((pubkey, status), signature))| {
+            let (signature, valid) = match status {
+                SignatureStatus::Missing => (json!(null), json!(null)),
+                status => (json!(signature.to_string()), json!(*status == SignatureStatus::Valid)),
+            };
+            json!({
+                "pubkey": pubkey.to_string(),
+                "slot": slot,
+                "signature": signature,
+                "valid": valid
+            })
+        })
+        .collect();
+
+    success(json!({
+        "signatures": results,
+        "all_valid": statuses.iter().all(|(_, status)| *status != SignatureStatus::Invalid),
+        "missing_signers": missing_signers(&tx)
+    }))
+}
+
+/// Whether the message follows the durable nonce rules: its first
+/// instruction is the system program's AdvanceNonceAccount, and its
+/// recent_blockhash is the nonce value rather than a real blockhash.
+pub(crate) fn uses_durable_nonce(message: &VersionedMessage) -> bool {
+    message.instructions().first().is_some_and(|ix| {
+        message.static_account_keys().get(usize::from(ix.program_id_index)) == Some(&system_program::id())
+            && matches!(bincode::deserialize(&ix.data), Ok(SystemInstruction::AdvanceNonceAccount))
+    })
+}
+
+/// Swaps in a fresh blockhash. Every signature covers the blockhash, so any
+/// that no longer verify are cleared and reported for re-signing.
+pub(crate) async fn update_blockhash(Json(req): Json<UpdateBlockhashRequest>) -> ApiResult {
+    let mut tx = parse_transaction("transaction", &req.transaction)?;
+    let blockhash = parse_blockhash("recentBlockhash", &req.recent_blockhash)?;
+    let signature_encoding = parse_transaction_signature_encoding(req.signature_encoding.as_deref())?;
+    if uses_durable_nonce(&tx.message) {
+        return Err(ApiError::bad_request(
+            "transaction uses a durable nonce (its first instruction advances a nonce account), so its \
+             recent_blockhash must stay the nonce value; rebuild it with the current nonce instead",
+        ));
+    }
+
+    let previous = *tx.message.recent_blockhash();
+    tx.message.set_recent_blockhash(blockhash);
+    let mut dropped = Vec::new();
+    for (slot, (signer, status)) in signature_statuses(&tx).into_iter().enumerate() {
+        if status == SignatureStatus::Invalid {
+            tx.signatures[slot] = Signature::default();
+            dropped.push(signer.to_string());
+        }
+    }
+
+    let mut data = transaction_id_json(&tx, signature_encoding);
+    data["transaction"] = json!(BASE64.encode(serialize_transaction(&tx)?));
+    data["previous_blockhash"] = json!(previous.to_string());
+    data["recent_blockhash"] = json!(blockhash.to_string());
+    data["dropped_signatures"] = json!(dropped);
+    data["missing_signers"] = json!(missing_signers(&tx));
+    success(data)
+}
+
+/// The bytes a hardware wallet signs: the serialized message alone, with
+/// the signer order that /transaction/assemble expects.
+pub(crate) async fn compile_message_endpoint(Json(req): Json<BuildTransactionRequest>) -> ApiResult {
+    let message = compile_message(&req)?;
+    let signers: Vec<String> = required_signers(&message).iter().map(Pubkey::to_string).collect();
+
+    success(json!({
+        "message": BASE64.encode(message.serialize()),
+        "version": version_json(&message),
+        "signers": signers
+    }))
+}
+
+fn parse_message(field: &str, value: &str) -> Result<VersionedMessage, ApiError> {
+    let bytes = BASE64
+        .decode(value)
+        .map_err(|e| ApiError::bad_request(format!("Invalid {}: {}", field, e)))?;
+    let message: VersionedMessage = bincode::options()
+        .with_fixint_encoding()
+        .reject_trailing_bytes()
+        .deserialize(&bytes)
+        .map_err(|e| ApiError::bad_request(format!("Invalid {}: {}", field, e)))?;
+    message
+        .sanitize()
+        .map_err(|e| ApiError::bad_request(format!("Invalid {}: {}", field, e)))?;
+    Ok(message)
+}
+
+/// Pairs compiled message bytes with externally produced signatures. Each
+/// signature must verify before it is placed; slots nobody supplied stay
+/// empty and are listed as missing.
+pub(crate) async fn assemble_transaction(Json(req): Json<AssembleTransactionRequest>) -> ApiResult {
+    let message = parse_message("message", &req.message)?;
+    let signature_encoding = parse_transaction_signature_encoding(req.signature_encoding.as_deref())?;
+    let mut tx = VersionedTransaction {
+        signatures: vec![Signature::default(); required_signers(&message).len()],
+        message,
+    };
+
+    let mut seen = Vec::new();
+    for (i, entry) in req.signatures.iter().enumerate() {
+        let pubkey_field = format!("signatures[{}].pubkey", i);
+        let signature_field = format!("signatures[{}].signature", i);
+        let pubkey = parse_pubkey(&pubkey_field, &entry.pubkey)?;
+        if seen.contains(&pubkey) {
+            return Err(ApiError::bad_request(format!("signatures[{}] repeats a signature for {}", i, pubkey)));
+        }
+        let signature = decode_signature(&signature_field, &entry.signature)?;
+        attach_signature(&mut tx, &pubkey, signature, &pubkey_field, &signature_field)?;
+        seen.push(pubkey);
+    }
+
+    let missing = missing_signers(&tx);
+    let mut data = transaction_id_json(&tx, signature_encoding);
+    data["transaction"] = json!(BASE64.encode(serialize_transaction(&tx)?));
+    data["complete"] = json!(missing.is_empty());
+    data["missing_signers"] = json!(missing);
+    success(data)
+}